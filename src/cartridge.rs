@@ -0,0 +1,253 @@
+use crate::cartridge::cart_ram::{CartRam, CartRamMut};
+use crate::cartridge::clock_source::{ClockSource, SystemClock};
+use crate::cartridge::header::{CartridgeHeader, CartridgeType, MapperType};
+use crate::cartridge::mbc::MBC;
+use crate::cartridge::mbc::mbc1::MBC1;
+use crate::cartridge::mbc::mbc3::MBC3;
+use crate::cartridge::mbc::mbc5::MBC5;
+use crate::cartridge::mbc::rtc::RTC;
+use crate::cartridge::mbc::snapshot::MbcSnapshot;
+use crate::cartridge::patch::{PatchError, apply_patch};
+use crate::helpers::crc32::crc32;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+use std::fmt::{Display, Formatter};
+
+pub mod cart_ram;
+pub mod checksum;
+pub mod clock_source;
+pub mod header;
+pub mod mbc;
+pub mod patch;
+pub mod rom_builder;
+
+pub const ROM_START: u16 = 0x0000;
+pub const ROM_END: u16 = 0x7FFF;
+pub const EXTERNAL_RAM_START: u16 = 0xA000;
+pub const EXTERNAL_RAM_END: u16 = 0xBFFF;
+/// Trimmed dumps drop the trailing unprogrammed bytes, which read as this value
+const ROM_PADDING: u8 = 0xFF;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CartridgeError {
+    MissingHeader,
+    UnknownCartridgeType(u8),
+    UnsupportedMapper(MapperType),
+    UnknownRomSize(u8),
+    UnknownRamSize(u8),
+    /// The ROM is larger than the size given in the header, smaller ROMs are padded
+    RomSizeMismatch { expected: usize, actual: usize },
+    SaveSizeMismatch { expected: usize, actual: usize },
+    /// The patch couldn't be applied to the ROM
+    Patch(PatchError),
+}
+
+impl Display for CartridgeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader => write!(f, "ROM is too small to contain a cartridge header"),
+            Self::UnknownCartridgeType(code) => write!(f, "unknown cartridge type {code:02X}"),
+            Self::UnsupportedMapper(mapper) => write!(f, "unsupported mapper {mapper:?}"),
+            Self::UnknownRomSize(code) => write!(f, "unknown ROM size code {code:02X}"),
+            Self::UnknownRamSize(code) => write!(f, "unknown RAM size code {code:02X}"),
+            Self::RomSizeMismatch { expected, actual } => {
+                write!(f, "ROM size mismatch: expected {expected} bytes, got {actual}")
+            }
+            Self::SaveSizeMismatch { expected, actual } => {
+                write!(f, "save size mismatch: expected {expected} bytes, got {actual}")
+            }
+            Self::Patch(error) => write!(f, "failed to apply patch: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {}
+
+/// A cartridge with its ROM, external RAM and memory bank controller.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cartridge {
+    header: CartridgeHeader,
+    cartridge_type: CartridgeType,
+    rom: Vec<u8>,
+    /// Identifies the ROM in save states, computed once since ROMs can be several megabytes large
+    rom_crc32: u32,
+    /// Bytes appended to a trimmed ROM to reach the size given in the header
+    rom_padding: usize,
+    ram: Vec<u8>,
+    mbc: MBC,
+}
+
+impl Cartridge {
+    /// Parses the header and sets up the mapper, the RTC of MBC3 cartridges uses the system time.
+    /// ROMs smaller than the size in the header are trimmed dumps, they are padded to that size.
+    pub fn load(rom: Vec<u8>) -> Result<Self, CartridgeError> {
+        Self::load_with_clock_source(rom, Box::new(SystemClock))
+    }
+
+    /// Applies an IPS or BPS patch to the ROM before loading it like [`Cartridge::load`]
+    pub fn load_patched(rom: &[u8], patch: &[u8]) -> Result<Self, CartridgeError> {
+        Self::load(apply_patch(rom, patch).map_err(CartridgeError::Patch)?)
+    }
+
+    /// Like [`Cartridge::load`], the given clock source is used if the cartridge has an RTC
+    pub fn load_with_clock_source(mut rom: Vec<u8>, clock: Box<dyn ClockSource>) -> Result<Self, CartridgeError> {
+        let header = CartridgeHeader::parse(&rom).ok_or(CartridgeError::MissingHeader)?;
+        let cartridge_type = header
+            .get_cartridge_type()
+            .ok_or(CartridgeError::UnknownCartridgeType(header.get_cartridge_type_code()))?;
+        let rom_size = header
+            .get_rom_size()
+            .ok_or(CartridgeError::UnknownRomSize(header.get_rom_size_code()))?;
+        let ram_size = header
+            .get_ram_size()
+            .ok_or(CartridgeError::UnknownRamSize(header.get_ram_size_code()))?;
+
+        if rom.len() > rom_size {
+            return Err(CartridgeError::RomSizeMismatch {
+                expected: rom_size,
+                actual: rom.len(),
+            });
+        }
+        // Banks are masked by the size in the header, which is always a power of two
+        let rom_padding = rom_size - rom.len();
+        rom.resize(rom_size, ROM_PADDING);
+
+        let mbc = match cartridge_type.get_mapper() {
+            MapperType::RomOnly => MBC::RomOnly,
+            MapperType::MBC1 => MBC::MBC1(MBC1::new()),
+            MapperType::MBC3 => MBC::MBC3(MBC3::new(cartridge_type.has_timer().then(|| RTC::new(clock)))),
+            MapperType::MBC5 => MBC::MBC5(MBC5::new(cartridge_type.has_rumble())),
+            mapper => return Err(CartridgeError::UnsupportedMapper(mapper)),
+        };
+
+        let ram_size = if cartridge_type.has_ram() { ram_size } else { 0 };
+
+        Ok(Self {
+            header,
+            cartridge_type,
+            rom_crc32: crc32(&rom),
+            rom_padding,
+            rom,
+            ram: vec![0xFF; ram_size],
+            mbc,
+        })
+    }
+
+    pub fn get_header(&self) -> &CartridgeHeader {
+        &self.header
+    }
+
+    pub fn get_cartridge_type(&self) -> CartridgeType {
+        self.cartridge_type
+    }
+
+    pub fn get_mbc(&self) -> &MBC {
+        &self.mbc
+    }
+
+    pub fn get_mbc_snapshot(&self) -> MbcSnapshot {
+        self.mbc.snapshot(self.rom.len(), self.ram.len())
+    }
+
+    /// The ROM bank currently mapped to the address in 0x0000-0x7FFF
+    pub fn get_rom_bank(&self, address: u16) -> usize {
+        self.mbc.get_rom_bank(self.rom.len(), address)
+    }
+
+    pub fn get_rom(&self) -> &[u8] {
+        &self.rom
+    }
+
+    /// CRC-32 of the complete ROM, including the padding of a trimmed ROM
+    pub fn get_rom_crc32(&self) -> u32 {
+        self.rom_crc32
+    }
+
+    /// How many bytes were appended to a trimmed ROM, 0 if the ROM had the size given in the header
+    pub fn get_rom_padding(&self) -> usize {
+        self.rom_padding
+    }
+
+    pub fn is_rom_padded(&self) -> bool {
+        self.rom_padding > 0
+    }
+
+    /// The complete external RAM, in the layout of a `.sav` file
+    pub fn get_ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    /// Bank-aware view of the external RAM, for save editors and tests
+    pub fn get_cart_ram(&self) -> CartRam<'_> {
+        CartRam::new(&self.ram)
+    }
+
+    /// Allows modifying the external RAM directly, without enabling it or switching banks first
+    pub fn get_cart_ram_mut(&mut self) -> CartRamMut<'_> {
+        CartRamMut::new(&mut self.ram)
+    }
+
+    /// Whether the external RAM keeps its contents without power and should be persisted
+    pub fn has_battery(&self) -> bool {
+        self.cartridge_type.has_battery()
+    }
+
+    /// Restores the external RAM from the contents of a `.sav` file
+    pub fn load_ram(&mut self, data: &[u8]) -> Result<(), CartridgeError> {
+        if data.len() != self.ram.len() {
+            return Err(CartridgeError::SaveSizeMismatch {
+                expected: self.ram.len(),
+                actual: data.len(),
+            });
+        }
+        self.ram.copy_from_slice(data);
+        Ok(())
+    }
+
+    /// The real time clock of MBC3 cartridges that have one
+    pub fn get_rtc(&self) -> Option<&RTC> {
+        match &self.mbc {
+            MBC::MBC3(mbc) => mbc.get_rtc(),
+            _ => None,
+        }
+    }
+
+    pub fn get_rtc_mut(&mut self) -> Option<&mut RTC> {
+        match &mut self.mbc {
+            MBC::MBC3(mbc) => mbc.get_rtc_mut(),
+            _ => None,
+        }
+    }
+
+    /// Reads 0x0000-0x7FFF and 0xA000-0xBFFF without side effects
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            ROM_START..=ROM_END => self.mbc.read_rom(&self.rom, address),
+            _ => self.mbc.read_ram(&self.ram, address),
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            ROM_START..=ROM_END => self.mbc.write_rom(address, value),
+            _ => self.mbc.write_ram(&mut self.ram, address, value),
+        }
+    }
+
+    /// Advances the cartridge hardware by one M-cycle
+    pub fn tick(&mut self) {
+        self.mbc.tick();
+    }
+}
+
+/// The ROM isn't part of the state, it has to be loaded into the same cartridge
+impl SaveState for Cartridge {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.ram);
+        self.mbc.save_state(writer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        reader.read_into(&mut self.ram)?;
+        self.mbc.load_state(reader)
+    }
+}
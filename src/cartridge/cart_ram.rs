@@ -0,0 +1,66 @@
+use crate::cartridge::mbc::RAM_BANK_SIZE;
+
+/// Read-only view of the external RAM split into its 8 KiB banks, independent of what the CPU currently sees.
+/// RAM smaller than a bank has a single, shorter bank.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CartRam<'a> {
+    ram: &'a [u8],
+}
+
+impl<'a> CartRam<'a> {
+    pub(super) fn new(ram: &'a [u8]) -> Self {
+        Self { ram }
+    }
+
+    pub fn get_bank_count(&self) -> usize {
+        self.ram.len().div_ceil(RAM_BANK_SIZE)
+    }
+
+    pub fn get_bank(&self, bank: usize) -> Option<&'a [u8]> {
+        self.ram.chunks(RAM_BANK_SIZE).nth(bank)
+    }
+
+    /// Reads the byte at the offset within the bank, None if either is out of range
+    pub fn read(&self, bank: usize, offset: usize) -> Option<u8> {
+        self.get_bank(bank)?.get(offset).copied()
+    }
+
+    /// All banks in order, in the layout of a `.sav` file
+    pub fn as_slice(&self) -> &'a [u8] {
+        self.ram
+    }
+}
+
+/// Writable view of the external RAM, see [`CartRam`].
+/// Writes bypass the RAM enable register and the mapper.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CartRamMut<'a> {
+    ram: &'a mut [u8],
+}
+
+impl<'a> CartRamMut<'a> {
+    pub(super) fn new(ram: &'a mut [u8]) -> Self {
+        Self { ram }
+    }
+
+    pub fn view(&self) -> CartRam<'_> {
+        CartRam::new(self.ram)
+    }
+
+    pub fn get_bank_mut(&mut self, bank: usize) -> Option<&mut [u8]> {
+        self.ram.chunks_mut(RAM_BANK_SIZE).nth(bank)
+    }
+
+    /// Writes the byte at the offset within the bank, returns false if either is out of range
+    pub fn write(&mut self, bank: usize, offset: usize, value: u8) -> bool {
+        let Some(byte) = self.get_bank_mut(bank).and_then(|bank| bank.get_mut(offset)) else {
+            return false;
+        };
+        *byte = value;
+        true
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.ram
+    }
+}
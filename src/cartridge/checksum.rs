@@ -0,0 +1,75 @@
+use crate::cartridge::header::HEADER_END_ADDRESS;
+
+// Checksums according to: https://gbdev.io/pandocs/The_Cartridge_Header.html#014d--header-checksum
+const HEADER_CHECKSUM_START: usize = 0x0134;
+const HEADER_CHECKSUM_END: usize = 0x014C;
+pub const HEADER_CHECKSUM_ADDRESS: usize = 0x014D;
+pub const GLOBAL_CHECKSUM_ADDRESS: usize = 0x014E;
+
+/// Computes the header checksum over 0x0134-0x014C, which the boot ROM verifies before handing off control.
+/// Returns None if the image is too small to contain a header.
+pub fn compute_header_checksum(rom: &[u8]) -> Option<u8> {
+    let bytes = rom.get(HEADER_CHECKSUM_START..=HEADER_CHECKSUM_END)?;
+    Some(
+        bytes
+            .iter()
+            .fold(0u8, |checksum, &byte| checksum.wrapping_sub(byte).wrapping_sub(1)),
+    )
+}
+
+/// Computes the global checksum, the 16-bit sum of all bytes except the two checksum bytes themselves.
+/// Not verified by real hardware.
+/// Returns None if the image is too small to contain a header.
+pub fn compute_global_checksum(rom: &[u8]) -> Option<u16> {
+    if rom.len() < HEADER_END_ADDRESS {
+        return None;
+    }
+
+    let checksum = rom
+        .iter()
+        .enumerate()
+        .filter(|(address, _)| *address != GLOBAL_CHECKSUM_ADDRESS && *address != GLOBAL_CHECKSUM_ADDRESS + 1)
+        .fold(0u16, |checksum, (_, &byte)| checksum.wrapping_add(byte as u16));
+    Some(checksum)
+}
+
+pub fn read_header_checksum(rom: &[u8]) -> Option<u8> {
+    rom.get(HEADER_CHECKSUM_ADDRESS).copied()
+}
+
+/// The global checksum is stored big endian
+pub fn read_global_checksum(rom: &[u8]) -> Option<u16> {
+    let bytes = rom.get(GLOBAL_CHECKSUM_ADDRESS..GLOBAL_CHECKSUM_ADDRESS + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+pub fn verify_header_checksum(rom: &[u8]) -> bool {
+    compute_header_checksum(rom).is_some_and(|checksum| read_header_checksum(rom) == Some(checksum))
+}
+
+pub fn verify_global_checksum(rom: &[u8]) -> bool {
+    compute_global_checksum(rom).is_some_and(|checksum| read_global_checksum(rom) == Some(checksum))
+}
+
+/// Writes the correct header and global checksum into the given image.
+/// Returns false without modifying the image if it is too small to contain a header.
+pub fn fix_checksums_in_place(rom: &mut [u8]) -> bool {
+    let Some(header_checksum) = compute_header_checksum(rom) else {
+        return false;
+    };
+    rom[HEADER_CHECKSUM_ADDRESS] = header_checksum;
+
+    // The header checksum byte is part of the global checksum, so it has to be fixed first
+    let Some(global_checksum) = compute_global_checksum(rom) else {
+        return false;
+    };
+    rom[GLOBAL_CHECKSUM_ADDRESS..GLOBAL_CHECKSUM_ADDRESS + 2].copy_from_slice(&global_checksum.to_be_bytes());
+    true
+}
+
+/// Returns a copy of the given image with corrected header and global checksums.
+/// Returns None if the image is too small to contain a header.
+pub fn fix_checksums(rom: &[u8]) -> Option<Vec<u8>> {
+    let mut fixed = rom.to_vec();
+    fix_checksums_in_place(&mut fixed).then_some(fixed)
+}
@@ -0,0 +1,122 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// T-cycles per second of the DMG clock
+const CYCLES_PER_SECOND: u64 = 4_194_304;
+
+/// Source of the wall-clock time used by cartridge real time clocks (MBC3, HuC3).
+/// Only the difference between two readings is relevant, the origin can be arbitrary.
+pub trait ClockSource: Debug + Send + ClockSourceClone {
+    /// Current time in seconds
+    fn now_seconds(&self) -> u64;
+
+    /// Called with the amount of emulated T-cycles that passed, clocks based on real time can ignore this.
+    fn advance_cycles(&mut self, _cycles: u64) {}
+
+    /// The emulated T-cycles the time is derived from, None for clocks based on real time.
+    /// Saved along with the RTC, so a loaded state ticks the clock at the same cycles again.
+    fn get_emulated_cycles(&self) -> Option<u64> {
+        None
+    }
+
+    /// Restores the emulated T-cycles from a save state, clocks based on real time ignore this.
+    fn set_emulated_cycles(&mut self, _cycles: u64) {}
+}
+
+/// Allows boxed clock sources to be cloned along with the cartridge that owns them.
+/// Implemented for every clock source that is [`Clone`].
+pub trait ClockSourceClone {
+    fn clone_box(&self) -> Box<dyn ClockSource>;
+}
+
+impl<T: ClockSource + Clone + 'static> ClockSourceClone for T {
+    fn clone_box(&self) -> Box<dyn ClockSource> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn ClockSource> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Reads the host system time.
+/// Not available on targets without a system clock like wasm32-unknown-unknown, use one of the other sources there.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct SystemClock;
+
+impl ClockSource for SystemClock {
+    fn now_seconds(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default()
+    }
+}
+
+/// A clock that only moves when told to, for tests and tools.
+/// Clones share the same time, so a handle can be kept to control a clock that was handed to a cartridge.
+#[derive(Debug, Default, Clone)]
+pub struct ManualClock {
+    seconds: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new(seconds: u64) -> Self {
+        Self {
+            seconds: Arc::new(AtomicU64::new(seconds)),
+        }
+    }
+
+    pub fn set_seconds(&self, seconds: u64) {
+        self.seconds.store(seconds, Ordering::Relaxed);
+    }
+
+    pub fn advance_seconds(&self, seconds: u64) {
+        self.seconds.fetch_add(seconds, Ordering::Relaxed);
+    }
+}
+
+impl ClockSource for ManualClock {
+    fn now_seconds(&self) -> u64 {
+        self.seconds.load(Ordering::Relaxed)
+    }
+}
+
+/// Derives the time from the amount of emulated cycles, making the RTC fully deterministic under replay.
+/// Emulated time only passes while the emulator is running, the cycles are part of save states.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct CycleClock {
+    cycles: u64,
+}
+
+impl CycleClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_cycles(&self) -> u64 {
+        self.cycles
+    }
+}
+
+impl ClockSource for CycleClock {
+    fn now_seconds(&self) -> u64 {
+        self.cycles / CYCLES_PER_SECOND
+    }
+
+    fn advance_cycles(&mut self, cycles: u64) {
+        self.cycles = self.cycles.wrapping_add(cycles);
+    }
+
+    fn get_emulated_cycles(&self) -> Option<u64> {
+        Some(self.cycles)
+    }
+
+    fn set_emulated_cycles(&mut self, cycles: u64) {
+        self.cycles = cycles;
+    }
+}
@@ -0,0 +1,284 @@
+use crate::cartridge::checksum::{read_global_checksum, read_header_checksum, verify_header_checksum};
+
+// Header layout according to: https://gbdev.io/pandocs/The_Cartridge_Header.html
+pub const ENTRY_POINT_ADDRESS: usize = 0x0100;
+pub const LOGO_ADDRESS: usize = 0x0104;
+pub const TITLE_ADDRESS: usize = 0x0134;
+/// Newer cartridges use the last 5 title bytes for the manufacturer code and CGB flag
+pub const TITLE_LENGTH: usize = 16;
+pub const CGB_FLAG_ADDRESS: usize = 0x0143;
+pub const NEW_LICENSEE_CODE_ADDRESS: usize = 0x0144;
+pub const SGB_FLAG_ADDRESS: usize = 0x0146;
+pub const CARTRIDGE_TYPE_ADDRESS: usize = 0x0147;
+pub const ROM_SIZE_ADDRESS: usize = 0x0148;
+pub const RAM_SIZE_ADDRESS: usize = 0x0149;
+pub const DESTINATION_CODE_ADDRESS: usize = 0x014A;
+pub const OLD_LICENSEE_CODE_ADDRESS: usize = 0x014B;
+pub const VERSION_ADDRESS: usize = 0x014C;
+/// First address after the header, where code usually continues after jumping away from the entry point
+pub const HEADER_END_ADDRESS: usize = 0x0150;
+
+pub const ROM_BANK_SIZE: usize = 0x4000;
+
+/// Verified by the boot ROM, the cartridge won't boot on real hardware if it doesn't match
+pub const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+const CGB_SUPPORT_BIT: u8 = 0x80;
+const CGB_ONLY: u8 = 0xC0;
+const SGB_SUPPORT: u8 = 0x03;
+/// The old licensee code that indicates the new licensee code is used instead
+const USE_NEW_LICENSEE_CODE: u8 = 0x33;
+/// The title of CGB cartridges doesn't include the CGB flag
+const CGB_TITLE_LENGTH: usize = 15;
+
+/// The parsed cartridge header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CartridgeHeader {
+    title: String,
+    cgb_flag: u8,
+    new_licensee_code: [u8; 2],
+    sgb_flag: u8,
+    cartridge_type: u8,
+    rom_size_code: u8,
+    ram_size_code: u8,
+    destination_code: u8,
+    old_licensee_code: u8,
+    version: u8,
+    header_checksum: u8,
+    global_checksum: u16,
+    header_checksum_valid: bool,
+}
+
+impl CartridgeHeader {
+    /// Returns None if the image is too small to contain a header
+    pub fn parse(rom: &[u8]) -> Option<Self> {
+        if rom.len() < HEADER_END_ADDRESS {
+            return None;
+        }
+
+        let cgb_flag = rom[CGB_FLAG_ADDRESS];
+        let title_length = if cgb_flag & CGB_SUPPORT_BIT != 0 {
+            CGB_TITLE_LENGTH
+        } else {
+            TITLE_LENGTH
+        };
+        let title = rom[TITLE_ADDRESS..TITLE_ADDRESS + title_length]
+            .iter()
+            .take_while(|&&byte| byte != 0x00)
+            .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '?' })
+            .collect::<String>()
+            .trim_end()
+            .to_string();
+
+        Some(Self {
+            title,
+            cgb_flag,
+            new_licensee_code: [rom[NEW_LICENSEE_CODE_ADDRESS], rom[NEW_LICENSEE_CODE_ADDRESS + 1]],
+            sgb_flag: rom[SGB_FLAG_ADDRESS],
+            cartridge_type: rom[CARTRIDGE_TYPE_ADDRESS],
+            rom_size_code: rom[ROM_SIZE_ADDRESS],
+            ram_size_code: rom[RAM_SIZE_ADDRESS],
+            destination_code: rom[DESTINATION_CODE_ADDRESS],
+            old_licensee_code: rom[OLD_LICENSEE_CODE_ADDRESS],
+            version: rom[VERSION_ADDRESS],
+            header_checksum: read_header_checksum(rom)?,
+            global_checksum: read_global_checksum(rom)?,
+            header_checksum_valid: verify_header_checksum(rom),
+        })
+    }
+
+    pub fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn get_cgb_flag(&self) -> u8 {
+        self.cgb_flag
+    }
+
+    pub fn supports_cgb(&self) -> bool {
+        self.cgb_flag & CGB_SUPPORT_BIT != 0
+    }
+
+    pub fn is_cgb_only(&self) -> bool {
+        self.cgb_flag == CGB_ONLY
+    }
+
+    /// Only cartridges that also use the old licensee code 0x33 can use SGB functions
+    pub fn supports_sgb(&self) -> bool {
+        self.sgb_flag == SGB_SUPPORT && self.old_licensee_code == USE_NEW_LICENSEE_CODE
+    }
+
+    pub fn get_sgb_flag(&self) -> u8 {
+        self.sgb_flag
+    }
+
+    pub fn get_new_licensee_code(&self) -> [u8; 2] {
+        self.new_licensee_code
+    }
+
+    pub fn get_old_licensee_code(&self) -> u8 {
+        self.old_licensee_code
+    }
+
+    pub fn get_cartridge_type_code(&self) -> u8 {
+        self.cartridge_type
+    }
+
+    /// Returns None for unknown cartridge type codes
+    pub fn get_cartridge_type(&self) -> Option<CartridgeType> {
+        CartridgeType::from_code(self.cartridge_type)
+    }
+
+    pub fn get_rom_size_code(&self) -> u8 {
+        self.rom_size_code
+    }
+
+    /// ROM size in bytes, None for unknown size codes
+    pub fn get_rom_size(&self) -> Option<usize> {
+        match self.rom_size_code {
+            // 32 KiB << code
+            code @ 0x00..=0x08 => Some((2 * ROM_BANK_SIZE) << code),
+            _ => None,
+        }
+    }
+
+    pub fn get_ram_size_code(&self) -> u8 {
+        self.ram_size_code
+    }
+
+    /// External RAM size in bytes, None for unknown size codes
+    pub fn get_ram_size(&self) -> Option<usize> {
+        match self.ram_size_code {
+            0x00 => Some(0),
+            // Unofficial, used by a few homebrew ROMs
+            0x01 => Some(0x800),
+            0x02 => Some(0x2000),
+            0x03 => Some(0x8000),
+            0x04 => Some(0x20000),
+            0x05 => Some(0x10000),
+            _ => None,
+        }
+    }
+
+    /// 0x00 for Japan, 0x01 for overseas
+    pub fn get_destination_code(&self) -> u8 {
+        self.destination_code
+    }
+
+    pub fn get_version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn get_header_checksum(&self) -> u8 {
+        self.header_checksum
+    }
+
+    pub fn get_global_checksum(&self) -> u16 {
+        self.global_checksum
+    }
+
+    /// The boot ROM locks up if the header checksum doesn't match, the cartridge is still loaded regardless
+    pub fn is_header_checksum_valid(&self) -> bool {
+        self.header_checksum_valid
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MapperType {
+    /// No mapper, up to 32 KiB ROM and optionally 8 KiB RAM
+    RomOnly,
+    MBC1,
+    MBC2,
+    MBC3,
+    MBC5,
+    MBC6,
+    MBC7,
+    MMM01,
+    PocketCamera,
+    TAMA5,
+    HuC1,
+    HuC3,
+}
+
+/// The mapper and additional hardware on a cartridge, decoded from the cartridge type code
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CartridgeType {
+    mapper: MapperType,
+    ram: bool,
+    battery: bool,
+    timer: bool,
+    rumble: bool,
+}
+
+impl CartridgeType {
+    const fn new(mapper: MapperType, ram: bool, battery: bool, timer: bool, rumble: bool) -> Self {
+        Self {
+            mapper,
+            ram,
+            battery,
+            timer,
+            rumble,
+        }
+    }
+
+    // Cartridge types according to: https://gbdev.io/pandocs/The_Cartridge_Header.html#0147--cartridge-type
+    pub fn from_code(code: u8) -> Option<Self> {
+        use MapperType::*;
+        let cartridge_type = match code {
+            0x00 => Self::new(RomOnly, false, false, false, false),
+            0x01 => Self::new(MBC1, false, false, false, false),
+            0x02 => Self::new(MBC1, true, false, false, false),
+            0x03 => Self::new(MBC1, true, true, false, false),
+            0x05 => Self::new(MBC2, false, false, false, false),
+            0x06 => Self::new(MBC2, false, true, false, false),
+            0x08 => Self::new(RomOnly, true, false, false, false),
+            0x09 => Self::new(RomOnly, true, true, false, false),
+            0x0B => Self::new(MMM01, false, false, false, false),
+            0x0C => Self::new(MMM01, true, false, false, false),
+            0x0D => Self::new(MMM01, true, true, false, false),
+            0x0F => Self::new(MBC3, false, true, true, false),
+            0x10 => Self::new(MBC3, true, true, true, false),
+            0x11 => Self::new(MBC3, false, false, false, false),
+            0x12 => Self::new(MBC3, true, false, false, false),
+            0x13 => Self::new(MBC3, true, true, false, false),
+            0x19 => Self::new(MBC5, false, false, false, false),
+            0x1A => Self::new(MBC5, true, false, false, false),
+            0x1B => Self::new(MBC5, true, true, false, false),
+            0x1C => Self::new(MBC5, false, false, false, true),
+            0x1D => Self::new(MBC5, true, false, false, true),
+            0x1E => Self::new(MBC5, true, true, false, true),
+            0x20 => Self::new(MBC6, true, true, false, false),
+            0x22 => Self::new(MBC7, true, true, false, true),
+            0xFC => Self::new(PocketCamera, true, true, false, false),
+            0xFD => Self::new(TAMA5, true, true, true, false),
+            0xFE => Self::new(HuC3, true, true, true, false),
+            0xFF => Self::new(HuC1, true, true, false, false),
+            _ => return None,
+        };
+        Some(cartridge_type)
+    }
+
+    pub fn get_mapper(&self) -> MapperType {
+        self.mapper
+    }
+
+    pub fn has_ram(&self) -> bool {
+        self.ram
+    }
+
+    pub fn has_battery(&self) -> bool {
+        self.battery
+    }
+
+    pub fn has_timer(&self) -> bool {
+        self.timer
+    }
+
+    pub fn has_rumble(&self) -> bool {
+        self.rumble
+    }
+}
@@ -0,0 +1,182 @@
+use crate::cartridge::header::{MapperType, ROM_BANK_SIZE};
+use crate::cartridge::mbc::mbc1::MBC1;
+use crate::cartridge::mbc::mbc3::MBC3;
+use crate::cartridge::mbc::mbc5::MBC5;
+use crate::cartridge::mbc::snapshot::MbcSnapshot;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+pub mod mbc1;
+pub mod mbc3;
+pub mod mbc5;
+pub mod rtc;
+pub mod snapshot;
+
+pub const RAM_BANK_SIZE: usize = 0x2000;
+/// Writing a value with this lower nibble to the RAM enable register enables external RAM
+const RAM_ENABLE_VALUE: u8 = 0x0A;
+/// Read from external RAM while it is disabled or missing
+const OPEN_BUS: u8 = 0xFF;
+
+/// The memory bank controller of a cartridge, translating CPU addresses into ROM and RAM offsets.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MBC {
+    RomOnly,
+    MBC1(MBC1),
+    MBC3(MBC3),
+    MBC5(MBC5),
+}
+
+impl MBC {
+    /// Reads 0x0000-0x7FFF
+    pub fn read_rom(&self, rom: &[u8], address: u16) -> u8 {
+        rom[rom_offset(rom.len(), self.get_rom_bank(rom.len(), address), address)]
+    }
+
+    /// The ROM bank mapped to the address in 0x0000-0x7FFF, wrapped to the size of the ROM
+    pub fn get_rom_bank(&self, rom_size: usize, address: u16) -> usize {
+        let bank = match self {
+            Self::RomOnly => (address as usize) / ROM_BANK_SIZE,
+            Self::MBC1(mbc) => mbc.get_rom_bank(address),
+            Self::MBC3(mbc) => mbc.get_rom_bank(address),
+            Self::MBC5(mbc) => mbc.get_rom_bank(address),
+        };
+        bank % (rom_size / ROM_BANK_SIZE).max(1)
+    }
+
+    /// Writes to 0x0000-0x7FFF go to the control registers of the mapper
+    pub fn write_rom(&mut self, address: u16, value: u8) {
+        match self {
+            Self::RomOnly => {}
+            Self::MBC1(mbc) => mbc.write_register(address, value),
+            Self::MBC3(mbc) => mbc.write_register(address, value),
+            Self::MBC5(mbc) => mbc.write_register(address, value),
+        }
+    }
+
+    /// Reads 0xA000-0xBFFF
+    pub fn read_ram(&self, ram: &[u8], address: u16) -> u8 {
+        if let Self::MBC3(mbc) = self
+            && let Some(value) = mbc.read_rtc()
+        {
+            return value;
+        }
+
+        self.get_ram_bank()
+            .and_then(|bank| ram_offset(ram.len(), bank, address))
+            .map_or(OPEN_BUS, |offset| ram[offset])
+    }
+
+    /// Writes 0xA000-0xBFFF
+    pub fn write_ram(&mut self, ram: &mut [u8], address: u16, value: u8) {
+        if let Self::MBC3(mbc) = self
+            && mbc.write_rtc(value)
+        {
+            return;
+        }
+
+        if let Some(offset) = self.get_ram_bank().and_then(|bank| ram_offset(ram.len(), bank, address)) {
+            ram[offset] = value;
+        }
+    }
+
+    /// The banking state for the given ROM and RAM sizes
+    pub fn snapshot(&self, rom_size: usize, ram_size: usize) -> MbcSnapshot {
+        let rom_banks = (rom_size / ROM_BANK_SIZE).max(1);
+        let ram_banks = ram_size.div_ceil(RAM_BANK_SIZE);
+        let mut snapshot = MbcSnapshot {
+            mapper: MapperType::RomOnly,
+            rom_bank_low: 0,
+            rom_bank_high: 1 % rom_banks,
+            ram_bank: (ram_banks > 0).then_some(0),
+            ram_enabled: ram_banks > 0,
+            advanced_banking: false,
+            rtc_register: None,
+            rtc_latched: None,
+            rumble_active: false,
+        };
+
+        let (rom_bank_low, rom_bank_high, ram_bank) = match self {
+            Self::RomOnly => return snapshot,
+            Self::MBC1(mbc) => {
+                snapshot.mapper = MapperType::MBC1;
+                snapshot.ram_enabled = mbc.is_ram_enabled();
+                snapshot.advanced_banking = mbc.is_advanced_banking();
+                (mbc.get_rom_bank(0x0000), mbc.get_rom_bank(0x4000), Some(mbc.get_selected_ram_bank()))
+            }
+            Self::MBC3(mbc) => {
+                snapshot.mapper = MapperType::MBC3;
+                snapshot.ram_enabled = mbc.is_ram_enabled();
+                snapshot.rtc_register = mbc.get_selected_rtc_register();
+                snapshot.rtc_latched = mbc.get_rtc().map(|rtc| rtc.get_latched_registers());
+                (mbc.get_rom_bank(0x0000), mbc.get_rom_bank(0x4000), mbc.get_selected_ram_bank())
+            }
+            Self::MBC5(mbc) => {
+                snapshot.mapper = MapperType::MBC5;
+                snapshot.ram_enabled = mbc.is_ram_enabled();
+                snapshot.rumble_active = mbc.is_rumble_active();
+                (mbc.get_rom_bank(0x0000), mbc.get_rom_bank(0x4000), Some(mbc.get_selected_ram_bank()))
+            }
+        };
+
+        snapshot.rom_bank_low = rom_bank_low % rom_banks;
+        snapshot.rom_bank_high = rom_bank_high % rom_banks;
+        snapshot.ram_bank = ram_bank.filter(|_| ram_banks > 0).map(|bank| bank % ram_banks);
+        snapshot
+    }
+
+    /// Advances the mapper by one M-cycle
+    pub fn tick(&mut self) {
+        if let Self::MBC3(mbc) = self {
+            mbc.tick();
+        }
+    }
+
+    /// The RAM bank mapped to 0xA000-0xBFFF, None if RAM is disabled
+    fn get_ram_bank(&self) -> Option<usize> {
+        match self {
+            Self::RomOnly => Some(0),
+            Self::MBC1(mbc) => mbc.get_ram_bank(),
+            Self::MBC3(mbc) => mbc.get_ram_bank(),
+            Self::MBC5(mbc) => mbc.get_ram_bank(),
+        }
+    }
+}
+
+fn is_ram_enable_value(value: u8) -> bool {
+    value & 0x0F == RAM_ENABLE_VALUE
+}
+
+/// Bank numbers beyond the ROM size wrap around, as the upper bank lines are not connected
+fn rom_offset(rom_size: usize, bank: usize, address: u16) -> usize {
+    let banks = (rom_size / ROM_BANK_SIZE).max(1);
+    (bank % banks) * ROM_BANK_SIZE + (address as usize % ROM_BANK_SIZE)
+}
+
+/// RAM smaller than a bank is mirrored across it, None if there is no RAM
+fn ram_offset(ram_size: usize, bank: usize, address: u16) -> Option<usize> {
+    if ram_size == 0 {
+        return None;
+    }
+    Some((bank * RAM_BANK_SIZE + (address as usize % RAM_BANK_SIZE)) % ram_size)
+}
+
+impl SaveState for MBC {
+    fn save_state(&self, writer: &mut StateWriter) {
+        match self {
+            Self::RomOnly => {}
+            Self::MBC1(mbc) => mbc.save_state(writer),
+            Self::MBC3(mbc) => mbc.save_state(writer),
+            Self::MBC5(mbc) => mbc.save_state(writer),
+        }
+    }
+
+    /// The mapper is given by the cartridge, only its registers are restored
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        match self {
+            Self::RomOnly => Ok(()),
+            Self::MBC1(mbc) => mbc.load_state(reader),
+            Self::MBC3(mbc) => mbc.load_state(reader),
+            Self::MBC5(mbc) => mbc.load_state(reader),
+        }
+    }
+}
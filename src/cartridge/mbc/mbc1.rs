@@ -0,0 +1,79 @@
+use crate::cartridge::mbc::is_ram_enable_value;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// MBC1 according to: https://gbdev.io/pandocs/MBC1.html
+const BANK_LOW_MASK: u8 = 0x1F;
+const BANK_HIGH_MASK: u8 = 0x03;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MBC1 {
+    ram_enabled: bool,
+    /// Lower 5 bits of the ROM bank, never 0
+    rom_bank: u8,
+    /// Either the RAM bank or the upper 2 bits of the ROM bank
+    bank_high: u8,
+    /// In advanced banking mode the upper bits also switch 0x0000-0x3FFF and the RAM bank
+    advanced_banking: bool,
+}
+
+impl MBC1 {
+    pub fn new() -> Self {
+        Self {
+            rom_bank: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn write_register(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = is_ram_enable_value(value),
+            // Only the 5 bit value is checked for 0, which makes banks 0x20, 0x40 and 0x60 unreachable here
+            0x2000..=0x3FFF => self.rom_bank = (value & BANK_LOW_MASK).max(1),
+            0x4000..=0x5FFF => self.bank_high = value & BANK_HIGH_MASK,
+            _ => self.advanced_banking = value & 0x01 != 0,
+        }
+    }
+
+    pub fn get_rom_bank(&self, address: u16) -> usize {
+        let bank_high = (self.bank_high as usize) << 5;
+        match address {
+            0x0000..=0x3FFF if self.advanced_banking => bank_high,
+            0x0000..=0x3FFF => 0,
+            _ => bank_high | self.rom_bank as usize,
+        }
+    }
+
+    pub fn get_ram_bank(&self) -> Option<usize> {
+        self.ram_enabled.then(|| self.get_selected_ram_bank())
+    }
+
+    /// The RAM bank that is mapped once RAM is enabled
+    pub fn get_selected_ram_bank(&self) -> usize {
+        if self.advanced_banking { self.bank_high as usize } else { 0 }
+    }
+
+    pub fn is_ram_enabled(&self) -> bool {
+        self.ram_enabled
+    }
+
+    pub fn is_advanced_banking(&self) -> bool {
+        self.advanced_banking
+    }
+}
+
+impl SaveState for MBC1 {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.ram_enabled);
+        writer.write_u8(self.rom_bank);
+        writer.write_u8(self.bank_high);
+        writer.write_bool(self.advanced_banking);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.ram_enabled = reader.read_bool()?;
+        self.rom_bank = (reader.read_u8()? & BANK_LOW_MASK).max(1);
+        self.bank_high = reader.read_u8()? & BANK_HIGH_MASK;
+        self.advanced_banking = reader.read_bool()?;
+        Ok(())
+    }
+}
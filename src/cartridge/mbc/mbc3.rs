@@ -0,0 +1,129 @@
+use crate::cartridge::mbc::is_ram_enable_value;
+use crate::cartridge::mbc::rtc::RTC;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// MBC3 according to: https://gbdev.io/pandocs/MBC3.html
+const ROM_BANK_MASK: u8 = 0x7F;
+const RAM_BANK_MASK: u8 = 0x07;
+const RTC_REGISTER_START: u8 = 0x08;
+const RTC_REGISTER_END: u8 = 0x0C;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MBC3 {
+    /// Also enables access to the RTC registers
+    ram_enabled: bool,
+    /// 7 bits, never 0
+    rom_bank: u8,
+    /// 0x00-0x07 select a RAM bank, 0x08-0x0C an RTC register
+    ram_select: u8,
+    rtc: Option<RTC>,
+}
+
+impl MBC3 {
+    pub fn new(rtc: Option<RTC>) -> Self {
+        Self {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_select: 0,
+            rtc,
+        }
+    }
+
+    pub fn write_register(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = is_ram_enable_value(value),
+            0x2000..=0x3FFF => self.rom_bank = (value & ROM_BANK_MASK).max(1),
+            0x4000..=0x5FFF => self.ram_select = value,
+            _ => {
+                if let Some(rtc) = &mut self.rtc {
+                    rtc.write_latch(value);
+                }
+            }
+        }
+    }
+
+    pub fn get_rom_bank(&self, address: u16) -> usize {
+        match address {
+            0x0000..=0x3FFF => 0,
+            _ => self.rom_bank as usize,
+        }
+    }
+
+    /// None if RAM is disabled or an RTC register is selected
+    pub fn get_ram_bank(&self) -> Option<usize> {
+        self.get_selected_ram_bank().filter(|_| self.ram_enabled)
+    }
+
+    /// The RAM bank that is mapped once RAM is enabled, None if an RTC register is selected
+    pub fn get_selected_ram_bank(&self) -> Option<usize> {
+        (self.ram_select <= RAM_BANK_MASK).then_some(self.ram_select as usize)
+    }
+
+    /// Returns None if no RTC register is selected, in which case the RAM is accessed instead
+    pub fn read_rtc(&self) -> Option<u8> {
+        let register = self.get_selected_rtc_register()?;
+        if !self.ram_enabled {
+            return Some(0xFF);
+        }
+        self.rtc.as_ref().map(|rtc| rtc.read(register))
+    }
+
+    /// Returns false if no RTC register is selected, in which case the RAM is accessed instead
+    pub fn write_rtc(&mut self, value: u8) -> bool {
+        let Some(register) = self.get_selected_rtc_register() else {
+            return false;
+        };
+        if self.ram_enabled
+            && let Some(rtc) = &mut self.rtc
+        {
+            rtc.write(register, value);
+        }
+        true
+    }
+
+    pub fn tick(&mut self) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.tick();
+        }
+    }
+
+    pub fn is_ram_enabled(&self) -> bool {
+        self.ram_enabled
+    }
+
+    pub fn get_rtc(&self) -> Option<&RTC> {
+        self.rtc.as_ref()
+    }
+
+    pub fn get_rtc_mut(&mut self) -> Option<&mut RTC> {
+        self.rtc.as_mut()
+    }
+
+    /// The RTC register mapped to 0xA000-0xBFFF, only on cartridges that have an RTC
+    pub fn get_selected_rtc_register(&self) -> Option<u8> {
+        let selected = (RTC_REGISTER_START..=RTC_REGISTER_END).contains(&self.ram_select);
+        (selected && self.rtc.is_some()).then(|| self.ram_select - RTC_REGISTER_START)
+    }
+}
+
+impl SaveState for MBC3 {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.ram_enabled);
+        writer.write_u8(self.rom_bank);
+        writer.write_u8(self.ram_select);
+        if let Some(rtc) = &self.rtc {
+            rtc.save_state(writer);
+        }
+    }
+
+    /// Whether there is an RTC is given by the cartridge, it isn't part of the state
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.ram_enabled = reader.read_bool()?;
+        self.rom_bank = (reader.read_u8()? & ROM_BANK_MASK).max(1);
+        self.ram_select = reader.read_u8()?;
+        if let Some(rtc) = &mut self.rtc {
+            rtc.load_state(reader)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use crate::cartridge::mbc::is_ram_enable_value;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// MBC5 according to: https://gbdev.io/pandocs/MBC5.html
+const RAM_BANK_MASK: u8 = 0x0F;
+/// On rumble cartridges bit 3 of the RAM bank register controls the motor
+const RUMBLE_BIT: u8 = 0x08;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MBC5 {
+    ram_enabled: bool,
+    /// 9 bits, unlike the other mappers bank 0 can also be mapped to 0x4000-0x7FFF
+    rom_bank: u16,
+    ram_bank: u8,
+    has_rumble: bool,
+    rumble_active: bool,
+}
+
+impl MBC5 {
+    pub fn new(has_rumble: bool) -> Self {
+        Self {
+            rom_bank: 1,
+            has_rumble,
+            ..Default::default()
+        }
+    }
+
+    pub fn write_register(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = is_ram_enable_value(value),
+            0x2000..=0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | value as u16,
+            0x3000..=0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | ((value as u16 & 0x01) << 8),
+            0x4000..=0x5FFF => {
+                if self.has_rumble {
+                    self.rumble_active = value & RUMBLE_BIT != 0;
+                    self.ram_bank = value & RAM_BANK_MASK & !RUMBLE_BIT;
+                } else {
+                    self.ram_bank = value & RAM_BANK_MASK;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn get_rom_bank(&self, address: u16) -> usize {
+        match address {
+            0x0000..=0x3FFF => 0,
+            _ => self.rom_bank as usize,
+        }
+    }
+
+    pub fn get_ram_bank(&self) -> Option<usize> {
+        self.ram_enabled.then_some(self.ram_bank as usize)
+    }
+
+    /// The RAM bank that is mapped once RAM is enabled
+    pub fn get_selected_ram_bank(&self) -> usize {
+        self.ram_bank as usize
+    }
+
+    pub fn is_ram_enabled(&self) -> bool {
+        self.ram_enabled
+    }
+
+    /// Whether the rumble motor is currently turned on
+    pub fn is_rumble_active(&self) -> bool {
+        self.rumble_active
+    }
+}
+
+impl SaveState for MBC5 {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.ram_enabled);
+        writer.write_u16(self.rom_bank);
+        writer.write_u8(self.ram_bank);
+        writer.write_bool(self.rumble_active);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.ram_enabled = reader.read_bool()?;
+        self.rom_bank = reader.read_u16()? & 0x1FF;
+        self.ram_bank = reader.read_u8()? & RAM_BANK_MASK;
+        self.rumble_active = reader.read_bool()? && self.has_rumble;
+        Ok(())
+    }
+}
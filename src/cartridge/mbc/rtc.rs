@@ -0,0 +1,208 @@
+use crate::cartridge::clock_source::{ClockSource, SystemClock};
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// MBC3 real time clock according to: https://gbdev.io/pandocs/MBC3.html#clock-counter-registers
+pub const SECONDS_REGISTER: u8 = 0x00;
+pub const MINUTES_REGISTER: u8 = 0x01;
+pub const HOURS_REGISTER: u8 = 0x02;
+pub const DAY_LOW_REGISTER: u8 = 0x03;
+pub const DAY_HIGH_REGISTER: u8 = 0x04;
+const REGISTER_COUNT: usize = 5;
+
+const DAY_HIGH_BIT: u8 = 0b0000_0001;
+const HALT_BIT: u8 = 0b0100_0000;
+const DAY_CARRY_BIT: u8 = 0b1000_0000;
+const DAYS: u64 = 512;
+/// T-cycles per M-cycle, passed on to the clock source
+const CYCLES_PER_TICK: u64 = 4;
+
+/// The time keeping registers of MBC3 cartridges.
+/// Time is taken from a [`ClockSource`] whenever the registers are latched or written,
+/// so the clock keeps going while the emulator is paused or closed if the source is based on real time.
+#[derive(Debug, Clone)]
+pub struct RTC {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    /// 9 bits
+    days: u16,
+    halted: bool,
+    /// Set when the day counter overflows, stays set until cleared by the game
+    day_carry: bool,
+    /// The registers visible to the CPU, copied from the counters when latching
+    latched: [u8; REGISTER_COUNT],
+    /// Writing 0x00 followed by 0x01 latches the counters
+    latch_armed: bool,
+    /// Clock source time the counters were last brought up to date
+    last_sync: u64,
+    clock: Box<dyn ClockSource>,
+}
+
+impl RTC {
+    pub fn new(clock: Box<dyn ClockSource>) -> Self {
+        Self {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days: 0,
+            halted: false,
+            day_carry: false,
+            latched: [0; REGISTER_COUNT],
+            latch_armed: false,
+            last_sync: clock.now_seconds(),
+            clock,
+        }
+    }
+
+    /// Replaces the clock source, the counters keep their current time
+    pub fn set_clock_source(&mut self, clock: Box<dyn ClockSource>) {
+        self.sync();
+        self.last_sync = clock.now_seconds();
+        self.clock = clock;
+    }
+
+    pub fn tick(&mut self) {
+        self.clock.advance_cycles(CYCLES_PER_TICK);
+    }
+
+    pub fn write_latch(&mut self, value: u8) {
+        if self.latch_armed && value == 0x01 {
+            self.sync();
+            self.latched = self.get_registers();
+        }
+        self.latch_armed = value == 0x00;
+    }
+
+    /// Reads one of the latched registers
+    pub fn read(&self, register: u8) -> u8 {
+        self.latched[register as usize]
+    }
+
+    /// Writes go to the counters directly, the latched registers only change when latching again
+    pub fn write(&mut self, register: u8, value: u8) {
+        self.sync();
+        match register {
+            SECONDS_REGISTER => self.seconds = value & 0x3F,
+            MINUTES_REGISTER => self.minutes = value & 0x3F,
+            HOURS_REGISTER => self.hours = value & 0x1F,
+            DAY_LOW_REGISTER => self.days = (self.days & 0x100) | value as u16,
+            DAY_HIGH_REGISTER => {
+                self.days = (self.days & 0xFF) | ((value & DAY_HIGH_BIT) as u16) << 8;
+                self.halted = value & HALT_BIT != 0;
+                self.day_carry = value & DAY_CARRY_BIT != 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// The current counter values in register order, brought up to date with the clock source
+    pub fn get_current_registers(&mut self) -> [u8; REGISTER_COUNT] {
+        self.sync();
+        self.get_registers()
+    }
+
+    pub fn get_latched_registers(&self) -> [u8; REGISTER_COUNT] {
+        self.latched
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    fn get_registers(&self) -> [u8; REGISTER_COUNT] {
+        let mut day_high = (self.days >> 8) as u8 & DAY_HIGH_BIT;
+        if self.halted {
+            day_high |= HALT_BIT;
+        }
+        if self.day_carry {
+            day_high |= DAY_CARRY_BIT;
+        }
+        [self.seconds, self.minutes, self.hours, self.days as u8, day_high]
+    }
+
+    /// Advances the counters by the seconds that passed on the clock source since the last sync
+    fn sync(&mut self) {
+        let now = self.clock.now_seconds();
+        let elapsed = now.saturating_sub(self.last_sync);
+        self.last_sync = now;
+        if self.halted || elapsed == 0 {
+            return;
+        }
+
+        let seconds = self.seconds as u64 + elapsed;
+        let minutes = self.minutes as u64 + seconds / 60;
+        let hours = self.hours as u64 + minutes / 60;
+        let days = self.days as u64 + hours / 24;
+        self.seconds = (seconds % 60) as u8;
+        self.minutes = (minutes % 60) as u8;
+        self.hours = (hours % 24) as u8;
+        self.days = (days % DAYS) as u16;
+        if days >= DAYS {
+            self.day_carry = true;
+        }
+    }
+}
+
+impl Default for RTC {
+    fn default() -> Self {
+        Self::new(Box::new(SystemClock))
+    }
+}
+
+/// The clock source is not compared, only the register state
+impl PartialEq for RTC {
+    fn eq(&self, other: &Self) -> bool {
+        self.seconds == other.seconds
+            && self.minutes == other.minutes
+            && self.hours == other.hours
+            && self.days == other.days
+            && self.halted == other.halted
+            && self.day_carry == other.day_carry
+            && self.latched == other.latched
+            && self.latch_armed == other.latch_armed
+            && self.last_sync == other.last_sync
+    }
+}
+
+/// The clock source isn't part of the state, only the seconds that passed on it since the last sync
+/// and the cycles of clocks based on emulated time.
+/// Real time between saving and loading a state doesn't reach the counters.
+impl SaveState for RTC {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.seconds);
+        writer.write_u8(self.minutes);
+        writer.write_u8(self.hours);
+        writer.write_u16(self.days);
+        writer.write_bool(self.halted);
+        writer.write_bool(self.day_carry);
+        writer.write_bytes(&self.latched);
+        writer.write_bool(self.latch_armed);
+        writer.write_u64(self.clock.now_seconds().saturating_sub(self.last_sync));
+        let cycles = self.clock.get_emulated_cycles();
+        writer.write_bool(cycles.is_some());
+        writer.write_u64(cycles.unwrap_or_default());
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.seconds = reader.read_u8()? & 0x3F;
+        self.minutes = reader.read_u8()? & 0x3F;
+        self.hours = reader.read_u8()? & 0x1F;
+        self.days = reader.read_u16()? % DAYS as u16;
+        self.halted = reader.read_bool()?;
+        self.day_carry = reader.read_bool()?;
+        reader.read_into(&mut self.latched)?;
+        self.latch_armed = reader.read_bool()?;
+        let unsynced_seconds = reader.read_u64()?;
+        if reader.has_version(13) {
+            let cycle_based = reader.read_bool()?;
+            let cycles = reader.read_u64()?;
+            if cycle_based {
+                self.clock.set_emulated_cycles(cycles);
+            }
+        } else if self.clock.get_emulated_cycles().is_some() {
+            reader.record_default("cycles of the RTC clock source");
+        }
+        self.last_sync = self.clock.now_seconds().saturating_sub(unsynced_seconds);
+        Ok(())
+    }
+}
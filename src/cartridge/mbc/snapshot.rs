@@ -0,0 +1,23 @@
+use crate::cartridge::header::MapperType;
+
+/// Read-only copy of the banking state of a mapper, for memory viewers and debuggers.
+/// Bank numbers are already wrapped to the size of the ROM and RAM.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MbcSnapshot {
+    pub mapper: MapperType,
+    /// Bank mapped to 0x0000-0x3FFF
+    pub rom_bank_low: usize,
+    /// Bank mapped to 0x4000-0x7FFF
+    pub rom_bank_high: usize,
+    /// Bank mapped to 0xA000-0xBFFF when RAM is enabled, None without RAM or while an RTC register is selected
+    pub ram_bank: Option<usize>,
+    pub ram_enabled: bool,
+    /// MBC1 banking mode, where the upper bank bits also switch 0x0000-0x3FFF and the RAM bank
+    pub advanced_banking: bool,
+    /// MBC3 RTC register mapped to 0xA000-0xBFFF, 0x00 (seconds) to 0x04 (day high)
+    pub rtc_register: Option<u8>,
+    /// The latched RTC registers visible to the CPU, in register order
+    pub rtc_latched: Option<[u8; 5]>,
+    /// MBC5 rumble motor state
+    pub rumble_active: bool,
+}
@@ -0,0 +1,301 @@
+use crate::helpers::crc32::crc32;
+use std::fmt::{Display, Formatter};
+
+const IPS_HEADER: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+const BPS_HEADER: &[u8] = b"BPS1";
+/// Source, target and patch CRC32 at the end of every BPS patch
+const BPS_FOOTER_SIZE: usize = 12;
+
+const BPS_SOURCE_READ: usize = 0;
+const BPS_TARGET_READ: usize = 1;
+const BPS_SOURCE_COPY: usize = 2;
+const BPS_TARGET_COPY: usize = 3;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PatchFormat {
+    IPS,
+    BPS,
+}
+
+impl PatchFormat {
+    /// Detects the patch format from the magic bytes at the start of the patch.
+    pub fn detect(patch: &[u8]) -> Option<Self> {
+        if patch.starts_with(IPS_HEADER) {
+            Some(Self::IPS)
+        } else if patch.starts_with(BPS_HEADER) {
+            Some(Self::BPS)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    UnknownFormat,
+    UnexpectedEnd,
+    InvalidOffset,
+    SourceSizeMismatch { expected: usize, actual: usize },
+    TargetSizeMismatch { expected: usize, actual: usize },
+    SourceChecksumMismatch { expected: u32, actual: u32 },
+    TargetChecksumMismatch { expected: u32, actual: u32 },
+    PatchChecksumMismatch { expected: u32, actual: u32 },
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownFormat => write!(f, "unknown patch format"),
+            Self::UnexpectedEnd => write!(f, "unexpected end of patch data"),
+            Self::InvalidOffset => write!(f, "patch references data outside of the ROM"),
+            Self::SourceSizeMismatch { expected, actual } => {
+                write!(f, "source size mismatch: expected {expected} bytes, got {actual}")
+            }
+            Self::TargetSizeMismatch { expected, actual } => {
+                write!(f, "target size mismatch: expected {expected} bytes, got {actual}")
+            }
+            Self::SourceChecksumMismatch { expected, actual } => {
+                write!(f, "source checksum mismatch: expected {expected:08X}, got {actual:08X}")
+            }
+            Self::TargetChecksumMismatch { expected, actual } => {
+                write!(f, "target checksum mismatch: expected {expected:08X}, got {actual:08X}")
+            }
+            Self::PatchChecksumMismatch { expected, actual } => {
+                write!(f, "patch checksum mismatch: expected {expected:08X}, got {actual:08X}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// Applies an IPS or BPS patch to the given ROM, detecting the format from the patch header.
+pub fn apply_patch(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    match PatchFormat::detect(patch) {
+        Some(PatchFormat::IPS) => apply_ips(rom, patch),
+        Some(PatchFormat::BPS) => apply_bps(rom, patch),
+        None => Err(PatchError::UnknownFormat),
+    }
+}
+
+/// Applies an IPS patch to the given ROM.
+/// Format according to: https://zerosoft.zophar.net/ips.php
+/// Records writing past the end of the ROM grow it, an optional truncation size after the EOF marker is respected.
+pub fn apply_ips(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let mut reader = PatchReader::new(patch);
+    if reader.read_slice(IPS_HEADER.len())? != IPS_HEADER {
+        return Err(PatchError::UnknownFormat);
+    }
+
+    let mut output = rom.to_vec();
+    loop {
+        let offset_bytes = reader.read_slice(3)?;
+        if offset_bytes == IPS_EOF {
+            break;
+        }
+        let offset = be_value(offset_bytes);
+        let size = be_value(reader.read_slice(2)?);
+
+        if size == 0 {
+            // RLE record
+            let run_length = be_value(reader.read_slice(2)?);
+            let value = reader.read_u8()?;
+            write_ips_record(&mut output, offset, &vec![value; run_length]);
+        } else {
+            let data = reader.read_slice(size)?;
+            write_ips_record(&mut output, offset, data);
+        }
+    }
+
+    if reader.remaining() >= 3 {
+        let truncated_size = be_value(reader.read_slice(3)?);
+        output.truncate(truncated_size);
+    }
+
+    Ok(output)
+}
+
+fn write_ips_record(output: &mut Vec<u8>, offset: usize, data: &[u8]) {
+    let end = offset + data.len();
+    if output.len() < end {
+        output.resize(end, 0);
+    }
+    output[offset..end].copy_from_slice(data);
+}
+
+fn be_value(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |value, &byte| (value << 8) | byte as usize)
+}
+
+/// Applies a BPS patch to the given ROM, verifying the source, target and patch checksums.
+/// Format according to: https://github.com/blakesmith/rombp/blob/master/docs/bps_spec.md
+pub fn apply_bps(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    if patch.len() < BPS_HEADER.len() + BPS_FOOTER_SIZE {
+        return Err(PatchError::UnexpectedEnd);
+    }
+
+    let footer_start = patch.len() - BPS_FOOTER_SIZE;
+    let footer = &patch[footer_start..];
+    let expected_source_crc = le_u32(&footer[0..4]);
+    let expected_target_crc = le_u32(&footer[4..8]);
+    let expected_patch_crc = le_u32(&footer[8..12]);
+
+    let actual_patch_crc = crc32(&patch[..patch.len() - 4]);
+    if actual_patch_crc != expected_patch_crc {
+        return Err(PatchError::PatchChecksumMismatch {
+            expected: expected_patch_crc,
+            actual: actual_patch_crc,
+        });
+    }
+
+    let mut reader = PatchReader::new(&patch[..footer_start]);
+    if reader.read_slice(BPS_HEADER.len())? != BPS_HEADER {
+        return Err(PatchError::UnknownFormat);
+    }
+
+    let source_size = reader.read_varint()?;
+    let target_size = reader.read_varint()?;
+    let metadata_size = reader.read_varint()?;
+    reader.read_slice(metadata_size)?;
+
+    if rom.len() != source_size {
+        return Err(PatchError::SourceSizeMismatch {
+            expected: source_size,
+            actual: rom.len(),
+        });
+    }
+
+    let actual_source_crc = crc32(rom);
+    if actual_source_crc != expected_source_crc {
+        return Err(PatchError::SourceChecksumMismatch {
+            expected: expected_source_crc,
+            actual: actual_source_crc,
+        });
+    }
+
+    // Not preallocated from the target size, since the header could request any amount of memory
+    let mut output: Vec<u8> = Vec::new();
+    let mut source_relative_offset: usize = 0;
+    let mut target_relative_offset: usize = 0;
+
+    while reader.remaining() > 0 {
+        let data = reader.read_varint()?;
+        let command = data & 3;
+        let length = (data >> 2) + 1;
+
+        // Also bounds the memory a malformed target copy can allocate
+        let written = output.len().saturating_add(length);
+        if written > target_size {
+            return Err(PatchError::TargetSizeMismatch {
+                expected: target_size,
+                actual: written,
+            });
+        }
+
+        match command {
+            BPS_SOURCE_READ => {
+                let start = output.len();
+                let end = start.checked_add(length).ok_or(PatchError::InvalidOffset)?;
+                let bytes = rom.get(start..end).ok_or(PatchError::InvalidOffset)?;
+                output.extend_from_slice(bytes);
+            }
+            BPS_TARGET_READ => {
+                output.extend_from_slice(reader.read_slice(length)?);
+            }
+            BPS_SOURCE_COPY => {
+                source_relative_offset = apply_relative_offset(source_relative_offset, reader.read_varint()?)?;
+                let end = source_relative_offset.checked_add(length).ok_or(PatchError::InvalidOffset)?;
+                let bytes = rom.get(source_relative_offset..end).ok_or(PatchError::InvalidOffset)?;
+                output.extend_from_slice(bytes);
+                source_relative_offset = end;
+            }
+            BPS_TARGET_COPY => {
+                target_relative_offset = apply_relative_offset(target_relative_offset, reader.read_varint()?)?;
+                // Copied byte by byte since source and destination may overlap
+                for _ in 0..length {
+                    let value = *output.get(target_relative_offset).ok_or(PatchError::InvalidOffset)?;
+                    output.push(value);
+                    target_relative_offset += 1;
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    if output.len() != target_size {
+        return Err(PatchError::TargetSizeMismatch {
+            expected: target_size,
+            actual: output.len(),
+        });
+    }
+
+    let actual_target_crc = crc32(&output);
+    if actual_target_crc != expected_target_crc {
+        return Err(PatchError::TargetChecksumMismatch {
+            expected: expected_target_crc,
+            actual: actual_target_crc,
+        });
+    }
+
+    Ok(output)
+}
+
+/// Lowest bit is the sign, the remaining bits are the magnitude
+fn apply_relative_offset(offset: usize, data: usize) -> Result<usize, PatchError> {
+    let magnitude = data >> 1;
+    if data & 1 == 1 {
+        offset.checked_sub(magnitude).ok_or(PatchError::InvalidOffset)
+    } else {
+        offset.checked_add(magnitude).ok_or(PatchError::InvalidOffset)
+    }
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+struct PatchReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PatchReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.position
+    }
+
+    fn read_u8(&mut self) -> Result<u8, PatchError> {
+        Ok(self.read_slice(1)?[0])
+    }
+
+    fn read_slice(&mut self, length: usize) -> Result<&'a [u8], PatchError> {
+        let end = self.position.checked_add(length).ok_or(PatchError::UnexpectedEnd)?;
+        let slice = self.data.get(self.position..end).ok_or(PatchError::UnexpectedEnd)?;
+        self.position = end;
+        Ok(slice)
+    }
+
+    /// BPS variable-length integer, 7 bits per byte with the high bit marking the last byte.
+    /// Values that don't fit into usize are rejected as invalid offsets.
+    fn read_varint(&mut self) -> Result<usize, PatchError> {
+        let mut value: usize = 0;
+        let mut shift: usize = 1;
+        loop {
+            let byte = self.read_u8()?;
+            value = ((byte & 0x7F) as usize)
+                .checked_mul(shift)
+                .and_then(|bits| value.checked_add(bits))
+                .ok_or(PatchError::InvalidOffset)?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift.checked_mul(0x80).ok_or(PatchError::InvalidOffset)?;
+            value = value.checked_add(shift).ok_or(PatchError::InvalidOffset)?;
+        }
+    }
+}
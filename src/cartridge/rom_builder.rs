@@ -0,0 +1,124 @@
+use crate::cartridge::checksum::fix_checksums_in_place;
+use crate::cartridge::header::{
+    CARTRIDGE_TYPE_ADDRESS, CGB_FLAG_ADDRESS, DESTINATION_CODE_ADDRESS, ENTRY_POINT_ADDRESS, HEADER_END_ADDRESS,
+    LOGO_ADDRESS, NEW_LICENSEE_CODE_ADDRESS, NINTENDO_LOGO, OLD_LICENSEE_CODE_ADDRESS, RAM_SIZE_ADDRESS, ROM_BANK_SIZE,
+    ROM_SIZE_ADDRESS, SGB_FLAG_ADDRESS, TITLE_ADDRESS, TITLE_LENGTH, VERSION_ADDRESS,
+};
+
+/// The smallest ROM, 2 banks without a mapper
+const MIN_ROM_BANKS: usize = 2;
+/// Unused ROM space is filled like erased flash
+const FILL_BYTE: u8 = 0xFF;
+/// NOP; JP 0x0150
+const DEFAULT_ENTRY_POINT: [u8; 4] = [0x00, 0xC3, 0x50, 0x01];
+
+/// Builds minimal valid ROM images from code bytes, so tests can boot the full system without binary fixtures.
+/// The entry point jumps to 0x0150, which is where [`RomBuilder::code`] places its bytes.
+/// The header and global checksums are always fixed up when building.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RomBuilder {
+    title: String,
+    cartridge_type: u8,
+    rom_banks: usize,
+    ram_size_code: u8,
+    cgb_flag: u8,
+    sgb_flag: u8,
+    segments: Vec<(usize, Vec<u8>)>,
+}
+
+impl Default for RomBuilder {
+    fn default() -> Self {
+        Self {
+            title: String::from("TEST"),
+            cartridge_type: 0x00,
+            rom_banks: MIN_ROM_BANKS,
+            ram_size_code: 0x00,
+            cgb_flag: 0x00,
+            sgb_flag: 0x00,
+            segments: Vec::new(),
+        }
+    }
+}
+
+impl RomBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only ASCII characters are kept, truncated to 16 bytes
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.chars().filter(char::is_ascii).take(TITLE_LENGTH).collect();
+        self
+    }
+
+    pub fn cartridge_type(mut self, cartridge_type: u8) -> Self {
+        self.cartridge_type = cartridge_type;
+        self
+    }
+
+    /// Rounded up to the next power of two, the image grows further if code is placed beyond it
+    pub fn rom_banks(mut self, banks: usize) -> Self {
+        self.rom_banks = banks.max(MIN_ROM_BANKS).next_power_of_two();
+        self
+    }
+
+    pub fn ram_size_code(mut self, code: u8) -> Self {
+        self.ram_size_code = code;
+        self
+    }
+
+    pub fn cgb_flag(mut self, flag: u8) -> Self {
+        self.cgb_flag = flag;
+        self
+    }
+
+    pub fn sgb_flag(mut self, flag: u8) -> Self {
+        self.sgb_flag = flag;
+        self
+    }
+
+    /// Places the code right after the header, where the default entry point jumps to
+    pub fn code(self, code: &[u8]) -> Self {
+        self.code_at(HEADER_END_ADDRESS, code)
+    }
+
+    /// Places bytes at an absolute ROM offset, later segments overwrite earlier ones and the header.
+    /// Bytes placed at 0x0100 replace the default entry point.
+    pub fn code_at(mut self, offset: usize, code: &[u8]) -> Self {
+        self.segments.push((offset, code.to_vec()));
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let required_size = self
+            .segments
+            .iter()
+            .map(|(offset, code)| offset + code.len())
+            .fold(self.rom_banks * ROM_BANK_SIZE, usize::max);
+        let banks = required_size.div_ceil(ROM_BANK_SIZE).next_power_of_two();
+
+        let mut rom = vec![FILL_BYTE; banks * ROM_BANK_SIZE];
+        rom[ENTRY_POINT_ADDRESS..ENTRY_POINT_ADDRESS + DEFAULT_ENTRY_POINT.len()].copy_from_slice(&DEFAULT_ENTRY_POINT);
+        rom[LOGO_ADDRESS..LOGO_ADDRESS + NINTENDO_LOGO.len()].copy_from_slice(&NINTENDO_LOGO);
+
+        rom[TITLE_ADDRESS..TITLE_ADDRESS + TITLE_LENGTH].fill(0x00);
+        rom[TITLE_ADDRESS..TITLE_ADDRESS + self.title.len()].copy_from_slice(self.title.as_bytes());
+        rom[CGB_FLAG_ADDRESS] = self.cgb_flag;
+        rom[NEW_LICENSEE_CODE_ADDRESS..NEW_LICENSEE_CODE_ADDRESS + 2].fill(0x00);
+        rom[SGB_FLAG_ADDRESS] = self.sgb_flag;
+        rom[CARTRIDGE_TYPE_ADDRESS] = self.cartridge_type;
+        // 32 KiB << code
+        rom[ROM_SIZE_ADDRESS] = (banks / MIN_ROM_BANKS).trailing_zeros() as u8;
+        rom[RAM_SIZE_ADDRESS] = self.ram_size_code;
+        rom[DESTINATION_CODE_ADDRESS] = 0x00;
+        rom[OLD_LICENSEE_CODE_ADDRESS] = 0x00;
+        rom[VERSION_ADDRESS] = 0x00;
+
+        for (offset, code) in &self.segments {
+            rom[*offset..offset + code.len()].copy_from_slice(code);
+        }
+
+        fix_checksums_in_place(&mut rom);
+        rom
+    }
+}
@@ -1,14 +1,371 @@
+use crate::cartridge::{Cartridge, EXTERNAL_RAM_END, EXTERNAL_RAM_START, ROM_END, ROM_START};
+use crate::circuitry::apu::{APU, NR10_ADDRESS, NR52_ADDRESS, WAVE_RAM_END, WAVE_RAM_START};
+use crate::circuitry::dma::{DMA_ADDRESS, OamDma};
+use crate::circuitry::hardware_variance::HardwareVariance;
 use crate::circuitry::interface::CircuitryInterface;
+use crate::circuitry::interrupts::{Interrupt, InterruptRegisters};
+use crate::circuitry::ppu::{LCDC_ADDRESS, OAM_END, OAM_START, PPU, VRAM_END, VRAM_START, WX_ADDRESS};
+use crate::circuitry::profiling::{Profiler, Stopwatch, SubsystemTimes};
+use crate::circuitry::ram_initialization::RamInitialization;
+use crate::circuitry::timer::{DIV_ADDRESS, TAC_ADDRESS, Timer};
+use crate::circuitry::undocumented::{UNDOCUMENTED_END, UNDOCUMENTED_START, UndocumentedRegisters};
+use crate::hardware_model::HardwareModel;
+use crate::hardware_model::revision::Quirks;
+use crate::helpers::random::SplitMix64;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+use std::time::Instant;
 
+pub mod apu;
+pub mod dma;
+pub mod hardware_variance;
 pub mod interface;
+pub mod interrupts;
+pub mod ppu;
+pub mod profiling;
+pub mod ram_initialization;
+pub mod timer;
+pub mod undocumented;
 
-#[derive(Debug, Default, PartialEq)]
+const WRAM_SIZE: usize = 0x2000;
+const HRAM_SIZE: usize = 0x7F;
+const WRAM_REGION_ID: u64 = 0;
+const HRAM_REGION_ID: u64 = 1;
+const OAM_VARIANCE_ID: u64 = 0;
+const BUS_NOISE_VARIANCE_ID: u64 = 1;
+
+pub const WRAM_START: u16 = 0xC000;
+pub const WRAM_END: u16 = 0xDFFF;
+pub const ECHO_RAM_START: u16 = 0xE000;
+pub const ECHO_RAM_END: u16 = 0xFDFF;
+pub const IF_ADDRESS: u16 = 0xFF0F;
+pub const HRAM_START: u16 = 0xFF80;
+pub const HRAM_END: u16 = 0xFFFE;
+pub const IE_ADDRESS: u16 = 0xFFFF;
+
+/// Value read from addresses nothing responds to
+const OPEN_BUS: u8 = 0xFF;
+
+// Initial timer counter according to: https://gbdev.io/pandocs/Power_Up_Sequence.html#hardware-registers
+// Only known for the DMG and MGB, the other models start at 0 until their values are verified.
+const DMG_TIMER_COUNTER: u16 = 0xABCC;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Circuitry {
+    wram: Vec<u8>,
+    hram: Vec<u8>,
+    interrupts: InterruptRegisters,
+    timer: Timer,
+    ppu: PPU,
+    apu: APU,
+    dma: OamDma,
+    /// Only present on the models that have them
+    undocumented: Option<UndocumentedRegisters>,
+    /// Generates the values read from the empty cartridge slot, None if they are always open bus
+    bus_noise: Option<SplitMix64>,
+    cartridge: Option<Cartridge>,
+    profiler: Profiler,
+}
+
+impl Circuitry {
+    pub fn initialize(model: HardwareModel, ram_initialization: RamInitialization, variance: HardwareVariance) -> Self {
+        let timer_counter = match model {
+            HardwareModel::DMG | HardwareModel::MGB => DMG_TIMER_COUNTER,
+            _ => 0,
+        };
+
+        let mut interrupts = InterruptRegisters::default();
+        // The VBlank interrupt is requested while the boot ROM runs
+        interrupts.request(Interrupt::VBlank);
+
+        let mut circuitry = Self {
+            wram: ram_initialization.create(WRAM_SIZE, WRAM_REGION_ID),
+            hram: ram_initialization.create(HRAM_SIZE, HRAM_REGION_ID),
+            interrupts,
+            timer: Timer::initialize(timer_counter),
+            ppu: PPU::initialize(),
+            apu: APU::initialize(),
+            dma: OamDma::default(),
+            undocumented: matches!(model, HardwareModel::CGB | HardwareModel::AGB).then(UndocumentedRegisters::default),
+            bus_noise: variance.create_rng(BUS_NOISE_VARIANCE_ID),
+            cartridge: None,
+            profiler: Profiler::default(),
+        };
+        if let Some(mut rng) = variance.create_rng(OAM_VARIANCE_ID) {
+            for address in OAM_START..=OAM_END {
+                circuitry.ppu.write_oam(address, rng.next_u8());
+            }
+        }
+        circuitry.set_quirks(model.get_default_revision().get_quirks());
+        circuitry
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.ppu.set_quirks(quirks);
+        self.apu.set_quirks(quirks);
+    }
+
+    /// Measures the host time spent in each subsystem, which has a small cost of its own
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiler.set_enabled(enabled, self.ppu.get_frame_count());
+    }
+
+    pub fn is_profiling(&self) -> bool {
+        self.profiler.is_enabled()
+    }
+
+    /// The host time spent on the last frame the PPU completed, None until a frame was completed while profiling
+    pub fn get_frame_profile(&self) -> Option<&SubsystemTimes> {
+        self.profiler.get_last_frame()
+    }
+
+    /// Starts measuring a CPU step, None while profiling is disabled
+    pub(crate) fn begin_profiled_step(&mut self) -> Option<Instant> {
+        if !self.profiler.is_enabled() {
+            return None;
+        }
+        self.profiler.begin_step()
+    }
+
+    pub(crate) fn finish_profiled_step(&mut self, started: Instant) {
+        self.profiler.finish_step(started.elapsed(), self.ppu.get_frame_count());
+    }
+
+    pub fn get_timer(&self) -> &Timer {
+        &self.timer
+    }
+
+    pub fn get_ppu(&self) -> &PPU {
+        &self.ppu
+    }
+
+    pub fn get_ppu_mut(&mut self) -> &mut PPU {
+        &mut self.ppu
+    }
+
+    pub fn get_apu(&self) -> &APU {
+        &self.apu
+    }
+
+    pub fn get_apu_mut(&mut self) -> &mut APU {
+        &mut self.apu
+    }
+
+    pub fn get_dma(&self) -> &OamDma {
+        &self.dma
+    }
+
+    pub fn get_cartridge(&self) -> Option<&Cartridge> {
+        self.cartridge.as_ref()
+    }
+
+    pub fn get_cartridge_mut(&mut self) -> Option<&mut Cartridge> {
+        self.cartridge.as_mut()
+    }
+
+    /// Returns the previously inserted cartridge
+    pub fn insert_cartridge(&mut self, cartridge: Cartridge) -> Option<Cartridge> {
+        self.cartridge.replace(cartridge)
+    }
+
+    pub fn remove_cartridge(&mut self) -> Option<Cartridge> {
+        self.cartridge.take()
+    }
+
+    pub fn get_interrupts(&self) -> &InterruptRegisters {
+        &self.interrupts
+    }
+
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupts.request(interrupt);
+    }
+
+    /// Reads memory without any side effects, for debugging tools.
+    /// The empty cartridge slot always reads as open bus, even if it is noisy for the CPU.
+    pub fn peek(&self, address: u16) -> u8 {
+        match address {
+            ROM_START..=ROM_END | EXTERNAL_RAM_START..=EXTERNAL_RAM_END => {
+                self.cartridge.as_ref().map_or(OPEN_BUS, |cartridge| cartridge.read(address))
+            }
+            VRAM_START..=VRAM_END => self.ppu.read_vram(address),
+            WRAM_START..=WRAM_END => self.wram[(address - WRAM_START) as usize],
+            ECHO_RAM_START..=ECHO_RAM_END => self.wram[(address - ECHO_RAM_START) as usize],
+            OAM_START..=OAM_END => self.ppu.read_oam(address),
+            DIV_ADDRESS..=TAC_ADDRESS => self.timer.read(address),
+            IF_ADDRESS => self.interrupts.read_flag(),
+            NR10_ADDRESS..=NR52_ADDRESS | WAVE_RAM_START..=WAVE_RAM_END => self.apu.read(address),
+            DMA_ADDRESS => self.dma.get_source(),
+            LCDC_ADDRESS..=WX_ADDRESS => self.ppu.read(address),
+            UNDOCUMENTED_START..=UNDOCUMENTED_END if let Some(undocumented) = &self.undocumented => {
+                undocumented.read(address, &self.apu)
+            }
+            HRAM_START..=HRAM_END => self.hram[(address - HRAM_START) as usize],
+            IE_ADDRESS => self.interrupts.get_enable(),
+            _ => OPEN_BUS,
+        }
+    }
+
+    /// Advances everything by one M-cycle, the observer sees the PPU after every dot
+    pub fn tick_observed(&mut self, observer: impl FnMut(&PPU)) {
+        let mut stopwatch = Stopwatch::start(self.profiler.is_enabled());
+        self.tick_dma();
+        let mut bus = stopwatch.lap();
+        self.ppu.tick_observed(&mut self.interrupts, observer);
+        let ppu = stopwatch.lap();
+        if let Some(cartridge) = &mut self.cartridge {
+            cartridge.tick();
+        }
+        if self.timer.tick() {
+            self.interrupts.request(Interrupt::Timer);
+        }
+        bus += stopwatch.lap();
+        self.apu.tick(self.timer.get_counter());
+        let apu = stopwatch.lap();
+
+        self.profiler.current.ppu += ppu;
+        self.profiler.current.apu += apu;
+        self.profiler.current.bus += bus;
+    }
+
+    fn is_oam_accessible(&self) -> bool {
+        !self.dma.is_active() && self.ppu.is_oam_accessible()
+    }
+
+    fn tick_dma(&mut self) {
+        if let Some((source, index)) = self.dma.tick() {
+            let value = self.peek(source);
+            self.dma.set_value(value);
+            self.ppu.write_oam(OAM_START + index as u16, value);
+        }
+    }
+
+    fn read_bus(&mut self, address: u16) -> u8 {
+        match address {
+            VRAM_START..=VRAM_END if !self.ppu.is_vram_accessible() => OPEN_BUS,
+            OAM_START..=OAM_END if !self.is_oam_accessible() => OPEN_BUS,
+            _ if self.dma.is_conflicting(address) => self.dma.get_value(),
+            ROM_START..=ROM_END | EXTERNAL_RAM_START..=EXTERNAL_RAM_END
+                if self.cartridge.is_none()
+                    && let Some(bus_noise) = &mut self.bus_noise =>
+            {
+                bus_noise.next_u8()
+            }
+            _ => self.peek(address),
+        }
+    }
+
+    fn write_bus(&mut self, address: u16, value: u8) {
+        match address {
+            ROM_START..=ROM_END | EXTERNAL_RAM_START..=EXTERNAL_RAM_END => {
+                if let Some(cartridge) = &mut self.cartridge {
+                    cartridge.write(address, value);
+                }
+            }
+            // Writes are ignored while the PPU is using the memory
+            VRAM_START..=VRAM_END if self.ppu.is_vram_accessible() => self.ppu.write_vram(address, value),
+            WRAM_START..=WRAM_END => self.wram[(address - WRAM_START) as usize] = value,
+            ECHO_RAM_START..=ECHO_RAM_END => self.wram[(address - ECHO_RAM_START) as usize] = value,
+            OAM_START..=OAM_END if self.is_oam_accessible() => self.ppu.write_oam(address, value),
+            DIV_ADDRESS..=TAC_ADDRESS => self.timer.write(address, value),
+            IF_ADDRESS => self.interrupts.write_flag(value),
+            NR10_ADDRESS..=NR52_ADDRESS | WAVE_RAM_START..=WAVE_RAM_END => self.apu.write(address, value),
+            DMA_ADDRESS => self.dma.start(value),
+            LCDC_ADDRESS..=WX_ADDRESS => self.ppu.write(address, value, &mut self.interrupts),
+            UNDOCUMENTED_START..=UNDOCUMENTED_END if let Some(undocumented) = &mut self.undocumented => {
+                undocumented.write(address, value)
+            }
+            HRAM_START..=HRAM_END => self.hram[(address - HRAM_START) as usize] = value,
+            IE_ADDRESS => self.interrupts.set_enable(value),
+            _ => {}
+        }
+    }
+}
 
+impl Default for Circuitry {
+    fn default() -> Self {
+        Self::initialize(HardwareModel::default(), RamInitialization::default(), HardwareVariance::default())
+    }
 }
 
 impl CircuitryInterface for Circuitry {
-    fn tick(&self) {
-        todo!()
+    fn tick(&mut self) {
+        self.tick_observed(|_| {});
+    }
+
+    fn read(&mut self, address: u16) -> u8 {
+        let mut stopwatch = Stopwatch::start(self.profiler.is_enabled());
+        let value = self.read_bus(address);
+        self.profiler.current.bus += stopwatch.lap();
+        value
     }
-}
\ No newline at end of file
+
+    fn write(&mut self, address: u16, value: u8) {
+        let mut stopwatch = Stopwatch::start(self.profiler.is_enabled());
+        self.write_bus(address, value);
+        self.profiler.current.bus += stopwatch.lap();
+    }
+
+    fn get_interrupt_enable(&self) -> u8 {
+        self.interrupts.get_enable()
+    }
+
+    fn get_interrupt_flag(&self) -> u8 {
+        self.interrupts.get_flag()
+    }
+
+    fn acknowledge_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupts.acknowledge(interrupt);
+    }
+}
+
+/// The cartridge isn't part of the state, it has to be inserted before loading
+impl SaveState for Circuitry {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.wram);
+        writer.write_bytes(&self.hram);
+        self.interrupts.save_state(writer);
+        self.timer.save_state(writer);
+        self.ppu.save_state(writer);
+        self.apu.save_state(writer);
+        self.dma.save_state(writer);
+        if let Some(undocumented) = &self.undocumented {
+            undocumented.save_state(writer);
+        }
+        writer.write_bool(self.bus_noise.is_some());
+        writer.write_u64(self.bus_noise.map_or(0, |bus_noise| bus_noise.get_state()));
+        if let Some(cartridge) = &self.cartridge {
+            cartridge.save_state(writer);
+        }
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        reader.read_into(&mut self.wram)?;
+        reader.read_into(&mut self.hram)?;
+        self.interrupts.load_state(reader)?;
+        self.timer.load_state(reader)?;
+        self.ppu.load_state(reader)?;
+        self.apu.load_state(reader)?;
+        self.dma.load_state(reader)?;
+        if let Some(undocumented) = &mut self.undocumented {
+            if reader.has_version(10) {
+                undocumented.load_state(reader)?;
+            } else {
+                *undocumented = UndocumentedRegisters::default();
+                reader.record_default("undocumented registers");
+            }
+        }
+        // Hardware variance is configured by the frontend, the state only continues the sequence if it is enabled
+        if reader.has_version(12) {
+            let bus_noise = reader.read_bool()?;
+            let bus_noise_state = reader.read_u64()?;
+            if bus_noise && let Some(current) = &mut self.bus_noise {
+                *current = SplitMix64::new(bus_noise_state);
+            }
+        } else {
+            reader.record_default("hardware variance of the bus");
+        }
+        if let Some(cartridge) = &mut self.cartridge {
+            cartridge.load_state(reader)?;
+        }
+        Ok(())
+    }
+}
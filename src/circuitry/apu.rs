@@ -0,0 +1,451 @@
+use crate::circuitry::apu::noise::NoiseChannel;
+use crate::circuitry::apu::output::AudioOutput;
+use crate::circuitry::apu::square::SquareChannel;
+use crate::circuitry::apu::wave::WaveChannel;
+use crate::hardware_model::revision::Quirks;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+pub use crate::circuitry::apu::output::{AudioBufferStats, AudioCallback};
+
+mod envelope;
+mod length_counter;
+mod noise;
+mod output;
+mod square;
+mod wave;
+
+// Audio registers according to: https://gbdev.io/pandocs/Audio_Registers.html
+pub const NR10_ADDRESS: u16 = 0xFF10;
+pub const NR11_ADDRESS: u16 = 0xFF11;
+pub const NR21_ADDRESS: u16 = 0xFF16;
+pub const NR31_ADDRESS: u16 = 0xFF1B;
+pub const NR41_ADDRESS: u16 = 0xFF20;
+pub const NR50_ADDRESS: u16 = 0xFF24;
+pub const NR51_ADDRESS: u16 = 0xFF25;
+pub const NR52_ADDRESS: u16 = 0xFF26;
+pub const WAVE_RAM_START: u16 = 0xFF30;
+pub const WAVE_RAM_END: u16 = 0xFF3F;
+
+/// Bits that always read as 1 for NR10-NR52, according to: https://gbdev.io/pandocs/Audio_details.html#register-reading
+const READ_MASKS: [u8; 23] = [
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, // NR10-NR14
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF, // NR20-NR24
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF, // NR30-NR34
+    0xFF, 0xFF, 0x00, 0x00, 0xBF, // NR40-NR44
+    0x00, 0x00, 0x70, // NR50-NR52
+];
+const NR52_POWER: u8 = 0b1000_0000;
+
+/// The frame sequencer is clocked by the falling edge of DIV bit 4
+const FRAME_SEQUENCER_BIT: u16 = 1 << 12;
+const T_CYCLES_PER_M_CYCLE: u16 = 4;
+pub const M_CYCLES_PER_SECOND: u32 = 1_048_576;
+pub const DEFAULT_SAMPLE_RATE: u32 = 48_000;
+/// Factor the output of a disabled DAC decays with per M-cycle
+const DAC_DECAY_FACTOR: f32 = 0.999;
+/// Charge factor of the high-pass capacitor per T-cycle, according to: https://gbdev.io/pandocs/Audio_details.html#obscure-behavior
+const CAPACITOR_CHARGE_FACTOR: f32 = 0.999958;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct StereoSample {
+    pub left: f32,
+    pub right: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioChannel {
+    Pulse1 = 0,
+    Pulse2 = 1,
+    Wave = 2,
+    Noise = 3,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct APU {
+    powered: bool,
+    square1: SquareChannel,
+    square2: SquareChannel,
+    wave: WaveChannel,
+    noise: NoiseChannel,
+    nr50: u8,
+    nr51: u8,
+    frame_sequencer_step: u8,
+    last_frame_sequencer_bit: bool,
+    /// Channels disabled for debugging, they keep running but aren't mixed into the output
+    muted_channels: [bool; 4],
+    /// Analog output of each DAC
+    dac_levels: [f32; 4],
+    sample_rate: u32,
+    sample_phase: u32,
+    accumulated: StereoSample,
+    accumulated_cycles: u32,
+    capacitor: StereoSample,
+    capacitor_factor: f32,
+    output: AudioOutput,
+    /// Samples the output buffer holds, one second of samples if not set
+    buffer_size: Option<usize>,
+    quirks: Quirks,
+}
+
+impl APU {
+    /// The APU in the state the boot ROM leaves it in, after playing the startup sound on channel 1
+    pub fn initialize() -> Self {
+        let mut apu = Self {
+            powered: true,
+            square1: SquareChannel::new(true),
+            square2: SquareChannel::new(false),
+            wave: WaveChannel::new(),
+            noise: NoiseChannel::new(),
+            nr50: 0x77,
+            nr51: 0xF3,
+            frame_sequencer_step: 0,
+            last_frame_sequencer_bit: false,
+            muted_channels: [false; 4],
+            dac_levels: [0.0; 4],
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            sample_phase: 0,
+            accumulated: StereoSample::default(),
+            accumulated_cycles: 0,
+            capacitor: StereoSample::default(),
+            capacitor_factor: 0.0,
+            output: AudioOutput::default(),
+            buffer_size: None,
+            quirks: Quirks::default(),
+        };
+        apu.set_sample_rate(DEFAULT_SAMPLE_RATE);
+
+        // The startup sound has faded out completely, but the channel is still enabled
+        apu.square1.write_register(1, 0xBF);
+        apu.square1.write_register(2, 0x08);
+        apu.square1.write_register(4, 0x80);
+        apu.square1.write_register(2, 0xF3);
+        apu
+    }
+
+    /// Returns the registers and channels to their state after boot.
+    /// The output configuration, muted channels and buffered samples are kept.
+    pub fn reset(&mut self) {
+        let mut apu = Self::initialize();
+        apu.muted_channels = self.muted_channels;
+        apu.buffer_size = self.buffer_size;
+        apu.output = std::mem::take(&mut self.output);
+        apu.quirks = self.quirks;
+        apu.set_sample_rate(self.sample_rate);
+        *self = apu;
+    }
+
+    /// Advances the APU by one M-cycle
+    ///
+    /// # Arguments
+    ///
+    /// * `timer_counter`: The internal counter of the timer after this M-cycle, it drives the frame sequencer
+    pub fn tick(&mut self, timer_counter: u16) {
+        let frame_sequencer_bit = timer_counter & FRAME_SEQUENCER_BIT != 0;
+        if self.powered {
+            if self.last_frame_sequencer_bit && !frame_sequencer_bit {
+                self.clock_frame_sequencer();
+            }
+            self.square1.tick(T_CYCLES_PER_M_CYCLE);
+            self.square2.tick(T_CYCLES_PER_M_CYCLE);
+            self.wave.tick(T_CYCLES_PER_M_CYCLE);
+            self.noise.tick(T_CYCLES_PER_M_CYCLE);
+        }
+        self.last_frame_sequencer_bit = frame_sequencer_bit;
+        self.mix();
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            NR10_ADDRESS..=NR52_ADDRESS => {
+                let index = address - NR10_ADDRESS;
+                READ_MASKS[index as usize] | self.read_register(index)
+            }
+            WAVE_RAM_START..=WAVE_RAM_END => self.wave.read_wave_ram((address - WAVE_RAM_START) as usize),
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            NR52_ADDRESS => self.set_powered(value & NR52_POWER != 0),
+            // Only NR52 and wave RAM are writable while the APU is off, on some revisions the length timers as well
+            NR10_ADDRESS..NR52_ADDRESS if self.powered => {
+                let index = address - NR10_ADDRESS;
+                match index {
+                    0..5 => self.square1.write_register(index, value),
+                    5..10 => self.square2.write_register(index - 5, value),
+                    10..15 => self.wave.write_register(index - 10, value),
+                    15..20 => self.noise.write_register(index - 15, value),
+                    20 => self.nr50 = value,
+                    _ => self.nr51 = value,
+                }
+            }
+            // The duty bits of NR11 and NR21 stay cleared
+            NR11_ADDRESS | NR21_ADDRESS | NR31_ADDRESS | NR41_ADDRESS if self.quirks.apu_length_writable_while_off => {
+                match address {
+                    NR11_ADDRESS => self.square1.write_register(1, value & 0x3F),
+                    NR21_ADDRESS => self.square2.write_register(1, value & 0x3F),
+                    NR31_ADDRESS => self.wave.write_register(1, value),
+                    _ => self.noise.write_register(1, value),
+                }
+            }
+            WAVE_RAM_START..=WAVE_RAM_END => self.wave.write_wave_ram((address - WAVE_RAM_START) as usize, value),
+            _ => {}
+        }
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    pub fn is_powered(&self) -> bool {
+        self.powered
+    }
+
+    /// Whether the channel is currently playing, as reported by NR52
+    pub fn is_channel_active(&self, channel: AudioChannel) -> bool {
+        match channel {
+            AudioChannel::Pulse1 => self.square1.is_enabled(),
+            AudioChannel::Pulse2 => self.square2.is_enabled(),
+            AudioChannel::Wave => self.wave.is_enabled(),
+            AudioChannel::Noise => self.noise.is_enabled(),
+        }
+    }
+
+    /// Digital outputs of the pulse channels as read from PCM12, pulse 2 in the high nibble
+    pub fn get_pcm12(&self) -> u8 {
+        self.square1.get_output() | (self.square2.get_output() << 4)
+    }
+
+    /// Digital outputs of the wave and noise channels as read from PCM34, noise in the high nibble
+    pub fn get_pcm34(&self) -> u8 {
+        self.wave.get_output() | (self.noise.get_output() << 4)
+    }
+
+    /// The analog output of the channel's DAC from -1 to 1, before panning and volume are applied
+    pub fn get_channel_level(&self, channel: AudioChannel) -> f32 {
+        self.dac_levels[channel as usize]
+    }
+
+    pub fn is_channel_muted(&self, channel: AudioChannel) -> bool {
+        self.muted_channels[channel as usize]
+    }
+
+    /// Excludes a channel from the output without affecting the emulation, for debugging individual channels
+    pub fn set_channel_muted(&mut self, channel: AudioChannel, muted: bool) {
+        self.muted_channels[channel as usize] = muted;
+    }
+
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Sets the rate output samples are generated at, clamped between 1 Hz and the M-cycle rate.
+    /// The buffer holds up to one second of samples unless its size was set.
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate.clamp(1, M_CYCLES_PER_SECOND);
+        self.sample_phase = 0;
+        self.capacitor_factor = CAPACITOR_CHARGE_FACTOR.powf(4.0 * M_CYCLES_PER_SECOND as f32 / self.sample_rate as f32);
+        self.output.set_capacity(self.get_buffer_size());
+    }
+
+    pub fn get_buffer_size(&self) -> usize {
+        self.buffer_size.unwrap_or(self.sample_rate as usize)
+    }
+
+    /// Sets how many samples are buffered at most before the oldest ones are dropped, at least 1.
+    /// None buffers one second of samples at the current sample rate.
+    pub fn set_buffer_size(&mut self, buffer_size: Option<usize>) {
+        self.buffer_size = buffer_size;
+        self.output.set_capacity(self.get_buffer_size());
+    }
+
+    /// Takes all samples generated since the last call
+    pub fn drain_samples(&mut self) -> Vec<StereoSample> {
+        self.output.drain()
+    }
+
+    /// Takes up to the given amount of the oldest buffered samples
+    pub fn take_samples(&mut self, count: usize) -> Vec<StereoSample> {
+        self.output.take(count)
+    }
+
+    pub fn get_buffer_stats(&self) -> AudioBufferStats {
+        self.output.get_stats()
+    }
+
+    pub fn reset_buffer_stats(&mut self) {
+        self.output.reset_stats();
+    }
+
+    pub fn get_buffered_sample_count(&self) -> usize {
+        self.output.get_buffered_count()
+    }
+
+    /// Samples are passed to the callback instead of being buffered while it is set
+    pub fn set_callback(&mut self, callback: Option<AudioCallback>) {
+        self.output.set_callback(callback);
+    }
+
+    fn read_register(&self, index: u16) -> u8 {
+        match index {
+            0..5 => self.square1.read_register(index),
+            5..10 => self.square2.read_register(index - 5),
+            10..15 => self.wave.read_register(index - 10),
+            15..20 => self.noise.read_register(index - 15),
+            20 => self.nr50,
+            21 => self.nr51,
+            _ => {
+                let channels = [AudioChannel::Pulse1, AudioChannel::Pulse2, AudioChannel::Wave, AudioChannel::Noise];
+                let active = channels
+                    .iter()
+                    .enumerate()
+                    .fold(0, |bits, (i, channel)| bits | ((self.is_channel_active(*channel) as u8) << i));
+                ((self.powered as u8) << 7) | active
+            }
+        }
+    }
+
+    /// Turning the APU off clears all registers except wave RAM
+    fn set_powered(&mut self, powered: bool) {
+        if powered && !self.powered {
+            self.frame_sequencer_step = 0;
+        } else if !powered && self.powered {
+            self.square1 = SquareChannel::new(true);
+            self.square2 = SquareChannel::new(false);
+            self.wave.reset();
+            self.noise = NoiseChannel::new();
+            self.nr50 = 0;
+            self.nr51 = 0;
+        }
+        self.powered = powered;
+    }
+
+    // Frame sequencer according to: https://gbdev.io/pandocs/Audio_details.html#div-apu
+    fn clock_frame_sequencer(&mut self) {
+        if self.frame_sequencer_step.is_multiple_of(2) {
+            self.square1.clock_length();
+            self.square2.clock_length();
+            self.wave.clock_length();
+            self.noise.clock_length();
+        }
+        if self.frame_sequencer_step == 2 || self.frame_sequencer_step == 6 {
+            self.square1.clock_sweep();
+        }
+        if self.frame_sequencer_step == 7 {
+            self.square1.clock_envelope();
+            self.square2.clock_envelope();
+            self.noise.clock_envelope();
+        }
+        self.frame_sequencer_step = (self.frame_sequencer_step + 1) % 8;
+    }
+
+    /// Adds the current output to the accumulated sample and emits it once the output rate is reached
+    fn mix(&mut self) {
+        let outputs = [
+            (self.square1.is_dac_enabled(), self.square1.get_output()),
+            (self.square2.is_dac_enabled(), self.square2.get_output()),
+            (self.wave.is_dac_enabled(), self.wave.get_output()),
+            (self.noise.is_dac_enabled(), self.noise.get_output()),
+        ];
+
+        let mut sample = StereoSample::default();
+        for (index, (dac_enabled, digital)) in outputs.into_iter().enumerate() {
+            // The DACs map 0-15 linearly to an analog value between -1 and 1, a disabled DAC fades out towards 0
+            let analog = if self.powered && dac_enabled {
+                digital as f32 / 7.5 - 1.0
+            } else {
+                self.dac_levels[index] * DAC_DECAY_FACTOR
+            };
+            self.dac_levels[index] = analog;
+            if self.muted_channels[index] {
+                continue;
+            }
+            if self.nr51 & (0x10 << index) != 0 {
+                sample.left += analog;
+            }
+            if self.nr51 & (0x01 << index) != 0 {
+                sample.right += analog;
+            }
+        }
+        self.accumulated.left += sample.left * (((self.nr50 >> 4) & 0x07) + 1) as f32 / 8.0;
+        self.accumulated.right += sample.right * ((self.nr50 & 0x07) + 1) as f32 / 8.0;
+        self.accumulated_cycles += 1;
+
+        self.sample_phase += self.sample_rate;
+        if self.sample_phase < M_CYCLES_PER_SECOND {
+            return;
+        }
+        self.sample_phase -= M_CYCLES_PER_SECOND;
+
+        // Averaged over the M-cycles since the last sample and scaled from 4 channels to a range of -1 to 1
+        let scale = 4.0 * self.accumulated_cycles as f32;
+        let left = self.high_pass(self.accumulated.left / scale, false);
+        let right = self.high_pass(self.accumulated.right / scale, true);
+        self.accumulated = StereoSample::default();
+        self.accumulated_cycles = 0;
+        self.output.push(StereoSample { left, right });
+    }
+
+    /// Removes the DC offset like the capacitors on the hardware output do
+    fn high_pass(&mut self, input: f32, right: bool) -> f32 {
+        let capacitor = if right { &mut self.capacitor.right } else { &mut self.capacitor.left };
+        let output = input - *capacitor;
+        *capacitor = input - output * self.capacitor_factor;
+        output
+    }
+}
+
+impl Default for APU {
+    fn default() -> Self {
+        Self::initialize()
+    }
+}
+
+/// The sample rate, muted channels and buffered samples are frontend settings and not part of the state.
+/// The sample phase is, so loading a state reproduces the same samples.
+impl SaveState for APU {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.powered);
+        self.square1.save_state(writer);
+        self.square2.save_state(writer);
+        self.wave.save_state(writer);
+        self.noise.save_state(writer);
+        writer.write_u8(self.nr50);
+        writer.write_u8(self.nr51);
+        writer.write_u8(self.frame_sequencer_step);
+        writer.write_bool(self.last_frame_sequencer_bit);
+        let values = [self.accumulated.left, self.accumulated.right, self.capacitor.left, self.capacitor.right];
+        for value in values.into_iter().chain(self.dac_levels) {
+            writer.write_u32(value.to_bits());
+        }
+        writer.write_u32(self.accumulated_cycles);
+        writer.write_u32(self.sample_phase);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.powered = reader.read_bool()?;
+        self.square1.load_state(reader)?;
+        self.square2.load_state(reader)?;
+        self.wave.load_state(reader)?;
+        self.noise.load_state(reader)?;
+        self.nr50 = reader.read_u8()?;
+        self.nr51 = reader.read_u8()?;
+        self.frame_sequencer_step = reader.read_u8()? % 8;
+        self.last_frame_sequencer_bit = reader.read_bool()?;
+        let mut values = [0.0; 8];
+        for value in &mut values {
+            *value = f32::from_bits(reader.read_u32()?);
+            if !value.is_finite() {
+                return Err(SaveStateError::InvalidData);
+            }
+        }
+        let [accumulated_left, accumulated_right, capacitor_left, capacitor_right, levels @ ..] = values;
+        self.dac_levels = levels;
+        self.accumulated = StereoSample { left: accumulated_left, right: accumulated_right };
+        self.capacitor = StereoSample { left: capacitor_left, right: capacitor_right };
+        self.accumulated_cycles = reader.read_u32()?;
+        self.sample_phase = reader.read_u32()? % M_CYCLES_PER_SECOND;
+        Ok(())
+    }
+}
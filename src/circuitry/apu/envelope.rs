@@ -0,0 +1,82 @@
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+const MAX_VOLUME: u8 = 15;
+
+/// Volume envelope of the pulse and noise channels, configured via NRx2
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Envelope {
+    /// The raw NRx2 value
+    register: u8,
+    volume: u8,
+    timer: u8,
+}
+
+impl Envelope {
+    pub fn read(&self) -> u8 {
+        self.register
+    }
+
+    pub fn write(&mut self, value: u8) {
+        self.register = value;
+    }
+
+    /// The DAC is off if the initial volume is 0 and the envelope decreases
+    pub fn is_dac_enabled(&self) -> bool {
+        self.register & 0xF8 != 0
+    }
+
+    pub fn get_volume(&self) -> u8 {
+        self.volume
+    }
+
+    pub fn trigger(&mut self) {
+        self.volume = self.get_initial_volume();
+        self.timer = self.get_period();
+    }
+
+    pub fn clock(&mut self) {
+        let period = self.get_period();
+        if period == 0 {
+            return;
+        }
+
+        self.timer = self.timer.saturating_sub(1);
+        if self.timer > 0 {
+            return;
+        }
+        self.timer = period;
+
+        if self.is_increasing() && self.volume < MAX_VOLUME {
+            self.volume += 1;
+        } else if !self.is_increasing() && self.volume > 0 {
+            self.volume -= 1;
+        }
+    }
+
+    fn get_initial_volume(&self) -> u8 {
+        self.register >> 4
+    }
+
+    fn is_increasing(&self) -> bool {
+        self.register & 0b0000_1000 != 0
+    }
+
+    fn get_period(&self) -> u8 {
+        self.register & 0b0000_0111
+    }
+}
+
+impl SaveState for Envelope {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.register);
+        writer.write_u8(self.volume);
+        writer.write_u8(self.timer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.register = reader.read_u8()?;
+        self.volume = reader.read_u8()?.min(MAX_VOLUME);
+        self.timer = reader.read_u8()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+/// Disables its channel after a number of frame sequencer length clocks
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LengthCounter {
+    enabled: bool,
+    counter: u16,
+    /// 64 for most channels, 256 for the wave channel
+    maximum: u16,
+}
+
+impl LengthCounter {
+    pub fn new(maximum: u16) -> Self {
+        Self {
+            enabled: false,
+            counter: 0,
+            maximum,
+        }
+    }
+
+    /// The written value is the length to skip, the counter runs for the remaining clocks
+    pub fn load(&mut self, value: u8) {
+        self.counter = self.maximum - (value as u16 % self.maximum);
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// An expired counter starts over with the full length
+    pub fn trigger(&mut self) {
+        if self.counter == 0 {
+            self.counter = self.maximum;
+        }
+    }
+
+    /// Returns true if the counter just expired and the channel has to be disabled
+    pub fn clock(&mut self) -> bool {
+        if !self.enabled || self.counter == 0 {
+            return false;
+        }
+        self.counter -= 1;
+        self.counter == 0
+    }
+}
+
+impl SaveState for LengthCounter {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.enabled);
+        writer.write_u16(self.counter);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.enabled = reader.read_bool()?;
+        self.counter = reader.read_u16()?.min(self.maximum);
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+use crate::circuitry::apu::envelope::Envelope;
+use crate::circuitry::apu::length_counter::LengthCounter;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// Noise channel according to: https://gbdev.io/pandocs/Audio_Registers.html#sound-channel-4--noise
+/// A divisor code of 0 acts as a divisor of 8 instead of 0
+const DIVISORS: [u16; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+/// Clock shifts of 14 and 15 stop the LFSR from being clocked
+const MAX_CLOCK_SHIFT: u8 = 13;
+const LFSR_MASK: u16 = 0x7FFF;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseChannel {
+    enabled: bool,
+    /// The raw NR43 value
+    polynomial: u8,
+    /// T-cycles until the LFSR is clocked next
+    timer: u32,
+    /// 15 bit linear feedback shift register
+    lfsr: u16,
+    length: LengthCounter,
+    envelope: Envelope,
+}
+
+impl NoiseChannel {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            polynomial: 0,
+            timer: 0,
+            lfsr: LFSR_MASK,
+            length: LengthCounter::new(64),
+            envelope: Envelope::default(),
+        }
+    }
+
+    /// Reads NR40-NR44 by index, write-only bits read as 0
+    pub fn read_register(&self, index: u16) -> u8 {
+        match index {
+            2 => self.envelope.read(),
+            3 => self.polynomial,
+            4 => (self.length.is_enabled() as u8) << 6,
+            _ => 0,
+        }
+    }
+
+    pub fn write_register(&mut self, index: u16, value: u8) {
+        match index {
+            1 => self.length.load(value & 0x3F),
+            2 => {
+                self.envelope.write(value);
+                if !self.envelope.is_dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => self.polynomial = value,
+            4 => {
+                self.length.set_enabled(value & 0x40 != 0);
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Clocks the LFSR for every period that passed within the given amount of T-cycles
+    pub fn tick(&mut self, cycles: u16) {
+        if self.polynomial >> 4 > MAX_CLOCK_SHIFT {
+            return;
+        }
+
+        let mut remaining = cycles as u32;
+        while remaining >= self.timer {
+            remaining -= self.timer;
+            self.timer = self.get_period();
+            self.clock_lfsr();
+        }
+        self.timer -= remaining;
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_dac_enabled(&self) -> bool {
+        self.envelope.is_dac_enabled()
+    }
+
+    /// Digital output from 0 to 15
+    pub fn get_output(&self) -> u8 {
+        if !self.enabled || self.lfsr & 0x01 != 0 {
+            return 0;
+        }
+        self.envelope.get_volume()
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.envelope.is_dac_enabled();
+        self.length.trigger();
+        self.envelope.trigger();
+        self.timer = self.get_period();
+        self.lfsr = LFSR_MASK;
+    }
+
+    fn clock_lfsr(&mut self) {
+        let feedback = (self.lfsr ^ (self.lfsr >> 1)) & 0x01;
+        self.lfsr = (self.lfsr >> 1) | (feedback << 14);
+        // The short mode also feeds bit 6, which results in a 7 bit sequence.
+        // Switching to it while the lower 7 bits are all 0 locks the sequence until the next trigger.
+        if self.polynomial & 0x08 != 0 {
+            self.lfsr = (self.lfsr & !0x40) | (feedback << 6);
+        }
+    }
+
+    fn get_period(&self) -> u32 {
+        (DIVISORS[(self.polynomial & 0x07) as usize] as u32) << (self.polynomial >> 4)
+    }
+}
+
+impl Default for NoiseChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SaveState for NoiseChannel {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.enabled);
+        writer.write_u8(self.polynomial);
+        writer.write_u32(self.timer);
+        writer.write_u16(self.lfsr);
+        self.length.save_state(writer);
+        self.envelope.save_state(writer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.enabled = reader.read_bool()?;
+        self.polynomial = reader.read_u8()?;
+        self.timer = reader.read_u32()?.min(self.get_period());
+        self.lfsr = reader.read_u16()? & LFSR_MASK;
+        self.length.load_state(reader)?;
+        self.envelope.load_state(reader)
+    }
+}
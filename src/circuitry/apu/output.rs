@@ -0,0 +1,113 @@
+use crate::circuitry::apu::StereoSample;
+use std::collections::VecDeque;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+type Callback = dyn FnMut(StereoSample) + Send;
+
+/// Receives every sample as soon as it is generated, clones of the emulator share the same callback
+#[derive(Clone)]
+pub struct AudioCallback(Arc<Mutex<Callback>>);
+
+impl AudioCallback {
+    pub fn new(callback: impl FnMut(StereoSample) + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(callback)))
+    }
+
+    fn call(&self, sample: StereoSample) {
+        // A callback that panicked before is skipped instead of taking down the emulation
+        if let Ok(mut callback) = self.0.lock() {
+            callback(sample);
+        }
+    }
+}
+
+impl Debug for AudioCallback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AudioCallback")
+    }
+}
+
+/// How well the frontend keeps up with the generated samples, for tuning the buffer size
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct AudioBufferStats {
+    /// Samples dropped because the buffer was full
+    pub overrun_samples: u64,
+    /// Requests for more samples than were buffered
+    pub underruns: u64,
+    /// Samples missing from those requests
+    pub underrun_samples: u64,
+}
+
+/// Generated samples waiting to be consumed by the frontend.
+/// They are not part of the emulated state, so they are ignored when comparing and aren't saved.
+#[derive(Debug, Default, Clone)]
+pub(super) struct AudioOutput {
+    samples: VecDeque<StereoSample>,
+    capacity: usize,
+    callback: Option<AudioCallback>,
+    stats: AudioBufferStats,
+}
+
+impl AudioOutput {
+    /// The oldest samples are dropped if the buffer isn't drained in time
+    pub(super) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+            self.stats.overrun_samples += 1;
+        }
+    }
+
+    pub(super) fn push(&mut self, sample: StereoSample) {
+        if let Some(callback) = &self.callback {
+            callback.call(sample);
+            return;
+        }
+        if self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+            self.stats.overrun_samples += 1;
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub(super) fn drain(&mut self) -> Vec<StereoSample> {
+        self.samples.drain(..).collect()
+    }
+
+    /// Takes up to the given amount of the oldest samples, an underrun is recorded if fewer are buffered
+    pub(super) fn take(&mut self, count: usize) -> Vec<StereoSample> {
+        if self.samples.len() < count {
+            self.stats.underruns += 1;
+            self.stats.underrun_samples += (count - self.samples.len()) as u64;
+        }
+        let available = count.min(self.samples.len());
+        self.samples.drain(..available).collect()
+    }
+
+    pub(super) fn get_stats(&self) -> AudioBufferStats {
+        self.stats
+    }
+
+    pub(super) fn reset_stats(&mut self) {
+        self.stats = AudioBufferStats::default();
+    }
+
+    pub(super) fn get_buffered_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Samples still buffered are passed to the new callback right away
+    pub(super) fn set_callback(&mut self, callback: Option<AudioCallback>) {
+        self.callback = callback;
+        if let Some(callback) = &self.callback {
+            self.samples.drain(..).for_each(|sample| callback.call(sample));
+        }
+    }
+}
+
+impl PartialEq for AudioOutput {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,277 @@
+use crate::circuitry::apu::envelope::Envelope;
+use crate::circuitry::apu::length_counter::LengthCounter;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// Pulse channels according to: https://gbdev.io/pandocs/Audio_Registers.html#sound-channel-1--pulse-with-period-sweep
+const DUTY_PATTERNS: [[u8; 8]; 4] = [
+    [0, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 0, 0, 1, 1, 1],
+    [0, 1, 1, 1, 1, 1, 1, 0],
+];
+const MAX_FREQUENCY: u16 = 2047;
+
+/// Pulse channel 1 and 2, only channel 1 has a frequency sweep
+#[derive(Debug, Clone, PartialEq)]
+pub struct SquareChannel {
+    enabled: bool,
+    sweep: Option<Sweep>,
+    duty: u8,
+    duty_position: u8,
+    /// 11 bit period value written to NRx3 and NRx4
+    frequency: u16,
+    /// T-cycles until the next duty step
+    timer: u16,
+    length: LengthCounter,
+    envelope: Envelope,
+}
+
+impl SquareChannel {
+    pub fn new(has_sweep: bool) -> Self {
+        Self {
+            enabled: false,
+            sweep: has_sweep.then(Sweep::default),
+            duty: 0,
+            duty_position: 0,
+            frequency: 0,
+            timer: 0,
+            length: LengthCounter::new(64),
+            envelope: Envelope::default(),
+        }
+    }
+
+    /// Reads NRx0-NRx4 by index, write-only bits read as 0
+    pub fn read_register(&self, index: u16) -> u8 {
+        match index {
+            0 => self.sweep.as_ref().map_or(0, Sweep::read),
+            1 => self.duty << 6,
+            2 => self.envelope.read(),
+            4 => (self.length.is_enabled() as u8) << 6,
+            _ => 0,
+        }
+    }
+
+    pub fn write_register(&mut self, index: u16, value: u8) {
+        match index {
+            0 => {
+                if let Some(sweep) = &mut self.sweep {
+                    sweep.write(value);
+                }
+            }
+            1 => {
+                self.duty = value >> 6;
+                self.length.load(value & 0x3F);
+            }
+            2 => {
+                self.envelope.write(value);
+                if !self.envelope.is_dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => self.frequency = (self.frequency & 0x700) | value as u16,
+            _ => {
+                self.frequency = (self.frequency & 0xFF) | ((value as u16 & 0x07) << 8);
+                self.length.set_enabled(value & 0x40 != 0);
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+        }
+    }
+
+    /// Advances the duty position by the given amount of T-cycles
+    pub fn tick(&mut self, cycles: u16) {
+        let mut remaining = cycles;
+        while remaining >= self.timer {
+            remaining -= self.timer;
+            self.timer = self.get_period();
+            self.duty_position = (self.duty_position + 1) % 8;
+        }
+        self.timer -= remaining;
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn clock_sweep(&mut self) {
+        let Some(sweep) = &mut self.sweep else {
+            return;
+        };
+        match sweep.clock() {
+            Some(SweepResult::Overflow) => self.enabled = false,
+            Some(SweepResult::Frequency(frequency)) => self.frequency = frequency,
+            None => {}
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_dac_enabled(&self) -> bool {
+        self.envelope.is_dac_enabled()
+    }
+
+    /// Digital output from 0 to 15
+    pub fn get_output(&self) -> u8 {
+        if !self.enabled {
+            return 0;
+        }
+        DUTY_PATTERNS[self.duty as usize][self.duty_position as usize] * self.envelope.get_volume()
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.envelope.is_dac_enabled();
+        self.length.trigger();
+        self.envelope.trigger();
+        self.timer = self.get_period();
+        if let Some(sweep) = &mut self.sweep
+            && sweep.trigger(self.frequency)
+        {
+            self.enabled = false;
+        }
+    }
+
+    fn get_period(&self) -> u16 {
+        (2048 - self.frequency) * 4
+    }
+}
+
+impl SaveState for SquareChannel {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.enabled);
+        if let Some(sweep) = &self.sweep {
+            sweep.save_state(writer);
+        }
+        writer.write_u8(self.duty);
+        writer.write_u8(self.duty_position);
+        writer.write_u16(self.frequency);
+        writer.write_u16(self.timer);
+        self.length.save_state(writer);
+        self.envelope.save_state(writer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.enabled = reader.read_bool()?;
+        if let Some(sweep) = &mut self.sweep {
+            sweep.load_state(reader)?;
+        }
+        self.duty = reader.read_u8()? & 0x03;
+        self.duty_position = reader.read_u8()? % 8;
+        self.frequency = reader.read_u16()? & MAX_FREQUENCY;
+        self.timer = reader.read_u16()?.min(self.get_period());
+        self.length.load_state(reader)?;
+        self.envelope.load_state(reader)
+    }
+}
+
+enum SweepResult {
+    Frequency(u16),
+    Overflow,
+}
+
+/// Frequency sweep of channel 1, configured via NR10
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Sweep {
+    register: u8,
+    enabled: bool,
+    shadow_frequency: u16,
+    timer: u8,
+}
+
+impl Sweep {
+    fn read(&self) -> u8 {
+        self.register
+    }
+
+    fn write(&mut self, value: u8) {
+        self.register = value & 0x7F;
+    }
+
+    /// Returns true if the initial overflow check disables the channel
+    fn trigger(&mut self, frequency: u16) -> bool {
+        self.shadow_frequency = frequency;
+        self.timer = self.get_timer_period();
+        self.enabled = self.get_period() != 0 || self.get_shift() != 0;
+        self.get_shift() != 0 && self.calculate() > MAX_FREQUENCY
+    }
+
+    fn clock(&mut self) -> Option<SweepResult> {
+        self.timer = self.timer.saturating_sub(1);
+        if self.timer > 0 {
+            return None;
+        }
+        self.timer = self.get_timer_period();
+        if !self.enabled || self.get_period() == 0 {
+            return None;
+        }
+
+        let frequency = self.calculate();
+        if frequency > MAX_FREQUENCY {
+            return Some(SweepResult::Overflow);
+        }
+        if self.get_shift() == 0 {
+            return None;
+        }
+
+        self.shadow_frequency = frequency;
+        // The new frequency is checked for an overflow again right away
+        if self.calculate() > MAX_FREQUENCY {
+            return Some(SweepResult::Overflow);
+        }
+        Some(SweepResult::Frequency(frequency))
+    }
+
+    fn calculate(&self) -> u16 {
+        let delta = self.shadow_frequency >> self.get_shift();
+        if self.is_decreasing() {
+            self.shadow_frequency - delta
+        } else {
+            self.shadow_frequency + delta
+        }
+    }
+
+    fn get_period(&self) -> u8 {
+        (self.register >> 4) & 0x07
+    }
+
+    /// A period of 0 is treated as 8 by the timer
+    fn get_timer_period(&self) -> u8 {
+        match self.get_period() {
+            0 => 8,
+            period => period,
+        }
+    }
+
+    fn is_decreasing(&self) -> bool {
+        self.register & 0x08 != 0
+    }
+
+    fn get_shift(&self) -> u8 {
+        self.register & 0x07
+    }
+}
+
+impl SaveState for Sweep {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.register);
+        writer.write_bool(self.enabled);
+        writer.write_u16(self.shadow_frequency);
+        writer.write_u8(self.timer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.register = reader.read_u8()? & 0x7F;
+        self.enabled = reader.read_bool()?;
+        self.shadow_frequency = reader.read_u16()? & MAX_FREQUENCY;
+        self.timer = reader.read_u8()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+use crate::circuitry::apu::length_counter::LengthCounter;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// Wave channel according to: https://gbdev.io/pandocs/Audio_Registers.html#sound-channel-3--wave-output
+pub const WAVE_RAM_SIZE: usize = 16;
+const SAMPLE_COUNT: u8 = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveChannel {
+    enabled: bool,
+    dac_enabled: bool,
+    /// 0 mutes the channel, 1-3 shift the samples right by 0-2 bits
+    volume_code: u8,
+    frequency: u16,
+    /// T-cycles until the next sample is read
+    timer: u16,
+    position: u8,
+    sample_buffer: u8,
+    length: LengthCounter,
+    wave_ram: [u8; WAVE_RAM_SIZE],
+}
+
+impl WaveChannel {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            dac_enabled: false,
+            volume_code: 0,
+            frequency: 0,
+            timer: 0,
+            position: 0,
+            sample_buffer: 0,
+            length: LengthCounter::new(256),
+            wave_ram: [0; WAVE_RAM_SIZE],
+        }
+    }
+
+    /// Reads NR30-NR34 by index, write-only bits read as 0
+    pub fn read_register(&self, index: u16) -> u8 {
+        match index {
+            0 => (self.dac_enabled as u8) << 7,
+            2 => self.volume_code << 5,
+            4 => (self.length.is_enabled() as u8) << 6,
+            _ => 0,
+        }
+    }
+
+    pub fn write_register(&mut self, index: u16, value: u8) {
+        match index {
+            0 => {
+                self.dac_enabled = value & 0x80 != 0;
+                if !self.dac_enabled {
+                    self.enabled = false;
+                }
+            }
+            1 => self.length.load(value),
+            2 => self.volume_code = (value >> 5) & 0x03,
+            3 => self.frequency = (self.frequency & 0x700) | value as u16,
+            _ => {
+                self.frequency = (self.frequency & 0xFF) | ((value as u16 & 0x07) << 8);
+                self.length.set_enabled(value & 0x40 != 0);
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+        }
+    }
+
+    /// Clears everything except wave RAM, which keeps its contents while the APU is off
+    pub fn reset(&mut self) {
+        *self = Self {
+            wave_ram: self.wave_ram,
+            ..Self::new()
+        };
+    }
+
+    pub fn read_wave_ram(&self, index: usize) -> u8 {
+        self.wave_ram[index]
+    }
+
+    pub fn write_wave_ram(&mut self, index: usize, value: u8) {
+        self.wave_ram[index] = value;
+    }
+
+    /// Advances the sample position by the given amount of T-cycles
+    pub fn tick(&mut self, cycles: u16) {
+        let mut remaining = cycles;
+        while remaining >= self.timer {
+            remaining -= self.timer;
+            self.timer = self.get_period();
+            self.position = (self.position + 1) % SAMPLE_COUNT;
+            self.sample_buffer = self.get_sample(self.position);
+        }
+        self.timer -= remaining;
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_dac_enabled(&self) -> bool {
+        self.dac_enabled
+    }
+
+    /// Digital output from 0 to 15
+    pub fn get_output(&self) -> u8 {
+        if !self.enabled || self.volume_code == 0 {
+            return 0;
+        }
+        self.sample_buffer >> (self.volume_code - 1)
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        self.length.trigger();
+        self.timer = self.get_period();
+        self.position = 0;
+    }
+
+    /// Each byte holds two samples, the upper nibble is played first
+    fn get_sample(&self, position: u8) -> u8 {
+        let byte = self.wave_ram[position as usize / 2];
+        if position.is_multiple_of(2) { byte >> 4 } else { byte & 0x0F }
+    }
+
+    fn get_period(&self) -> u16 {
+        (2048 - self.frequency) * 2
+    }
+}
+
+impl Default for WaveChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SaveState for WaveChannel {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.enabled);
+        writer.write_bool(self.dac_enabled);
+        writer.write_u8(self.volume_code);
+        writer.write_u16(self.frequency);
+        writer.write_u16(self.timer);
+        writer.write_u8(self.position);
+        writer.write_u8(self.sample_buffer);
+        self.length.save_state(writer);
+        writer.write_bytes(&self.wave_ram);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.enabled = reader.read_bool()?;
+        self.dac_enabled = reader.read_bool()?;
+        self.volume_code = reader.read_u8()? & 0x03;
+        self.frequency = reader.read_u16()? & 0x7FF;
+        self.timer = reader.read_u16()?.min(self.get_period());
+        self.position = reader.read_u8()? % SAMPLE_COUNT;
+        self.sample_buffer = reader.read_u8()? & 0x0F;
+        self.length.load_state(reader)?;
+        reader.read_into(&mut self.wave_ram)
+    }
+}
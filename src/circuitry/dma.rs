@@ -0,0 +1,160 @@
+use crate::circuitry::ECHO_RAM_START;
+use crate::circuitry::ppu::{OAM_SIZE, OAM_START, VRAM_END, VRAM_START};
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// OAM DMA according to: https://gbdev.io/pandocs/OAM_DMA_Transfer.html
+pub const DMA_ADDRESS: u16 = 0xFF46;
+
+/// M-cycles from writing the DMA register to the first byte being transferred, OAM is still accessible in between
+const STARTUP_DELAY: u8 = 2;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Bus {
+    /// Cartridge and WRAM
+    External,
+    Video,
+    /// OAM, I/O registers and HRAM, which DMA sources from 0xE000 upward are mapped away from
+    Internal,
+}
+
+fn get_bus(address: u16) -> Bus {
+    match address {
+        VRAM_START..=VRAM_END => Bus::Video,
+        OAM_START..=0xFFFF => Bus::Internal,
+        _ => Bus::External,
+    }
+}
+
+/// Copies 160 bytes to OAM, one byte per M-cycle
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OamDma {
+    /// Last value written to the DMA register, the upper byte of the source address
+    source: u8,
+    /// Index of the next byte to transfer, None if no transfer is running.
+    /// After the last byte the transfer blocks OAM for one more M-cycle with the index equal to the OAM size.
+    index: Option<u8>,
+    /// M-cycles until a newly started transfer copies its first byte
+    delay: u8,
+    /// Source and next index of the transfer that was running when this one was started,
+    /// it keeps copying and blocking OAM until the new transfer begins
+    previous: Option<(u8, u8)>,
+    /// The byte transferred last, which is on the bus during the transfer
+    value: u8,
+}
+
+impl OamDma {
+    /// Starting a new transfer while one is running restarts it, the running one continues until the new one begins
+    pub fn start(&mut self, source: u8) {
+        if self.delay == 0 {
+            self.previous = self.index.map(|index| (self.source, index));
+        }
+        self.source = source;
+        self.index = Some(0);
+        self.delay = STARTUP_DELAY;
+    }
+
+    /// Advances the transfer by one M-cycle.
+    ///
+    /// # Returns
+    ///
+    /// The source address and the OAM offset of the byte to copy in this cycle, if any
+    pub fn tick(&mut self) -> Option<(u16, u8)> {
+        if self.delay > 0 {
+            self.delay -= 1;
+            if self.delay > 0 {
+                let (source, index) = self.previous.as_mut()?;
+                return advance(*source, index);
+            }
+            self.previous = None;
+        }
+
+        let index = self.index.as_mut()?;
+        let transfer = advance(self.source, index);
+        if transfer.is_none() {
+            self.index = None;
+        }
+        transfer
+    }
+
+    /// OAM is blocked for the CPU while bytes are being copied, by the previous transfer during a restart
+    pub fn is_active(&self) -> bool {
+        self.index.is_some() && (self.delay == 0 || self.previous.is_some())
+    }
+
+    pub fn get_source(&self) -> u8 {
+        self.source
+    }
+
+    pub fn get_value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn set_value(&mut self, value: u8) {
+        self.value = value;
+    }
+
+    /// Whether the transfer occupies the same bus as the address,
+    /// CPU reads on that bus see the transferred byte instead, according to: https://gbdev.io/pandocs/OAM_DMA_Transfer.html#oam-dma-bus-conflicts
+    pub fn is_conflicting(&self, address: u16) -> bool {
+        let source = match self.previous {
+            Some((source, _)) if self.delay > 0 => source,
+            _ => self.source,
+        };
+        self.is_active() && get_bus(address) == get_bus(get_source_address(source))
+    }
+}
+
+/// Sources from 0xE000 upward read WRAM instead of echo RAM and OAM
+fn get_source_address(source: u8) -> u16 {
+    let address = (source as u16) << 8;
+    if address >= ECHO_RAM_START { address - 0x2000 } else { address }
+}
+
+/// The source address and OAM offset of the next byte of a transfer, None once all bytes were copied
+fn advance(source: u8, index: &mut u8) -> Option<(u16, u8)> {
+    if *index as usize >= OAM_SIZE {
+        return None;
+    }
+    let transfer = (get_source_address(source) | *index as u16, *index);
+    *index += 1;
+    Some(transfer)
+}
+
+impl SaveState for OamDma {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.source);
+        writer.write_bool(self.index.is_some());
+        writer.write_u8(self.index.unwrap_or_default());
+        writer.write_u8(self.delay);
+        writer.write_u8(self.value);
+        writer.write_bool(self.previous.is_some());
+        let (previous_source, previous_index) = self.previous.unwrap_or_default();
+        writer.write_u8(previous_source);
+        writer.write_u8(previous_index);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.source = reader.read_u8()?;
+        let active = reader.read_bool()?;
+        let index = reader.read_u8()?;
+        if index as usize > OAM_SIZE {
+            return Err(SaveStateError::InvalidData);
+        }
+        self.index = active.then_some(index);
+        self.delay = reader.read_u8()?;
+        self.value = reader.read_u8()?;
+        if reader.has_version(14) {
+            let restarted = reader.read_bool()?;
+            let previous_source = reader.read_u8()?;
+            let previous_index = reader.read_u8()?;
+            if previous_index as usize > OAM_SIZE {
+                return Err(SaveStateError::InvalidData);
+            }
+            self.previous = restarted.then_some((previous_source, previous_index));
+        } else {
+            self.previous = None;
+            reader.record_default("restarted OAM DMA transfer");
+        }
+        Ok(())
+    }
+}
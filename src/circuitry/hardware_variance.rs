@@ -0,0 +1,26 @@
+use crate::helpers::random::SplitMix64;
+
+/// Behavior that differs between units and power cycles on real hardware, because it depends on analog effects.
+/// Deterministic emulation keeps every run identical, seeded variance reproduces the randomness of real hardware
+/// while the same seed still produces the same run. Power-on RAM contents are configured separately with
+/// [`RamInitialization`](crate::circuitry::ram_initialization::RamInitialization).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum HardwareVariance {
+    /// OAM starts zeroed and reading the empty cartridge slot returns 0xFF
+    #[default]
+    Deterministic,
+    /// OAM starts with pseudo-random contents, since the boot ROM doesn't clear it,
+    /// and reading the empty cartridge slot returns the noise of the floating data lines
+    Seeded { seed: u64 },
+}
+
+impl HardwareVariance {
+    /// The generator for one source of variance, None if emulation is deterministic.
+    /// Every source uses its own id, so they don't produce identical sequences.
+    pub fn create_rng(&self, source_id: u64) -> Option<SplitMix64> {
+        match *self {
+            Self::Deterministic => None,
+            Self::Seeded { seed } => Some(SplitMix64::new(seed ^ source_id.wrapping_mul(0xD1B5_4A32_D192_ED03))),
+        }
+    }
+}
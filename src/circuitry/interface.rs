@@ -1,3 +1,28 @@
+use crate::circuitry::interrupts::Interrupt;
+
+/// Everything the CPU is connected to.
+/// The CPU drives the system, every M-cycle it spends is forwarded via `tick` so all other components keep in sync.
 pub trait CircuitryInterface {
-    fn tick(&self);
-}
\ No newline at end of file
+    /// Advances all components by a single M-cycle (4 T-cycles)
+    fn tick(&mut self);
+
+    /// A CPU read on the memory bus, this does not take any time by itself
+    fn read(&mut self, address: u16) -> u8;
+
+    /// A CPU write on the memory bus, this does not take any time by itself
+    fn write(&mut self, address: u16, value: u8);
+
+    /// IE register
+    fn get_interrupt_enable(&self) -> u8;
+
+    /// IF register, only the lower 5 bits are relevant
+    fn get_interrupt_flag(&self) -> u8;
+
+    /// Clears the request of an interrupt that is about to be serviced
+    fn acknowledge_interrupt(&mut self, interrupt: Interrupt);
+
+    /// Interrupts that are both requested and enabled
+    fn get_pending_interrupts(&self) -> u8 {
+        self.get_interrupt_enable() & self.get_interrupt_flag() & 0b0001_1111
+    }
+}
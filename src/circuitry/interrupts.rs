@@ -0,0 +1,103 @@
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// Interrupts according to: https://gbdev.io/pandocs/Interrupts.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Interrupt {
+    VBlank,
+    LCD,
+    Timer,
+    Serial,
+    Joypad,
+}
+
+impl Interrupt {
+    /// Ordered by priority, highest first
+    pub const ALL: [Interrupt; 5] = [
+        Interrupt::VBlank,
+        Interrupt::LCD,
+        Interrupt::Timer,
+        Interrupt::Serial,
+        Interrupt::Joypad,
+    ];
+
+    /// Bit of the interrupt in the IE and IF registers
+    pub fn get_bit(&self) -> u8 {
+        match self {
+            Interrupt::VBlank => 0b0000_0001,
+            Interrupt::LCD => 0b0000_0010,
+            Interrupt::Timer => 0b0000_0100,
+            Interrupt::Serial => 0b0000_1000,
+            Interrupt::Joypad => 0b0001_0000,
+        }
+    }
+
+    /// Address the CPU jumps to when servicing the interrupt
+    pub fn get_vector(&self) -> u16 {
+        match self {
+            Interrupt::VBlank => 0x0040,
+            Interrupt::LCD => 0x0048,
+            Interrupt::Timer => 0x0050,
+            Interrupt::Serial => 0x0058,
+            Interrupt::Joypad => 0x0060,
+        }
+    }
+
+    /// The interrupt with the highest priority that is set in the given mask
+    pub fn highest_priority(mask: u8) -> Option<Interrupt> {
+        Self::ALL.into_iter().find(|interrupt| mask & interrupt.get_bit() != 0)
+    }
+}
+
+/// Only the lower 5 bits of IF are used, the upper bits always read as 1
+const IF_UNUSED_BITS: u8 = 0b1110_0000;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct InterruptRegisters {
+    /// IE - which interrupts are allowed to be serviced
+    enable: u8,
+    /// IF - which interrupts are requested
+    flag: u8,
+}
+
+impl InterruptRegisters {
+    pub fn request(&mut self, interrupt: Interrupt) {
+        self.flag |= interrupt.get_bit();
+    }
+
+    pub fn acknowledge(&mut self, interrupt: Interrupt) {
+        self.flag &= !interrupt.get_bit();
+    }
+
+    pub fn get_enable(&self) -> u8 {
+        self.enable
+    }
+
+    pub fn set_enable(&mut self, value: u8) {
+        self.enable = value;
+    }
+
+    pub fn get_flag(&self) -> u8 {
+        self.flag
+    }
+
+    pub fn read_flag(&self) -> u8 {
+        self.flag | IF_UNUSED_BITS
+    }
+
+    pub fn write_flag(&mut self, value: u8) {
+        self.flag = value & !IF_UNUSED_BITS;
+    }
+}
+
+impl SaveState for InterruptRegisters {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.enable);
+        writer.write_u8(self.flag);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.enable = reader.read_u8()?;
+        self.flag = reader.read_u8()? & !IF_UNUSED_BITS;
+        Ok(())
+    }
+}
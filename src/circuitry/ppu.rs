@@ -0,0 +1,556 @@
+use crate::circuitry::interrupts::{Interrupt, InterruptRegisters};
+use crate::circuitry::ppu::layers::{LayerBuffer, LayerPixel};
+use crate::hardware_model::revision::Quirks;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+pub mod layers;
+mod rendering;
+pub mod screen_tiles;
+
+// PPU according to: https://gbdev.io/pandocs/Rendering.html
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+/// Shades from 0 (white) to 3 (black), with the palettes already applied
+pub type FrameBuffer = [u8; SCREEN_WIDTH * SCREEN_HEIGHT];
+
+pub const VRAM_START: u16 = 0x8000;
+pub const VRAM_END: u16 = 0x9FFF;
+pub const OAM_START: u16 = 0xFE00;
+pub const OAM_END: u16 = 0xFE9F;
+pub const LCDC_ADDRESS: u16 = 0xFF40;
+pub const STAT_ADDRESS: u16 = 0xFF41;
+pub const SCY_ADDRESS: u16 = 0xFF42;
+pub const SCX_ADDRESS: u16 = 0xFF43;
+pub const LY_ADDRESS: u16 = 0xFF44;
+pub const LYC_ADDRESS: u16 = 0xFF45;
+pub const BGP_ADDRESS: u16 = 0xFF47;
+pub const OBP0_ADDRESS: u16 = 0xFF48;
+pub const OBP1_ADDRESS: u16 = 0xFF49;
+pub const WY_ADDRESS: u16 = 0xFF4A;
+pub const WX_ADDRESS: u16 = 0xFF4B;
+
+const VRAM_SIZE: usize = 0x2000;
+pub const OAM_SIZE: usize = 0xA0;
+const OBJ_COUNT: usize = 40;
+const ALL_OBJECTS_MASK: u64 = (1 << OBJ_COUNT) - 1;
+
+// Timing according to: https://gbdev.io/pandocs/STAT.html#stat-modes
+pub const DOTS_PER_LINE: u16 = 456;
+pub const LINES_PER_FRAME: u8 = 154;
+const OAM_SCAN_DOTS: u16 = 80;
+/// The drawing length actually varies with scrolling, the window and sprites, the minimum is used
+const DRAWING_DOTS: u16 = 172;
+const DOTS_PER_TICK: u16 = 4;
+/// LY increments at the start of a line, but the mode only changes this many dots later on lines 1 to 144.
+/// Until then STAT still reports HBlank, while the OAM interrupt source is already active, also on line 144.
+const LINE_START_DOTS: u16 = 4;
+/// Line 153 only reads as LY 153 for this many dots, LY reads 0 and is compared as 0 for the rest of it
+const LAST_LINE_LY_DOTS: u16 = 4;
+
+const LCDC_ENABLE: u8 = 0b1000_0000;
+const STAT_LYC_INTERRUPT: u8 = 0b0100_0000;
+const STAT_OAM_INTERRUPT: u8 = 0b0010_0000;
+const STAT_VBLANK_INTERRUPT: u8 = 0b0001_0000;
+const STAT_HBLANK_INTERRUPT: u8 = 0b0000_1000;
+const STAT_LYC_EQUAL: u8 = 0b0000_0100;
+const STAT_WRITABLE_BITS: u8 = 0b0111_1000;
+const STAT_UNUSED_BITS: u8 = 0b1000_0000;
+
+// Register values after the boot ROM according to: https://gbdev.io/pandocs/Power_Up_Sequence.html#hardware-registers
+const INITIAL_LCDC: u8 = 0x91;
+const INITIAL_BGP: u8 = 0xFC;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum PpuMode {
+    #[default]
+    HBlank = 0,
+    VBlank = 1,
+    OamScan = 2,
+    Drawing = 3,
+}
+
+/// Describes how a frame was rendered, completed together with the frame buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameMetadata {
+    /// The LCD was turned off, the frame buffer is blank
+    pub lcd_off: bool,
+    /// Lines the window was drawn on
+    pub window_lines: [bool; SCREEN_HEIGHT],
+    /// Lines with more than 10 objects, of which only the first 10 were drawn
+    pub sprite_overflow_lines: [bool; SCREEN_HEIGHT],
+    /// The OAM entries that overlapped each line but weren't drawn because of the limit, bit n is entry n
+    pub dropped_objects: [u64; SCREEN_HEIGHT],
+}
+
+impl FrameMetadata {
+    fn lcd_off() -> Self {
+        Self {
+            lcd_off: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn get_overflow_line_count(&self) -> usize {
+        self.sprite_overflow_lines.iter().filter(|&&overflow| overflow).count()
+    }
+
+    /// Total objects not drawn on any line, an object dropped on several lines is counted once per line
+    pub fn get_dropped_object_count(&self) -> u32 {
+        self.dropped_objects.iter().map(|mask| mask.count_ones()).sum()
+    }
+
+    /// The OAM indices of the objects that weren't drawn on the line, in OAM order
+    pub fn get_dropped_objects(&self, line: u8) -> impl Iterator<Item = u8> + '_ {
+        let mask = self.dropped_objects.get(line as usize).copied().unwrap_or_default();
+        (0..OBJ_COUNT as u8).filter(move |index| mask & (1 << index) != 0)
+    }
+
+    fn save_lines(lines: &[bool; SCREEN_HEIGHT], writer: &mut StateWriter) {
+        lines.iter().for_each(|&line| writer.write_bool(line));
+    }
+
+    fn load_lines(lines: &mut [bool; SCREEN_HEIGHT], reader: &mut StateReader) -> Result<(), SaveStateError> {
+        for line in lines.iter_mut() {
+            *line = reader.read_bool()?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for FrameMetadata {
+    fn default() -> Self {
+        Self {
+            lcd_off: false,
+            window_lines: [false; SCREEN_HEIGHT],
+            sprite_overflow_lines: [false; SCREEN_HEIGHT],
+            dropped_objects: [0; SCREEN_HEIGHT],
+        }
+    }
+}
+
+impl SaveState for FrameMetadata {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.lcd_off);
+        Self::save_lines(&self.window_lines, writer);
+        Self::save_lines(&self.sprite_overflow_lines, writer);
+        self.dropped_objects.iter().for_each(|&mask| writer.write_u64(mask));
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.lcd_off = reader.read_bool()?;
+        Self::load_lines(&mut self.window_lines, reader)?;
+        Self::load_lines(&mut self.sprite_overflow_lines, reader)?;
+        if !reader.has_version(11) {
+            self.dropped_objects = [0; SCREEN_HEIGHT];
+            reader.record_default("dropped objects of the frame metadata");
+            return Ok(());
+        }
+        for mask in self.dropped_objects.iter_mut() {
+            *mask = reader.read_u64()? & ALL_OBJECTS_MASK;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PPU {
+    vram: Vec<u8>,
+    oam: Vec<u8>,
+    lcdc: u8,
+    /// Only the interrupt select bits, the mode and LYC flag are derived when reading
+    stat: u8,
+    scy: u8,
+    scx: u8,
+    ly: u8,
+    lyc: u8,
+    bgp: u8,
+    obp0: u8,
+    obp1: u8,
+    wy: u8,
+    wx: u8,
+    mode: PpuMode,
+    /// Dot within the current line
+    dot: u16,
+    /// The window has its own line counter, which only advances on lines it was drawn on
+    window_line: u8,
+    /// WY matched LY at some point during this frame
+    window_triggered: bool,
+    /// The combined STAT interrupt sources, the interrupt is only requested on a rising edge
+    stat_line: bool,
+    frame_buffer: Box<FrameBuffer>,
+    frame_count: u64,
+    /// Metadata of the frame currently being drawn
+    metadata: FrameMetadata,
+    /// Metadata of the last completed frame
+    frame_metadata: FrameMetadata,
+    quirks: Quirks,
+    /// The layer of every pixel, only recorded while enabled since it is meant for debugging
+    layer_buffer: Option<Box<LayerBuffer>>,
+}
+
+impl PPU {
+    pub fn initialize() -> Self {
+        Self {
+            vram: vec![0; VRAM_SIZE],
+            oam: vec![0; OAM_SIZE],
+            lcdc: INITIAL_LCDC,
+            stat: 0,
+            scy: 0,
+            scx: 0,
+            ly: 0,
+            lyc: 0,
+            bgp: INITIAL_BGP,
+            obp0: 0xFF,
+            obp1: 0xFF,
+            wy: 0,
+            wx: 0,
+            mode: PpuMode::OamScan,
+            dot: 0,
+            window_line: 0,
+            // WY is 0, which matches the first line
+            window_triggered: true,
+            stat_line: false,
+            frame_buffer: Box::new([0; SCREEN_WIDTH * SCREEN_HEIGHT]),
+            frame_count: 0,
+            metadata: FrameMetadata::default(),
+            frame_metadata: FrameMetadata::default(),
+            quirks: Quirks::default(),
+            layer_buffer: None,
+        }
+    }
+
+    /// Returns the registers, VRAM and OAM to their state after boot and restarts the current frame.
+    /// The frame count and the last completed frame are kept, layer tracking stays enabled.
+    pub fn reset(&mut self) {
+        let mut ppu = Self::initialize();
+        std::mem::swap(&mut ppu.frame_buffer, &mut self.frame_buffer);
+        ppu.frame_count = self.frame_count;
+        ppu.frame_metadata = std::mem::take(&mut self.frame_metadata);
+        ppu.quirks = self.quirks;
+        ppu.layer_buffer = self.layer_buffer.take();
+        *self = ppu;
+    }
+
+    /// Advances the PPU by one M-cycle
+    pub fn tick(&mut self, interrupts: &mut InterruptRegisters) {
+        self.tick_observed(interrupts, |_| {});
+    }
+
+    /// Advances the PPU by one M-cycle and passes its state to the observer after every dot
+    pub fn tick_observed(&mut self, interrupts: &mut InterruptRegisters, mut observer: impl FnMut(&PPU)) {
+        for _ in 0..DOTS_PER_TICK {
+            if self.is_enabled() {
+                self.tick_dot(interrupts);
+            }
+            observer(self);
+        }
+    }
+
+    /// Reads a register without any side effects
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            LCDC_ADDRESS => self.lcdc,
+            STAT_ADDRESS => self.read_stat(),
+            SCY_ADDRESS => self.scy,
+            SCX_ADDRESS => self.scx,
+            LY_ADDRESS => self.read_ly(),
+            LYC_ADDRESS => self.lyc,
+            BGP_ADDRESS => self.bgp,
+            OBP0_ADDRESS => self.obp0,
+            OBP1_ADDRESS => self.obp1,
+            WY_ADDRESS => self.wy,
+            WX_ADDRESS => self.wx,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8, interrupts: &mut InterruptRegisters) {
+        match address {
+            LCDC_ADDRESS => self.write_lcdc(value),
+            STAT_ADDRESS => {
+                if self.quirks.stat_write_interrupt {
+                    // For a moment every source except the OAM scan is selected
+                    self.stat = STAT_HBLANK_INTERRUPT | STAT_VBLANK_INTERRUPT | STAT_LYC_INTERRUPT;
+                    self.update_stat_line(interrupts);
+                }
+                self.stat = value & STAT_WRITABLE_BITS;
+            }
+            SCY_ADDRESS => self.scy = value,
+            SCX_ADDRESS => self.scx = value,
+            // LY is read-only
+            LY_ADDRESS => {}
+            LYC_ADDRESS => self.lyc = value,
+            BGP_ADDRESS => self.bgp = value,
+            OBP0_ADDRESS => self.obp0 = value,
+            OBP1_ADDRESS => self.obp1 = value,
+            WY_ADDRESS => self.wy = value,
+            WX_ADDRESS => self.wx = value,
+            _ => {}
+        }
+        // Changing LYC or the interrupt selection can raise the STAT line immediately
+        self.update_stat_line(interrupts);
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    pub fn read_vram(&self, address: u16) -> u8 {
+        self.vram[(address - VRAM_START) as usize]
+    }
+
+    pub fn write_vram(&mut self, address: u16, value: u8) {
+        self.vram[(address - VRAM_START) as usize] = value;
+    }
+
+    pub fn read_oam(&self, address: u16) -> u8 {
+        self.oam[(address - OAM_START) as usize]
+    }
+
+    pub fn write_oam(&mut self, address: u16, value: u8) {
+        self.oam[(address - OAM_START) as usize] = value;
+    }
+
+    /// The CPU can't access VRAM while the PPU is drawing
+    pub fn is_vram_accessible(&self) -> bool {
+        self.mode != PpuMode::Drawing
+    }
+
+    /// The CPU can't access OAM while the PPU is scanning or drawing
+    pub fn is_oam_accessible(&self) -> bool {
+        !matches!(self.mode, PpuMode::OamScan | PpuMode::Drawing)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.lcdc & LCDC_ENABLE != 0
+    }
+
+    pub fn get_mode(&self) -> PpuMode {
+        self.mode
+    }
+
+    /// The line being processed, which differs from the value of LY for most of line 153
+    pub fn get_ly(&self) -> u8 {
+        self.ly
+    }
+
+    /// LY as read by the CPU and compared to LYC
+    fn read_ly(&self) -> u8 {
+        if self.ly == LINES_PER_FRAME - 1 && self.dot >= LAST_LINE_LY_DOTS { 0 } else { self.ly }
+    }
+
+    /// The first dots of lines 1 to 144, before the mode of the new line begins
+    fn is_line_start(&self) -> bool {
+        self.ly != 0 && self.ly as usize <= SCREEN_HEIGHT && self.dot < LINE_START_DOTS
+    }
+
+    /// STAT as read by the CPU
+    pub fn get_stat(&self) -> u8 {
+        self.read_stat()
+    }
+
+    pub fn get_dot(&self) -> u16 {
+        self.dot
+    }
+
+    pub fn get_frame_buffer(&self) -> &FrameBuffer {
+        &self.frame_buffer
+    }
+
+    /// Records which layer drew each pixel from the next line on, the buffer is dropped when disabled
+    pub fn set_layer_tracking(&mut self, enabled: bool) {
+        if !enabled {
+            self.layer_buffer = None;
+        } else if self.layer_buffer.is_none() {
+            self.layer_buffer = Some(Box::new([LayerPixel::default(); SCREEN_WIDTH * SCREEN_HEIGHT]));
+        }
+    }
+
+    /// Like the frame buffer, the layers are complete when a frame was completed
+    pub fn get_layer_buffer(&self) -> Option<&LayerBuffer> {
+        self.layer_buffer.as_deref()
+    }
+
+    pub fn get_frame_metadata(&self) -> &FrameMetadata {
+        &self.frame_metadata
+    }
+
+    /// Incremented every time the PPU enters VBlank, which is when the frame buffer is complete
+    pub fn get_frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn get_vram(&self) -> &[u8] {
+        &self.vram
+    }
+
+    pub fn get_oam(&self) -> &[u8] {
+        &self.oam
+    }
+
+    fn tick_dot(&mut self, interrupts: &mut InterruptRegisters) {
+        self.dot += 1;
+        if self.dot == DOTS_PER_LINE {
+            self.dot = 0;
+            self.ly += 1;
+            if self.ly == LINES_PER_FRAME {
+                self.ly = 0;
+                self.window_line = 0;
+                self.window_triggered = false;
+            }
+        }
+
+        let mode = if self.is_line_start() {
+            PpuMode::HBlank
+        } else if self.ly as usize >= SCREEN_HEIGHT {
+            PpuMode::VBlank
+        } else if self.dot < OAM_SCAN_DOTS {
+            PpuMode::OamScan
+        } else if self.dot < OAM_SCAN_DOTS + DRAWING_DOTS {
+            PpuMode::Drawing
+        } else {
+            PpuMode::HBlank
+        };
+
+        if mode != self.mode {
+            self.enter_mode(mode, interrupts);
+        }
+        self.update_stat_line(interrupts);
+    }
+
+    fn enter_mode(&mut self, mode: PpuMode, interrupts: &mut InterruptRegisters) {
+        self.mode = mode;
+        match mode {
+            PpuMode::OamScan => {
+                if self.ly == self.wy {
+                    self.window_triggered = true;
+                }
+            }
+            PpuMode::HBlank => self.render_line(),
+            PpuMode::VBlank => {
+                self.frame_count += 1;
+                self.frame_metadata = std::mem::take(&mut self.metadata);
+                interrupts.request(Interrupt::VBlank);
+            }
+            PpuMode::Drawing => {}
+        }
+    }
+
+    fn write_lcdc(&mut self, value: u8) {
+        let was_enabled = self.is_enabled();
+        self.lcdc = value;
+        match (was_enabled, self.is_enabled()) {
+            // The screen goes blank and the PPU resets to the start of the frame
+            (true, false) => {
+                self.ly = 0;
+                self.dot = 0;
+                self.mode = PpuMode::HBlank;
+                self.window_line = 0;
+                self.window_triggered = false;
+                self.frame_buffer.fill(0);
+                if let Some(layer_buffer) = &mut self.layer_buffer {
+                    layer_buffer.fill(LayerPixel::default());
+                }
+                self.metadata = FrameMetadata::default();
+                self.frame_metadata = FrameMetadata::lcd_off();
+            }
+            (false, true) => {
+                self.mode = PpuMode::OamScan;
+                self.window_triggered = self.wy == 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn read_stat(&self) -> u8 {
+        let mut stat = STAT_UNUSED_BITS | self.stat;
+        if self.is_enabled() {
+            stat |= self.mode as u8;
+            if self.read_ly() == self.lyc {
+                stat |= STAT_LYC_EQUAL;
+            }
+        }
+        stat
+    }
+
+    /// Requests the STAT interrupt if any of the selected sources became active
+    fn update_stat_line(&mut self, interrupts: &mut InterruptRegisters) {
+        let stat_line = self.is_enabled()
+            && ((self.stat & STAT_LYC_INTERRUPT != 0 && self.read_ly() == self.lyc)
+                || (self.stat & STAT_OAM_INTERRUPT != 0 && (self.mode == PpuMode::OamScan || self.is_line_start()))
+                || (self.stat & STAT_VBLANK_INTERRUPT != 0 && self.mode == PpuMode::VBlank)
+                || (self.stat & STAT_HBLANK_INTERRUPT != 0 && self.mode == PpuMode::HBlank));
+
+        if stat_line && !self.stat_line {
+            interrupts.request(Interrupt::LCD);
+        }
+        self.stat_line = stat_line;
+    }
+}
+
+impl SaveState for PPU {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bytes(&self.vram);
+        writer.write_bytes(&self.oam);
+        writer.write_u8(self.lcdc);
+        writer.write_u8(self.stat);
+        writer.write_u8(self.scy);
+        writer.write_u8(self.scx);
+        writer.write_u8(self.ly);
+        writer.write_u8(self.lyc);
+        writer.write_u8(self.bgp);
+        writer.write_u8(self.obp0);
+        writer.write_u8(self.obp1);
+        writer.write_u8(self.wy);
+        writer.write_u8(self.wx);
+        writer.write_u8(self.mode as u8);
+        writer.write_u16(self.dot);
+        writer.write_u8(self.window_line);
+        writer.write_bool(self.window_triggered);
+        writer.write_bool(self.stat_line);
+        writer.write_bytes(self.frame_buffer.as_slice());
+        writer.write_u64(self.frame_count);
+        self.metadata.save_state(writer);
+        self.frame_metadata.save_state(writer);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        reader.read_into(&mut self.vram)?;
+        reader.read_into(&mut self.oam)?;
+        self.lcdc = reader.read_u8()?;
+        self.stat = reader.read_u8()? & STAT_WRITABLE_BITS;
+        self.scy = reader.read_u8()?;
+        self.scx = reader.read_u8()?;
+        self.ly = reader.read_u8()?;
+        self.lyc = reader.read_u8()?;
+        self.bgp = reader.read_u8()?;
+        self.obp0 = reader.read_u8()?;
+        self.obp1 = reader.read_u8()?;
+        self.wy = reader.read_u8()?;
+        self.wx = reader.read_u8()?;
+        self.mode = match reader.read_u8()? {
+            0 => PpuMode::HBlank,
+            1 => PpuMode::VBlank,
+            2 => PpuMode::OamScan,
+            3 => PpuMode::Drawing,
+            _ => return Err(SaveStateError::InvalidData),
+        };
+        self.dot = reader.read_u16()?;
+        if self.dot >= DOTS_PER_LINE || self.ly >= LINES_PER_FRAME {
+            return Err(SaveStateError::InvalidData);
+        }
+        self.window_line = reader.read_u8()?;
+        self.window_triggered = reader.read_bool()?;
+        self.stat_line = reader.read_bool()?;
+        reader.read_into(self.frame_buffer.as_mut_slice())?;
+        self.frame_count = reader.read_u64()?;
+        self.metadata.load_state(reader)?;
+        self.frame_metadata.load_state(reader)
+    }
+}
+
+impl Default for PPU {
+    fn default() -> Self {
+        Self::initialize()
+    }
+}
@@ -0,0 +1,63 @@
+use crate::circuitry::ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// Which layer produced the pixel in the frame buffer
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PixelLayer {
+    /// Also used while the background is disabled or the LCD is off
+    #[default]
+    Background,
+    Window,
+    /// An object using OBP0
+    Object0,
+    /// An object using OBP1
+    Object1,
+}
+
+/// The source of a pixel, with the color index before the palette was applied
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct LayerPixel {
+    pub layer: PixelLayer,
+    pub color: u8,
+}
+
+pub type LayerBuffer = [LayerPixel; SCREEN_WIDTH * SCREEN_HEIGHT];
+
+pub type Rgb = [u8; 3];
+
+/// Colors for each layer by color index, for visualizing which layer drew each pixel instead of the game palettes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LayerPalettes {
+    pub background: [Rgb; 4],
+    pub window: [Rgb; 4],
+    pub object0: [Rgb; 4],
+    pub object1: [Rgb; 4],
+}
+
+impl LayerPalettes {
+    pub fn get_color(&self, pixel: LayerPixel) -> Rgb {
+        let palette = match pixel.layer {
+            PixelLayer::Background => &self.background,
+            PixelLayer::Window => &self.window,
+            PixelLayer::Object0 => &self.object0,
+            PixelLayer::Object1 => &self.object1,
+        };
+        palette[(pixel.color & 0x03) as usize]
+    }
+
+    /// Colors every pixel of the layer buffer, row by row
+    pub fn colorize(&self, buffer: &LayerBuffer) -> Vec<Rgb> {
+        buffer.iter().map(|&pixel| self.get_color(pixel)).collect()
+    }
+}
+
+/// Grays for the background, greens for the window, reds for OBP0 and blues for OBP1
+impl Default for LayerPalettes {
+    fn default() -> Self {
+        Self {
+            background: [[0xE0, 0xE0, 0xE0], [0xA8, 0xA8, 0xA8], [0x60, 0x60, 0x60], [0x20, 0x20, 0x20]],
+            window: [[0xC8, 0xF0, 0xC8], [0x78, 0xD0, 0x78], [0x30, 0x90, 0x30], [0x08, 0x48, 0x08]],
+            object0: [[0xF8, 0xC8, 0xC8], [0xF0, 0x78, 0x78], [0xC0, 0x28, 0x28], [0x68, 0x08, 0x08]],
+            object1: [[0xC8, 0xD8, 0xF8], [0x78, 0x98, 0xF0], [0x28, 0x48, 0xC0], [0x08, 0x18, 0x68]],
+        }
+    }
+}
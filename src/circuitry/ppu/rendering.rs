@@ -0,0 +1,212 @@
+use crate::circuitry::ppu::layers::{LayerPixel, PixelLayer};
+use crate::circuitry::ppu::screen_tiles::{SCREEN_COLUMNS, SCREEN_ROWS, ScreenTiles};
+use crate::circuitry::ppu::{OBJ_COUNT, PPU, SCREEN_WIDTH, VRAM_START};
+
+// LCDC bits according to: https://gbdev.io/pandocs/LCDC.html
+const LCDC_BG_WINDOW_ENABLE: u8 = 0b0000_0001;
+const LCDC_OBJ_ENABLE: u8 = 0b0000_0010;
+const LCDC_OBJ_SIZE: u8 = 0b0000_0100;
+const LCDC_BG_TILE_MAP: u8 = 0b0000_1000;
+const LCDC_TILE_DATA: u8 = 0b0001_0000;
+const LCDC_WINDOW_ENABLE: u8 = 0b0010_0000;
+const LCDC_WINDOW_TILE_MAP: u8 = 0b0100_0000;
+
+// OAM attributes according to: https://gbdev.io/pandocs/OAM.html
+const OBJ_BG_PRIORITY: u8 = 0b1000_0000;
+const OBJ_Y_FLIP: u8 = 0b0100_0000;
+const OBJ_X_FLIP: u8 = 0b0010_0000;
+const OBJ_PALETTE: u8 = 0b0001_0000;
+const OBJS_PER_LINE: usize = 10;
+const OBJ_Y_OFFSET: i16 = 16;
+const OBJ_X_OFFSET: i16 = 8;
+
+const TILE_MAP_LOW: u16 = 0x9800;
+const TILE_MAP_HIGH: u16 = 0x9C00;
+const TILE_DATA_UNSIGNED: u16 = 0x8000;
+const TILE_DATA_SIGNED: u16 = 0x9000;
+const TILE_SIZE: u16 = 16;
+const TILE_MAP_WIDTH: u16 = 32;
+const WINDOW_X_OFFSET: u8 = 7;
+
+#[derive(Debug, Copy, Clone)]
+struct Object {
+    y: i16,
+    x: i16,
+    tile: u8,
+    attributes: u8,
+}
+
+impl PPU {
+    /// Renders the current line into the frame buffer, called when entering HBlank
+    pub(super) fn render_line(&mut self) {
+        let mut bg_colors = [0u8; SCREEN_WIDTH];
+        let line_offset = self.ly as usize * SCREEN_WIDTH;
+
+        // On the DMG the BG/window enable bit blanks both layers to white
+        let mut window_start = SCREEN_WIDTH;
+        if self.lcdc & LCDC_BG_WINDOW_ENABLE != 0 {
+            self.render_background(&mut bg_colors);
+            window_start = self.render_window(&mut bg_colors).unwrap_or(SCREEN_WIDTH);
+        }
+        for (x, &color) in bg_colors.iter().enumerate() {
+            self.frame_buffer[line_offset + x] = apply_palette(self.bgp, color);
+        }
+        if let Some(layer_buffer) = &mut self.layer_buffer {
+            for (x, &color) in bg_colors.iter().enumerate() {
+                let layer = if x >= window_start { PixelLayer::Window } else { PixelLayer::Background };
+                layer_buffer[line_offset + x] = LayerPixel { layer, color };
+            }
+        }
+
+        if self.lcdc & LCDC_OBJ_ENABLE != 0 {
+            self.render_objects(&bg_colors);
+        }
+    }
+
+    /// The tile at the center of every screen cell with the current registers, changes during the frame aren't
+    /// taken into account. The window is assumed to start at WY, as if it wasn't toggled during the frame.
+    /// None while the BG/window enable bit blanks both layers.
+    pub fn get_screen_tiles(&self) -> Option<ScreenTiles> {
+        if self.lcdc & LCDC_BG_WINDOW_ENABLE == 0 {
+            return None;
+        }
+
+        let bg_map = if self.lcdc & LCDC_BG_TILE_MAP != 0 { TILE_MAP_HIGH } else { TILE_MAP_LOW };
+        let window_map = if self.lcdc & LCDC_WINDOW_TILE_MAP != 0 { TILE_MAP_HIGH } else { TILE_MAP_LOW };
+        let window_enabled = self.lcdc & LCDC_WINDOW_ENABLE != 0;
+        let window_x = self.wx as i16 - WINDOW_X_OFFSET as i16;
+
+        let mut tiles = [[0; SCREEN_COLUMNS]; SCREEN_ROWS];
+        for (row, row_tiles) in tiles.iter_mut().enumerate() {
+            for (column, tile) in row_tiles.iter_mut().enumerate() {
+                let x = (column * 8 + 4) as i16;
+                let y = (row * 8 + 4) as u8;
+                let (tile_map, map_x, map_y) = if window_enabled && y >= self.wy && x >= window_x {
+                    (window_map, (x - window_x) as u8, y - self.wy)
+                } else {
+                    (bg_map, (x as u8).wrapping_add(self.scx), y.wrapping_add(self.scy))
+                };
+                *tile = self.read_vram(tile_map + (map_y as u16 / 8) * TILE_MAP_WIDTH + map_x as u16 / 8);
+            }
+        }
+        Some(ScreenTiles::new(tiles))
+    }
+
+    fn render_background(&self, bg_colors: &mut [u8; SCREEN_WIDTH]) {
+        let tile_map = if self.lcdc & LCDC_BG_TILE_MAP != 0 { TILE_MAP_HIGH } else { TILE_MAP_LOW };
+        let y = self.ly.wrapping_add(self.scy);
+        for (x, color) in bg_colors.iter_mut().enumerate() {
+            *color = self.get_tile_map_color(tile_map, (x as u8).wrapping_add(self.scx), y);
+        }
+    }
+
+    /// Returns the first X the window was drawn at, if it was drawn on this line
+    fn render_window(&mut self, bg_colors: &mut [u8; SCREEN_WIDTH]) -> Option<usize> {
+        if self.lcdc & LCDC_WINDOW_ENABLE == 0 || !self.window_triggered {
+            return None;
+        }
+
+        let start = self.wx as i16 - WINDOW_X_OFFSET as i16;
+        if start >= SCREEN_WIDTH as i16 {
+            return None;
+        }
+
+        let tile_map = if self.lcdc & LCDC_WINDOW_TILE_MAP != 0 { TILE_MAP_HIGH } else { TILE_MAP_LOW };
+        for (x, color) in bg_colors.iter_mut().enumerate().skip(start.max(0) as usize) {
+            let window_x = (x as i16 - start) as u8;
+            *color = self.get_tile_map_color(tile_map, window_x, self.window_line);
+        }
+        self.metadata.window_lines[self.ly as usize] = true;
+        self.window_line = self.window_line.wrapping_add(1);
+        Some(start.max(0) as usize)
+    }
+
+    fn render_objects(&mut self, bg_colors: &[u8; SCREEN_WIDTH]) {
+        let height: i16 = if self.lcdc & LCDC_OBJ_SIZE != 0 { 16 } else { 8 };
+        let line = self.ly as i16;
+
+        // The first 10 objects in OAM order that overlap the line are drawn
+        let mut objects: Vec<(usize, Object)> = (0..OBJ_COUNT)
+            .map(|index| (index, self.get_object(index)))
+            .filter(|(_, object)| line >= object.y && line < object.y + height)
+            .collect();
+        if objects.len() > OBJS_PER_LINE {
+            let dropped = objects.split_off(OBJS_PER_LINE);
+            self.metadata.sprite_overflow_lines[self.ly as usize] = true;
+            let mask = dropped.iter().fold(0, |mask, (index, _)| mask | (1 << index));
+            self.metadata.dropped_objects[self.ly as usize] = mask;
+        }
+        let mut objects: Vec<Object> = objects.into_iter().map(|(_, object)| object).collect();
+        // Objects with a smaller X are drawn above the others, ties are resolved by OAM order which the stable sort keeps
+        objects.sort_by_key(|object| object.x);
+
+        let line_offset = self.ly as usize * SCREEN_WIDTH;
+        for (x, &bg_color) in bg_colors.iter().enumerate() {
+            let x = x as i16;
+            let pixel = objects
+                .iter()
+                .filter(|object| x >= object.x && x < object.x + 8)
+                .map(|object| (object, self.get_object_color(object, x - object.x, line - object.y, height)))
+                .find(|(_, color)| *color != 0);
+
+            let Some((object, color)) = pixel else {
+                continue;
+            };
+            if object.attributes & OBJ_BG_PRIORITY != 0 && bg_color != 0 {
+                continue;
+            }
+
+            let (palette, layer) = if object.attributes & OBJ_PALETTE != 0 {
+                (self.obp1, PixelLayer::Object1)
+            } else {
+                (self.obp0, PixelLayer::Object0)
+            };
+            self.frame_buffer[line_offset + x as usize] = apply_palette(palette, color);
+            if let Some(layer_buffer) = &mut self.layer_buffer {
+                layer_buffer[line_offset + x as usize] = LayerPixel { layer, color };
+            }
+        }
+    }
+
+    fn get_object(&self, index: usize) -> Object {
+        let entry = &self.oam[index * 4..index * 4 + 4];
+        Object {
+            y: entry[0] as i16 - OBJ_Y_OFFSET,
+            x: entry[1] as i16 - OBJ_X_OFFSET,
+            tile: entry[2],
+            attributes: entry[3],
+        }
+    }
+
+    fn get_object_color(&self, object: &Object, x: i16, y: i16, height: i16) -> u8 {
+        let x = if object.attributes & OBJ_X_FLIP != 0 { 7 - x } else { x };
+        let y = if object.attributes & OBJ_Y_FLIP != 0 { height - 1 - y } else { y };
+        // In 8x16 mode the lowest bit of the tile index is ignored
+        let tile = if height == 16 { object.tile & 0xFE } else { object.tile };
+        self.get_tile_color(TILE_DATA_UNSIGNED + tile as u16 * TILE_SIZE, x as u8, y as u8)
+    }
+
+    fn get_tile_map_color(&self, tile_map: u16, x: u8, y: u8) -> u8 {
+        let map_address = tile_map + (y as u16 / 8) * TILE_MAP_WIDTH + x as u16 / 8;
+        let tile_index = self.read_vram(map_address);
+        let tile_address = if self.lcdc & LCDC_TILE_DATA != 0 {
+            TILE_DATA_UNSIGNED + tile_index as u16 * TILE_SIZE
+        } else {
+            TILE_DATA_SIGNED.wrapping_add_signed(tile_index as i8 as i16 * TILE_SIZE as i16)
+        };
+        self.get_tile_color(tile_address, x % 8, y % 8)
+    }
+
+    /// Tiles are stored as 2 bit planes per row, according to: https://gbdev.io/pandocs/Tile_Data.html
+    fn get_tile_color(&self, tile_address: u16, x: u8, y: u8) -> u8 {
+        let row_address = (tile_address - VRAM_START) as usize + y as usize * 2;
+        let low = self.vram[row_address];
+        let high = self.vram[row_address + 1];
+        let bit = 7 - x;
+        (((high >> bit) & 1) << 1) | ((low >> bit) & 1)
+    }
+}
+
+fn apply_palette(palette: u8, color: u8) -> u8 {
+    (palette >> (color * 2)) & 0b11
+}
@@ -0,0 +1,45 @@
+use crate::circuitry::ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+pub const SCREEN_COLUMNS: usize = SCREEN_WIDTH / 8;
+pub const SCREEN_ROWS: usize = SCREEN_HEIGHT / 8;
+/// Replaces tiles outside of the font in [`ScreenTiles::to_ascii`]
+const UNKNOWN_CHAR: char = '.';
+
+/// The tile index shown in every 8x8 cell of the screen, for asserting on the text of a game without comparing images.
+/// Only the background and window are considered, objects are ignored.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScreenTiles {
+    tiles: [[u8; SCREEN_COLUMNS]; SCREEN_ROWS],
+}
+
+impl ScreenTiles {
+    pub(super) fn new(tiles: [[u8; SCREEN_COLUMNS]; SCREEN_ROWS]) -> Self {
+        Self { tiles }
+    }
+
+    /// None if the cell is outside of the screen
+    pub fn get(&self, column: usize, row: usize) -> Option<u8> {
+        self.tiles.get(row)?.get(column).copied()
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[u8; SCREEN_COLUMNS]> {
+        self.tiles.iter()
+    }
+
+    /// Renders one line per row, mapping every tile index to a character
+    pub fn to_text(&self, mut map: impl FnMut(u8) -> char) -> String {
+        let lines: Vec<String> = self.rows().map(|row| row.iter().map(|&tile| map(tile)).collect()).collect();
+        lines.join("\n")
+    }
+
+    /// Renders the tiles of a font in ASCII order starting with the space at the given tile.
+    /// Tiles outside of the printable range become dots.
+    pub fn to_ascii(&self, space_tile: u8) -> String {
+        self.to_text(|tile| {
+            tile.checked_sub(space_tile)
+                .map(|offset| (b' ' as u32 + offset as u32) as u8 as char)
+                .filter(|char| char.is_ascii_graphic() || *char == ' ')
+                .unwrap_or(UNKNOWN_CHAR)
+        })
+    }
+}
@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+/// Host time spent emulating each subsystem
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SubsystemTimes {
+    /// Executing instructions, everything of a step that isn't attributed to another subsystem
+    pub cpu: Duration,
+    pub ppu: Duration,
+    pub apu: Duration,
+    /// Memory accesses, OAM DMA, the timer and the cartridge
+    pub bus: Duration,
+}
+
+impl SubsystemTimes {
+    pub fn get_total(&self) -> Duration {
+        self.cpu + self.ppu + self.apu + self.bus
+    }
+
+    fn get_circuitry(&self) -> Duration {
+        self.ppu + self.apu + self.bus
+    }
+}
+
+/// Measures the time between laps, or nothing at all while profiling is disabled
+pub(super) struct Stopwatch {
+    last: Option<Instant>,
+}
+
+impl Stopwatch {
+    pub(super) fn start(enabled: bool) -> Self {
+        Self {
+            last: enabled.then(Instant::now),
+        }
+    }
+
+    /// The time since the start or the previous lap
+    pub(super) fn lap(&mut self) -> Duration {
+        let Some(last) = self.last else {
+            return Duration::ZERO;
+        };
+        let now = Instant::now();
+        self.last = Some(now);
+        now - last
+    }
+}
+
+/// Host time measurements, they are not part of the emulated state so they are ignored when comparing and aren't saved
+#[derive(Debug, Default, Clone)]
+pub(super) struct Profiler {
+    enabled: bool,
+    pub(super) current: SubsystemTimes,
+    last_frame: Option<SubsystemTimes>,
+    frame_count: u64,
+    /// Circuitry time accumulated before the current step started
+    step_baseline: Duration,
+}
+
+impl Profiler {
+    /// Measurements start over whenever profiling is enabled
+    pub(super) fn set_enabled(&mut self, enabled: bool, frame_count: u64) {
+        *self = Self {
+            enabled,
+            frame_count,
+            ..Default::default()
+        };
+    }
+
+    pub(super) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(super) fn get_last_frame(&self) -> Option<&SubsystemTimes> {
+        self.last_frame.as_ref()
+    }
+
+    /// The step is measured from the returned instant on, None while profiling is disabled
+    pub(super) fn begin_step(&mut self) -> Option<Instant> {
+        self.step_baseline = self.current.get_circuitry();
+        self.enabled.then(Instant::now)
+    }
+
+    /// Attributes the time of the step the circuitry didn't use to the CPU, then publishes a completed frame
+    pub(super) fn finish_step(&mut self, elapsed: Duration, frame_count: u64) {
+        let circuitry = self.current.get_circuitry() - self.step_baseline;
+        self.current.cpu += elapsed.saturating_sub(circuitry);
+        if frame_count != self.frame_count {
+            self.frame_count = frame_count;
+            self.last_frame = Some(std::mem::take(&mut self.current));
+        }
+    }
+}
+
+impl PartialEq for Profiler {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
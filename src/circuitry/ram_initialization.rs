@@ -0,0 +1,51 @@
+use crate::helpers::random::SplitMix64;
+
+/// Length of the alternating 0x00/0xFF runs of the hardware-like pattern
+const PATTERN_RUN_LENGTH: usize = 8;
+/// On average one in this many bytes gets a random bit flipped in the hardware-like pattern
+const PATTERN_NOISE_RATE: u8 = 16;
+
+/// What RAM contains at power-on.
+/// Real hardware powers on with semi-random contents, some games unknowingly depend on them.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum RamInitialization {
+    #[default]
+    Zeroed,
+    Filled(u8),
+    /// Reproducible pseudo-random contents
+    Random { seed: u64 },
+    /// Approximation of typical power-on contents, alternating runs of 0x00 and 0xFF with a few random bit flips.
+    /// Actual contents vary per unit and temperature, so this only resembles what real hardware tends to show.
+    HardwareLike { seed: u64 },
+}
+
+impl RamInitialization {
+    /// Creates a memory region of the given size.
+    /// Every region uses its own id, so regions with the same seed don't get identical contents.
+    pub fn create(&self, size: usize, region_id: u64) -> Vec<u8> {
+        let mut memory = vec![0; size];
+        self.fill(&mut memory, region_id);
+        memory
+    }
+
+    pub fn fill(&self, memory: &mut [u8], region_id: u64) {
+        match *self {
+            Self::Zeroed => memory.fill(0x00),
+            Self::Filled(value) => memory.fill(value),
+            Self::Random { seed } => Self::region_rng(seed, region_id).fill_bytes(memory),
+            Self::HardwareLike { seed } => {
+                let mut rng = Self::region_rng(seed, region_id);
+                for (index, byte) in memory.iter_mut().enumerate() {
+                    *byte = if (index / PATTERN_RUN_LENGTH).is_multiple_of(2) { 0x00 } else { 0xFF };
+                    if rng.next_u8() < u8::MAX / PATTERN_NOISE_RATE {
+                        *byte ^= 1 << (rng.next_u8() % 8);
+                    }
+                }
+            }
+        }
+    }
+
+    fn region_rng(seed: u64, region_id: u64) -> SplitMix64 {
+        SplitMix64::new(seed ^ region_id.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+}
@@ -0,0 +1,156 @@
+use crate::helpers::bit_operations::get_bit_u16;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// Timer behavior according to: https://gbdev.io/pandocs/Timer_Obscure_Behaviour.html
+pub const DIV_ADDRESS: u16 = 0xFF04;
+pub const TIMA_ADDRESS: u16 = 0xFF05;
+pub const TMA_ADDRESS: u16 = 0xFF06;
+pub const TAC_ADDRESS: u16 = 0xFF07;
+
+const TAC_ENABLE: u8 = 0b0000_0100;
+const TAC_CLOCK_SELECT: u8 = 0b0000_0011;
+const TAC_UNUSED_BITS: u8 = 0b1111_1000;
+/// After an overflow TIMA reads 0 for 4 T-cycles before TMA is loaded and the interrupt is requested
+const RELOAD_DELAY: u8 = 4;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Timer {
+    /// Internal 16-bit counter incremented every T-cycle, DIV is its upper byte
+    counter: u16,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+    /// T-cycles left until TIMA is reloaded after an overflow, 0 if no reload is pending
+    reload_delay: u8,
+    /// TIMA was reloaded during the last M-cycle, writes to TIMA are ignored and writes to TMA go through to TIMA
+    reloaded: bool,
+}
+
+impl Timer {
+    pub fn initialize(counter: u16) -> Self {
+        Self {
+            counter,
+            ..Default::default()
+        }
+    }
+
+    /// Advances the timer by one M-cycle, returns true if the timer interrupt should be requested
+    pub fn tick(&mut self) -> bool {
+        self.reloaded = false;
+        let mut interrupt = false;
+
+        for _ in 0..4 {
+            if self.reload_delay > 0 {
+                self.reload_delay -= 1;
+                if self.reload_delay == 0 {
+                    self.tima = self.tma;
+                    self.reloaded = true;
+                    interrupt = true;
+                }
+            }
+
+            let old_signal = self.get_signal();
+            self.counter = self.counter.wrapping_add(1);
+            self.detect_falling_edge(old_signal);
+        }
+
+        interrupt
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            DIV_ADDRESS => self.get_div(),
+            TIMA_ADDRESS => self.tima,
+            TMA_ADDRESS => self.tma,
+            TAC_ADDRESS => self.tac | TAC_UNUSED_BITS,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            DIV_ADDRESS => {
+                // Resetting the counter can cause a falling edge and with it an additional TIMA increment
+                let old_signal = self.get_signal();
+                self.counter = 0;
+                self.detect_falling_edge(old_signal);
+            }
+            // Writes in the cycle TIMA is reloaded are ignored
+            TIMA_ADDRESS if !self.reloaded => {
+                self.tima = value;
+                // Writing during the delay cancels the reload and the interrupt
+                self.reload_delay = 0;
+            }
+            TMA_ADDRESS => {
+                self.tma = value;
+                if self.reloaded {
+                    self.tima = value;
+                }
+            }
+            TAC_ADDRESS => {
+                // Disabling the timer or switching the frequency can also cause a falling edge
+                let old_signal = self.get_signal();
+                self.tac = value & !TAC_UNUSED_BITS;
+                self.detect_falling_edge(old_signal);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn get_div(&self) -> u8 {
+        (self.counter >> 8) as u8
+    }
+
+    pub fn get_counter(&self) -> u16 {
+        self.counter
+    }
+
+    /// The counter bit selected by TAC, combined with the enable bit
+    fn get_signal(&self) -> bool {
+        let bit_index = match self.tac & TAC_CLOCK_SELECT {
+            0b00 => 9,
+            0b01 => 3,
+            0b10 => 5,
+            _ => 7,
+        };
+        self.tac & TAC_ENABLE != 0 && get_bit_u16(self.counter, bit_index)
+    }
+
+    fn detect_falling_edge(&mut self, old_signal: bool) {
+        if old_signal && !self.get_signal() {
+            self.increment_tima();
+        }
+    }
+
+    fn increment_tima(&mut self) {
+        let (result, overflow) = self.tima.overflowing_add(1);
+        self.tima = result;
+        if overflow {
+            self.reload_delay = RELOAD_DELAY;
+        }
+    }
+}
+
+impl SaveState for Timer {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u16(self.counter);
+        writer.write_u8(self.tima);
+        writer.write_u8(self.tma);
+        writer.write_u8(self.tac);
+        writer.write_u8(self.reload_delay);
+        writer.write_bool(self.reloaded);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.counter = reader.read_u16()?;
+        self.tima = reader.read_u8()?;
+        self.tma = reader.read_u8()?;
+        self.tac = reader.read_u8()? & !TAC_UNUSED_BITS;
+        self.reload_delay = reader.read_u8()?;
+        if self.reload_delay > RELOAD_DELAY {
+            return Err(SaveStateError::InvalidData);
+        }
+        self.reloaded = reader.read_bool()?;
+        Ok(())
+    }
+}
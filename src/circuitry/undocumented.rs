@@ -0,0 +1,54 @@
+use crate::circuitry::apu::APU;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+
+// Undocumented CGB registers according to: https://gbdev.io/pandocs/CGB_Registers.html#undocumented-registers
+pub const UNDOCUMENTED_START: u16 = 0xFF72;
+pub const UNDOCUMENTED_END: u16 = 0xFF77;
+const FF75_WRITABLE_BITS: u8 = 0x70;
+
+/// FF72-FF77, which only exist on the CGB and AGB.
+/// FF74 is only writable in CGB mode, which isn't emulated, so it always reads 0xFF.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UndocumentedRegisters {
+    ff72: u8,
+    ff73: u8,
+    ff75: u8,
+}
+
+impl UndocumentedRegisters {
+    /// PCM12 and PCM34 read the current digital output of two channels each, the lower channel in the low nibble
+    pub fn read(&self, address: u16, apu: &APU) -> u8 {
+        match address {
+            0xFF72 => self.ff72,
+            0xFF73 => self.ff73,
+            0xFF75 => self.ff75 | !FF75_WRITABLE_BITS,
+            0xFF76 => apu.get_pcm12(),
+            0xFF77 => apu.get_pcm34(),
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF72 => self.ff72 = value,
+            0xFF73 => self.ff73 = value,
+            0xFF75 => self.ff75 = value & FF75_WRITABLE_BITS,
+            _ => {}
+        }
+    }
+}
+
+impl SaveState for UndocumentedRegisters {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u8(self.ff72);
+        writer.write_u8(self.ff73);
+        writer.write_u8(self.ff75);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.ff72 = reader.read_u8()?;
+        self.ff73 = reader.read_u8()?;
+        self.ff75 = reader.read_u8()? & FF75_WRITABLE_BITS;
+        Ok(())
+    }
+}
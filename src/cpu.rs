@@ -1,16 +1,266 @@
 use crate::circuitry::interface::CircuitryInterface;
+use crate::circuitry::interrupts::Interrupt;
+use crate::cpu::opcodes::{CB_PREFIX, get_opcode_info};
 use crate::cpu::registers::{CPURegisters, CpuRegistersAccessTrait};
+use crate::cpu::snapshot::{CpuSnapshot, FlagsSnapshot};
+use crate::hardware_model::HardwareModel;
+use crate::helpers::bit_operations::{construct_u16, deconstruct_u16};
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
 
-mod registers;
+pub mod assembler;
+mod instructions;
+pub mod opcodes;
+pub(crate) mod registers;
+pub mod snapshot;
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct CPU {
     registers: CPURegisters,
+    /// Interrupt master enable
+    ime: bool,
+    /// Set by EI, IME is enabled after the instruction following EI
+    ime_scheduled: bool,
+    halted: bool,
+    /// HALT was executed with IME disabled while an interrupt was pending, the next opcode byte is read twice
+    halt_bug: bool,
+    stopped: bool,
+    /// An illegal opcode was executed, the CPU hangs until reset
+    locked: bool,
+    /// M-cycles spent in the current step
+    cycles: u8,
 }
 
 impl CPU {
-    pub fn step(&mut self, c: &mut impl CircuitryInterface) {
+    pub fn initialize(model: HardwareModel) -> Self {
+        Self {
+            registers: CPURegisters::initialize(model),
+            ..Default::default()
+        }
+    }
+
+    /// The instruction bytes are read at PC without side effects via the given peek function
+    pub fn snapshot(&self, peek: impl Fn(u16) -> u8) -> CpuSnapshot {
+        let pc = self.get_pc();
+        let opcode = peek(pc);
+        let instruction_length = if opcode == CB_PREFIX {
+            2
+        } else {
+            get_opcode_info(opcode).map_or(1, |info| info.get_length())
+        };
+
+        let mut instruction = [0; 3];
+        for (offset, byte) in instruction.iter_mut().enumerate().take(instruction_length as usize) {
+            *byte = peek(pc.wrapping_add(offset as u16));
+        }
+
+        CpuSnapshot {
+            a: self.get_a(),
+            b: self.get_b(),
+            c: self.get_c(),
+            d: self.get_d(),
+            e: self.get_e(),
+            f: self.get_f(),
+            h: self.get_h(),
+            l: self.get_l(),
+            pc,
+            sp: self.get_sp(),
+            flags: FlagsSnapshot {
+                zero: self.get_f_zero(),
+                subtract: self.get_f_subtract(),
+                half_carry: self.get_f_half_carry(),
+                carry: self.get_f_carry(),
+            },
+            ime: self.ime,
+            ime_scheduled: self.ime_scheduled,
+            halted: self.halted,
+            stopped: self.stopped,
+            locked: self.locked,
+            instruction,
+            instruction_length,
+        }
+    }
+
+    /// Executes the next instruction, or services a pending interrupt.
+    /// While halted, stopped or locked, only a single M-cycle passes.
+    ///
+    /// # Returns
+    ///
+    /// The amount of M-cycles that passed
+    pub fn step(&mut self, c: &mut impl CircuitryInterface) -> u8 {
+        self.cycles = 0;
 
+        if self.locked {
+            self.cycle(c);
+            return self.cycles;
+        }
+
+        if self.stopped {
+            // Without a joypad being pressed nothing leaves STOP mode, the clock gating is not modelled
+            if c.get_interrupt_flag() & Interrupt::Joypad.get_bit() == 0 {
+                self.cycle(c);
+                return self.cycles;
+            }
+            self.stopped = false;
+        }
+
+        if self.halted {
+            // Any pending interrupt ends HALT, even when IME is disabled
+            if c.get_pending_interrupts() == 0 {
+                self.cycle(c);
+                return self.cycles;
+            }
+            self.halted = false;
+            // Servicing an interrupt right out of HALT takes an additional M-cycle, according to:
+            // https://gbdev.io/pandocs/Interrupts.html#interrupt-handling
+            // With IME disabled the next instruction is fetched immediately.
+            if self.ime {
+                self.cycle(c);
+            }
+        }
+
+        if self.ime && c.get_pending_interrupts() != 0 {
+            self.service_interrupt(c);
+            return self.cycles;
+        }
+
+        if self.ime_scheduled {
+            self.ime_scheduled = false;
+            self.ime = true;
+        }
+
+        let opcode = self.fetch(c);
+        self.execute(c, opcode);
+        self.cycles
+    }
+
+    pub fn get_ime(&self) -> bool {
+        self.ime
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Takes 5 M-cycles: 2 idle, 2 for pushing PC and 1 for jumping to the vector
+    fn service_interrupt(&mut self, c: &mut impl CircuitryInterface) {
+        self.ime = false;
+        self.cycle(c);
+        self.cycle(c);
+
+        let (pc_low, pc_high) = deconstruct_u16(self.get_pc());
+        self.decrement_sp();
+        self.write_cycle(c, self.get_sp(), pc_high);
+
+        // Pushing the high byte can overwrite IE when SP wraps around to 0xFFFF.
+        // The interrupt to service is only decided afterward, if none is left the CPU jumps to 0x0000.
+        let interrupt = Interrupt::highest_priority(c.get_pending_interrupts());
+
+        self.decrement_sp();
+        self.write_cycle(c, self.get_sp(), pc_low);
+
+        match interrupt {
+            Some(interrupt) => {
+                c.acknowledge_interrupt(interrupt);
+                self.set_pc(interrupt.get_vector());
+            }
+            None => self.set_pc(0x0000),
+        }
+        self.cycle(c);
+    }
+
+    /// A single M-cycle without a memory access
+    fn cycle(&mut self, c: &mut impl CircuitryInterface) {
+        c.tick();
+        self.cycles += 1;
+    }
+
+    fn read_cycle(&mut self, c: &mut impl CircuitryInterface, address: u16) -> u8 {
+        self.cycle(c);
+        c.read(address)
+    }
+
+    fn write_cycle(&mut self, c: &mut impl CircuitryInterface, address: u16, value: u8) {
+        self.cycle(c);
+        c.write(address, value);
+    }
+
+    /// Reads the byte at PC and increments PC
+    fn fetch(&mut self, c: &mut impl CircuitryInterface) -> u8 {
+        let value = self.read_cycle(c, self.get_pc());
+        if self.halt_bug {
+            self.halt_bug = false;
+        } else {
+            self.set_pc(self.get_pc().wrapping_add(1));
+        }
+        value
+    }
+
+    fn fetch_u16(&mut self, c: &mut impl CircuitryInterface) -> u16 {
+        let lsb = self.fetch(c);
+        let msb = self.fetch(c);
+        construct_u16(lsb, msb)
+    }
+
+    /// Takes 3 M-cycles: 1 idle for decrementing SP, 2 for writing
+    fn push_u16(&mut self, c: &mut impl CircuitryInterface, value: u16) {
+        let (lsb, msb) = deconstruct_u16(value);
+        self.cycle(c);
+        self.decrement_sp();
+        self.write_cycle(c, self.get_sp(), msb);
+        self.decrement_sp();
+        self.write_cycle(c, self.get_sp(), lsb);
+    }
+
+    /// Takes 2 M-cycles
+    fn pop_u16(&mut self, c: &mut impl CircuitryInterface) -> u16 {
+        let lsb = self.read_cycle(c, self.get_sp());
+        self.increment_sp();
+        let msb = self.read_cycle(c, self.get_sp());
+        self.increment_sp();
+        construct_u16(lsb, msb)
+    }
+}
+
+impl SaveState for CPU {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u16(self.get_af());
+        writer.write_u16(self.get_bc());
+        writer.write_u16(self.get_de());
+        writer.write_u16(self.get_hl());
+        writer.write_u16(self.get_sp());
+        writer.write_u16(self.get_pc());
+        writer.write_bool(self.ime);
+        writer.write_bool(self.ime_scheduled);
+        writer.write_bool(self.halted);
+        writer.write_bool(self.halt_bug);
+        writer.write_bool(self.stopped);
+        writer.write_bool(self.locked);
+        writer.write_u8(self.cycles);
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        self.set_af(reader.read_u16()?);
+        self.set_bc(reader.read_u16()?);
+        self.set_de(reader.read_u16()?);
+        self.set_hl(reader.read_u16()?);
+        self.set_sp(reader.read_u16()?);
+        self.set_pc(reader.read_u16()?);
+        self.ime = reader.read_bool()?;
+        self.ime_scheduled = reader.read_bool()?;
+        self.halted = reader.read_bool()?;
+        self.halt_bug = reader.read_bool()?;
+        self.stopped = reader.read_bool()?;
+        self.locked = reader.read_bool()?;
+        self.cycles = reader.read_u8()?;
+        Ok(())
     }
 }
 
@@ -22,4 +272,4 @@ impl CpuRegistersAccessTrait for CPU {
     fn get_registers_mut(&mut self) -> &mut CPURegisters {
         &mut self.registers
     }
-}
\ No newline at end of file
+}
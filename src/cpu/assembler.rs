@@ -0,0 +1,202 @@
+use crate::cpu::opcodes::{CB_OPCODES, CB_PREFIX, OPCODES, OpcodeInfo};
+use std::fmt::{Display, Formatter};
+
+// Operand placeholders as used in the opcode table mnemonics
+const PLACEHOLDERS: [&str; 5] = ["d16", "a16", "d8", "a8", "r8"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssemblerError {
+    /// No opcode matches the given instruction
+    UnknownInstruction(String),
+    /// The instruction matched, but an operand doesn't fit its placeholder
+    OperandOutOfRange(String),
+}
+
+impl Display for AssemblerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownInstruction(instruction) => write!(f, "unknown instruction: {instruction}"),
+            Self::OperandOutOfRange(instruction) => write!(f, "operand out of range: {instruction}"),
+        }
+    }
+}
+
+impl std::error::Error for AssemblerError {}
+
+/// Assembled bytes that are meant to be placed at a specific address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assembly {
+    origin: u16,
+    bytes: Vec<u8>,
+}
+
+impl Assembly {
+    pub fn get_origin(&self) -> u16 {
+        self.origin
+    }
+
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Copies the bytes into the given memory image starting at the origin.
+    /// Panics if the memory is too small, since this is meant for setting up tests.
+    pub fn write_into(&self, memory: &mut [u8]) {
+        let start = self.origin as usize;
+        memory[start..start + self.bytes.len()].copy_from_slice(&self.bytes);
+    }
+}
+
+/// Assembles instructions separated by `;` or newlines, e.g. `"LD A, 0x42; CALL 0x0150"`.
+///
+/// Mnemonics follow the opcode tables in [`crate::cpu::opcodes`] and are case-insensitive.
+/// Brackets may be used instead of parentheses, numbers can be decimal, `0x`/`$` hex, `0b`/`%` binary or `38H` style hex.
+/// Relative jump operands are the raw signed offset, e.g. `JR -2` loops forever.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssemblerError> {
+    let mut bytes = Vec::new();
+    for instruction in source.split([';', '\n']).map(str::trim).filter(|line| !line.is_empty()) {
+        bytes.extend(assemble_instruction(instruction)?);
+    }
+    Ok(bytes)
+}
+
+/// Like [`assemble`], but the result remembers the address it should be placed at.
+pub fn assemble_at(origin: u16, source: &str) -> Result<Assembly, AssemblerError> {
+    Ok(Assembly {
+        origin,
+        bytes: assemble(source)?,
+    })
+}
+
+fn assemble_instruction(instruction: &str) -> Result<Vec<u8>, AssemblerError> {
+    let (mnemonic, operands) = split_instruction(&instruction.to_uppercase().replace('[', "(").replace(']', ")"));
+
+    let unprefixed = OPCODES
+        .iter()
+        .enumerate()
+        .filter_map(|(opcode, info)| info.as_ref().map(|info| (vec![opcode as u8], info)));
+    let prefixed = CB_OPCODES
+        .iter()
+        .enumerate()
+        .map(|(opcode, info)| (vec![CB_PREFIX, opcode as u8], info));
+
+    let mut out_of_range = false;
+    for (opcode_bytes, info) in unprefixed.chain(prefixed) {
+        match encode(info, &mnemonic, &operands) {
+            Encoding::Encoded(operand_bytes) => {
+                let mut bytes = opcode_bytes;
+                bytes.extend(operand_bytes);
+                // Pads STOP, which is followed by an unused byte
+                bytes.resize(info.get_length() as usize, 0x00);
+                return Ok(bytes);
+            }
+            Encoding::OutOfRange => out_of_range = true,
+            Encoding::Mismatch => {}
+        }
+    }
+
+    if out_of_range {
+        Err(AssemblerError::OperandOutOfRange(instruction.to_string()))
+    } else {
+        Err(AssemblerError::UnknownInstruction(instruction.to_string()))
+    }
+}
+
+fn split_instruction(instruction: &str) -> (String, Vec<String>) {
+    let (mnemonic, operands) = instruction.split_once(char::is_whitespace).unwrap_or((instruction, ""));
+    let operands = operands
+        .split(',')
+        .map(|operand| operand.chars().filter(|char| !char.is_whitespace()).collect::<String>())
+        .filter(|operand| !operand.is_empty())
+        .collect();
+    (mnemonic.to_string(), operands)
+}
+
+enum Encoding {
+    Encoded(Vec<u8>),
+    OutOfRange,
+    Mismatch,
+}
+
+fn encode(info: &OpcodeInfo, mnemonic: &str, operands: &[String]) -> Encoding {
+    let (template_mnemonic, template_operands) = split_instruction(info.get_mnemonic());
+    if template_mnemonic != mnemonic || template_operands.len() != operands.len() {
+        return Encoding::Mismatch;
+    }
+
+    let mut bytes = Vec::new();
+    for (template, operand) in template_operands.iter().zip(operands) {
+        let Some(placeholder) = PLACEHOLDERS.iter().find(|placeholder| template.contains(*placeholder)) else {
+            if !literal_matches(template, operand) {
+                return Encoding::Mismatch;
+            }
+            continue;
+        };
+
+        let (prefix, suffix) = template.split_once(placeholder).unwrap();
+        // "SP+r8" also accepts "SP-2", the sign is parsed with the number
+        let prefix = prefix.strip_suffix('+').unwrap_or(prefix);
+        let Some(value) = operand
+            .strip_prefix(prefix)
+            .and_then(|operand| operand.strip_suffix(suffix))
+            .and_then(parse_number)
+        else {
+            return Encoding::Mismatch;
+        };
+
+        match encode_operand(placeholder, value) {
+            Some(operand_bytes) => bytes.extend(operand_bytes),
+            None => return Encoding::OutOfRange,
+        }
+    }
+
+    Encoding::Encoded(bytes)
+}
+
+/// Register names have to match exactly, fixed numbers like RST vectors or bit indices are compared by value
+fn literal_matches(template: &str, operand: &str) -> bool {
+    if template == operand {
+        return true;
+    }
+    match (parse_number(template), parse_number(operand)) {
+        (Some(template), Some(operand)) => template == operand,
+        _ => false,
+    }
+}
+
+fn encode_operand(placeholder: &str, value: i64) -> Option<Vec<u8>> {
+    match placeholder {
+        "d8" if (-0x80..=0xFF).contains(&value) => Some(vec![value as u8]),
+        "r8" if (-0x80..=0x7F).contains(&value) => Some(vec![value as i8 as u8]),
+        // Accepts both the offset and the full high RAM/IO address
+        "a8" if (0x00..=0xFF).contains(&value) || (0xFF00..=0xFFFF).contains(&value) => Some(vec![value as u8]),
+        "d16" if (-0x8000..=0xFFFF).contains(&value) => Some((value as u16).to_le_bytes().to_vec()),
+        "a16" if (0x0000..=0xFFFF).contains(&value) => Some((value as u16).to_le_bytes().to_vec()),
+        _ => None,
+    }
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+    let (negative, text) = match text.as_bytes().first()? {
+        b'-' => (true, &text[1..]),
+        b'+' => (false, &text[1..]),
+        _ => (false, text),
+    };
+
+    let value = if let Some(hex) = text.strip_prefix("0X").or_else(|| text.strip_prefix('$')) {
+        i64::from_str_radix(hex, 16)
+    } else if let Some(binary) = text.strip_prefix("0B").or_else(|| text.strip_prefix('%')) {
+        i64::from_str_radix(binary, 2)
+    } else if let Some(hex) = text.strip_suffix('H') {
+        i64::from_str_radix(hex, 16)
+    } else {
+        text.parse::<i64>()
+    }
+    .ok()?;
+
+    Some(if negative { -value } else { value })
+}
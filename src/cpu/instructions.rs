@@ -0,0 +1,407 @@
+use crate::circuitry::interface::CircuitryInterface;
+use crate::circuitry::timer::DIV_ADDRESS;
+use crate::cpu::CPU;
+use crate::cpu::opcodes::CB_PREFIX;
+use crate::cpu::registers::CpuRegistersAccessTrait;
+use crate::helpers::bit_operations::{
+    deconstruct_u16, rotate_left_get_carry_u8, rotate_left_through_carry_u8, rotate_right_get_carry_u8, rotate_right_through_carry_u8,
+};
+
+mod alu;
+mod prefixed;
+
+// Register indices as encoded in the lower 3 bits of most opcodes
+const REGISTER_INDEX_HL_INDIRECT: u8 = 6;
+
+impl CPU {
+    /// Executes an already fetched opcode.
+    /// Decoding according to: https://gbdev.io/pandocs/CPU_Instruction_Set.html
+    pub(super) fn execute(&mut self, c: &mut impl CircuitryInterface, opcode: u8) {
+        match opcode {
+            // NOP
+            0x00 => {}
+            // LD rr,d16
+            0x01 | 0x11 | 0x21 | 0x31 => {
+                let value = self.fetch_u16(c);
+                self.set_register_pair(opcode >> 4, value);
+            }
+            // LD (BC),A
+            0x02 => self.write_cycle(c, self.get_bc(), self.get_a()),
+            // LD (DE),A
+            0x12 => self.write_cycle(c, self.get_de(), self.get_a()),
+            // LD (HL+),A
+            0x22 => {
+                let address = self.get_hl();
+                self.write_cycle(c, address, self.get_a());
+                self.set_hl(address.wrapping_add(1));
+            }
+            // LD (HL-),A
+            0x32 => {
+                let address = self.get_hl();
+                self.write_cycle(c, address, self.get_a());
+                self.set_hl(address.wrapping_sub(1));
+            }
+            // LD A,(BC)
+            0x0A => {
+                let value = self.read_cycle(c, self.get_bc());
+                self.set_a(value);
+            }
+            // LD A,(DE)
+            0x1A => {
+                let value = self.read_cycle(c, self.get_de());
+                self.set_a(value);
+            }
+            // LD A,(HL+)
+            0x2A => {
+                let address = self.get_hl();
+                let value = self.read_cycle(c, address);
+                self.set_a(value);
+                self.set_hl(address.wrapping_add(1));
+            }
+            // LD A,(HL-)
+            0x3A => {
+                let address = self.get_hl();
+                let value = self.read_cycle(c, address);
+                self.set_a(value);
+                self.set_hl(address.wrapping_sub(1));
+            }
+            // INC rr
+            0x03 | 0x13 | 0x23 | 0x33 => {
+                let value = self.get_register_pair(opcode >> 4).wrapping_add(1);
+                self.set_register_pair(opcode >> 4, value);
+                self.cycle(c);
+            }
+            // DEC rr
+            0x0B | 0x1B | 0x2B | 0x3B => {
+                let value = self.get_register_pair(opcode >> 4).wrapping_sub(1);
+                self.set_register_pair(opcode >> 4, value);
+                self.cycle(c);
+            }
+            // ADD HL,rr
+            0x09 | 0x19 | 0x29 | 0x39 => {
+                self.add_hl(self.get_register_pair(opcode >> 4));
+                self.cycle(c);
+            }
+            // INC r
+            0x04 | 0x0C | 0x14 | 0x1C | 0x24 | 0x2C | 0x34 | 0x3C => {
+                let index = opcode >> 3;
+                let value = self.read_register(c, index);
+                let result = self.inc_u8(value);
+                self.write_register(c, index, result);
+            }
+            // DEC r
+            0x05 | 0x0D | 0x15 | 0x1D | 0x25 | 0x2D | 0x35 | 0x3D => {
+                let index = opcode >> 3;
+                let value = self.read_register(c, index);
+                let result = self.dec_u8(value);
+                self.write_register(c, index, result);
+            }
+            // LD r,d8
+            0x06 | 0x0E | 0x16 | 0x1E | 0x26 | 0x2E | 0x36 | 0x3E => {
+                let value = self.fetch(c);
+                self.write_register(c, opcode >> 3, value);
+            }
+            // RLCA
+            0x07 => {
+                let (result, carry) = rotate_left_get_carry_u8(self.get_a());
+                self.set_accumulator_rotation(result, carry);
+            }
+            // RRCA
+            0x0F => {
+                let (result, carry) = rotate_right_get_carry_u8(self.get_a());
+                self.set_accumulator_rotation(result, carry);
+            }
+            // RLA
+            0x17 => {
+                let (result, carry) = rotate_left_through_carry_u8(self.get_a(), self.get_f_carry());
+                self.set_accumulator_rotation(result, carry);
+            }
+            // RRA
+            0x1F => {
+                let (result, carry) = rotate_right_through_carry_u8(self.get_a(), self.get_f_carry());
+                self.set_accumulator_rotation(result, carry);
+            }
+            // LD (a16),SP
+            0x08 => {
+                let address = self.fetch_u16(c);
+                let (lsb, msb) = deconstruct_u16(self.get_sp());
+                self.write_cycle(c, address, lsb);
+                self.write_cycle(c, address.wrapping_add(1), msb);
+            }
+            // STOP, the following byte is skipped
+            0x10 => {
+                self.set_pc(self.get_pc().wrapping_add(1));
+                c.write(DIV_ADDRESS, 0);
+                self.stopped = true;
+            }
+            // JR r8
+            0x18 => self.jump_relative(c, true),
+            // JR cc,r8
+            0x20 | 0x28 | 0x30 | 0x38 => {
+                let condition = self.check_condition(opcode >> 3);
+                self.jump_relative(c, condition);
+            }
+            // DAA
+            0x27 => self.daa(),
+            // CPL
+            0x2F => {
+                self.set_a(!self.get_a());
+                self.set_f_subtract(true);
+                self.set_f_half_carry(true);
+            }
+            // SCF
+            0x37 => {
+                self.set_f_subtract(false);
+                self.set_f_half_carry(false);
+                self.set_f_carry(true);
+            }
+            // CCF
+            0x3F => {
+                self.set_f_subtract(false);
+                self.set_f_half_carry(false);
+                self.set_f_carry(!self.get_f_carry());
+            }
+            // HALT
+            0x76 => {
+                if !self.ime && c.get_pending_interrupts() != 0 {
+                    self.halt_bug = true;
+                } else {
+                    self.halted = true;
+                }
+            }
+            // LD r,r
+            0x40..=0x7F => {
+                let value = self.read_register(c, opcode);
+                self.write_register(c, opcode >> 3, value);
+            }
+            // ADD/ADC/SUB/SBC/AND/XOR/OR/CP A,r
+            0x80..=0xBF => {
+                let value = self.read_register(c, opcode);
+                self.alu(opcode >> 3, value);
+            }
+            // ADD/ADC/SUB/SBC/AND/XOR/OR/CP A,d8
+            0xC6 | 0xCE | 0xD6 | 0xDE | 0xE6 | 0xEE | 0xF6 | 0xFE => {
+                let value = self.fetch(c);
+                self.alu(opcode >> 3, value);
+            }
+            // RET cc
+            0xC0 | 0xC8 | 0xD0 | 0xD8 => {
+                self.cycle(c);
+                if self.check_condition(opcode >> 3) {
+                    self.ret(c);
+                }
+            }
+            // RET
+            0xC9 => self.ret(c),
+            // RETI, IME is enabled immediately
+            0xD9 => {
+                self.ret(c);
+                self.ime = true;
+            }
+            // POP rr
+            0xC1 | 0xD1 | 0xE1 | 0xF1 => {
+                let value = self.pop_u16(c);
+                self.set_stack_register_pair(opcode >> 4, value);
+            }
+            // PUSH rr
+            0xC5 | 0xD5 | 0xE5 | 0xF5 => {
+                let value = self.get_stack_register_pair(opcode >> 4);
+                self.push_u16(c, value);
+            }
+            // JP a16
+            0xC3 => self.jump_absolute(c, true),
+            // JP cc,a16
+            0xC2 | 0xCA | 0xD2 | 0xDA => {
+                let condition = self.check_condition(opcode >> 3);
+                self.jump_absolute(c, condition);
+            }
+            // JP HL
+            0xE9 => self.set_pc(self.get_hl()),
+            // CALL a16
+            0xCD => self.call(c, true),
+            // CALL cc,a16
+            0xC4 | 0xCC | 0xD4 | 0xDC => {
+                let condition = self.check_condition(opcode >> 3);
+                self.call(c, condition);
+            }
+            // RST
+            0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF => {
+                self.push_u16(c, self.get_pc());
+                self.set_pc((opcode & 0b0011_1000) as u16);
+            }
+            CB_PREFIX => {
+                let opcode = self.fetch(c);
+                self.execute_prefixed(c, opcode);
+            }
+            // LDH (a8),A
+            0xE0 => {
+                let offset = self.fetch(c);
+                self.write_cycle(c, 0xFF00 | offset as u16, self.get_a());
+            }
+            // LDH A,(a8)
+            0xF0 => {
+                let offset = self.fetch(c);
+                let value = self.read_cycle(c, 0xFF00 | offset as u16);
+                self.set_a(value);
+            }
+            // LD (C),A
+            0xE2 => self.write_cycle(c, 0xFF00 | self.get_c() as u16, self.get_a()),
+            // LD A,(C)
+            0xF2 => {
+                let value = self.read_cycle(c, 0xFF00 | self.get_c() as u16);
+                self.set_a(value);
+            }
+            // LD (a16),A
+            0xEA => {
+                let address = self.fetch_u16(c);
+                self.write_cycle(c, address, self.get_a());
+            }
+            // LD A,(a16)
+            0xFA => {
+                let address = self.fetch_u16(c);
+                let value = self.read_cycle(c, address);
+                self.set_a(value);
+            }
+            // ADD SP,r8
+            0xE8 => {
+                let offset = self.fetch(c) as i8;
+                let result = self.add_sp_offset(offset);
+                self.set_sp(result);
+                self.cycle(c);
+                self.cycle(c);
+            }
+            // LD HL,SP+r8
+            0xF8 => {
+                let offset = self.fetch(c) as i8;
+                let result = self.add_sp_offset(offset);
+                self.set_hl(result);
+                self.cycle(c);
+            }
+            // LD SP,HL
+            0xF9 => {
+                self.set_sp(self.get_hl());
+                self.cycle(c);
+            }
+            // DI
+            0xF3 => {
+                self.ime = false;
+                self.ime_scheduled = false;
+            }
+            // EI
+            0xFB => self.ime_scheduled = true,
+            // 0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD
+            _ => self.locked = true,
+        }
+    }
+
+    /// Index 0-7 maps to B, C, D, E, H, L, (HL), A, only the lower 3 bits are used.
+    /// Accessing (HL) takes an M-cycle.
+    fn read_register(&mut self, c: &mut impl CircuitryInterface, index: u8) -> u8 {
+        match index & 0b111 {
+            0 => self.get_b(),
+            1 => self.get_c(),
+            2 => self.get_d(),
+            3 => self.get_e(),
+            4 => self.get_h(),
+            5 => self.get_l(),
+            REGISTER_INDEX_HL_INDIRECT => self.read_cycle(c, self.get_hl()),
+            _ => self.get_a(),
+        }
+    }
+
+    /// See [`CPU::read_register`]
+    fn write_register(&mut self, c: &mut impl CircuitryInterface, index: u8, value: u8) {
+        match index & 0b111 {
+            0 => self.set_b(value),
+            1 => self.set_c(value),
+            2 => self.set_d(value),
+            3 => self.set_e(value),
+            4 => self.set_h(value),
+            5 => self.set_l(value),
+            REGISTER_INDEX_HL_INDIRECT => self.write_cycle(c, self.get_hl(), value),
+            _ => self.set_a(value),
+        }
+    }
+
+    /// Index 0-3 maps to BC, DE, HL, SP, only the lower 2 bits are used
+    fn get_register_pair(&self, index: u8) -> u16 {
+        match index & 0b11 {
+            0 => self.get_bc(),
+            1 => self.get_de(),
+            2 => self.get_hl(),
+            _ => self.get_sp(),
+        }
+    }
+
+    fn set_register_pair(&mut self, index: u8, value: u16) {
+        match index & 0b11 {
+            0 => self.set_bc(value),
+            1 => self.set_de(value),
+            2 => self.set_hl(value),
+            _ => self.set_sp(value),
+        }
+    }
+
+    /// Like [`CPU::get_register_pair`], but index 3 maps to AF as used by PUSH and POP
+    fn get_stack_register_pair(&self, index: u8) -> u16 {
+        match index & 0b11 {
+            3 => self.get_af(),
+            index => self.get_register_pair(index),
+        }
+    }
+
+    fn set_stack_register_pair(&mut self, index: u8, value: u16) {
+        match index & 0b11 {
+            3 => self.set_af(value),
+            index => self.set_register_pair(index, value),
+        }
+    }
+
+    /// Index 0-3 maps to NZ, Z, NC, C, only the lower 2 bits are used
+    fn check_condition(&self, index: u8) -> bool {
+        match index & 0b11 {
+            0 => !self.get_f_zero(),
+            1 => self.get_f_zero(),
+            2 => !self.get_f_carry(),
+            _ => self.get_f_carry(),
+        }
+    }
+
+    fn set_accumulator_rotation(&mut self, result: u8, carry: bool) {
+        self.set_a(result);
+        self.set_f_zero(false);
+        self.set_f_subtract(false);
+        self.set_f_half_carry(false);
+        self.set_f_carry(carry);
+    }
+
+    fn jump_relative(&mut self, c: &mut impl CircuitryInterface, condition: bool) {
+        let offset = self.fetch(c) as i8;
+        if condition {
+            self.set_pc(self.get_pc().wrapping_add(offset as i16 as u16));
+            self.cycle(c);
+        }
+    }
+
+    fn jump_absolute(&mut self, c: &mut impl CircuitryInterface, condition: bool) {
+        let address = self.fetch_u16(c);
+        if condition {
+            self.set_pc(address);
+            self.cycle(c);
+        }
+    }
+
+    fn call(&mut self, c: &mut impl CircuitryInterface, condition: bool) {
+        let address = self.fetch_u16(c);
+        if condition {
+            self.push_u16(c, self.get_pc());
+            self.set_pc(address);
+        }
+    }
+
+    fn ret(&mut self, c: &mut impl CircuitryInterface) {
+        let address = self.pop_u16(c);
+        self.set_pc(address);
+        self.cycle(c);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::cpu::CPU;
+use crate::cpu::registers::CpuRegistersAccessTrait;
+use crate::helpers::bit_operations::{add_carry_u8, add_u16, add_u16_i8, add_u8, sub_carry_u8, sub_u8};
+
+impl CPU {
+    /// Operation index 0-7 maps to ADD, ADC, SUB, SBC, AND, XOR, OR, CP, only the lower 3 bits are used
+    pub(super) fn alu(&mut self, operation: u8, value: u8) {
+        match operation & 0b111 {
+            0 => self.add_a(value),
+            1 => self.adc_a(value),
+            2 => self.sub_a(value),
+            3 => self.sbc_a(value),
+            4 => self.and_a(value),
+            5 => self.xor_a(value),
+            6 => self.or_a(value),
+            _ => self.cp_a(value),
+        }
+    }
+
+    fn add_a(&mut self, value: u8) {
+        let (result, half_carry, carry) = add_u8(self.get_a(), value);
+        self.set_a(result);
+        self.set_flags(result == 0, false, half_carry, carry);
+    }
+
+    fn adc_a(&mut self, value: u8) {
+        let (result, half_carry, carry) = add_carry_u8(self.get_a(), value, self.get_f_carry());
+        self.set_a(result);
+        self.set_flags(result == 0, false, half_carry, carry);
+    }
+
+    fn sub_a(&mut self, value: u8) {
+        let (result, half_carry, carry) = sub_u8(self.get_a(), value);
+        self.set_a(result);
+        self.set_flags(result == 0, true, half_carry, carry);
+    }
+
+    fn sbc_a(&mut self, value: u8) {
+        let (result, half_carry, carry) = sub_carry_u8(self.get_a(), value, self.get_f_carry());
+        self.set_a(result);
+        self.set_flags(result == 0, true, half_carry, carry);
+    }
+
+    fn and_a(&mut self, value: u8) {
+        let result = self.get_a() & value;
+        self.set_a(result);
+        self.set_flags(result == 0, false, true, false);
+    }
+
+    fn xor_a(&mut self, value: u8) {
+        let result = self.get_a() ^ value;
+        self.set_a(result);
+        self.set_flags(result == 0, false, false, false);
+    }
+
+    fn or_a(&mut self, value: u8) {
+        let result = self.get_a() | value;
+        self.set_a(result);
+        self.set_flags(result == 0, false, false, false);
+    }
+
+    /// Like SUB, but only the flags are kept
+    fn cp_a(&mut self, value: u8) {
+        let (result, half_carry, carry) = sub_u8(self.get_a(), value);
+        self.set_flags(result == 0, true, half_carry, carry);
+    }
+
+    /// Carry is not affected
+    pub(super) fn inc_u8(&mut self, value: u8) -> u8 {
+        let (result, half_carry, _) = add_u8(value, 1);
+        self.set_f_zero(result == 0);
+        self.set_f_subtract(false);
+        self.set_f_half_carry(half_carry);
+        result
+    }
+
+    /// Carry is not affected
+    pub(super) fn dec_u8(&mut self, value: u8) -> u8 {
+        let (result, half_carry, _) = sub_u8(value, 1);
+        self.set_f_zero(result == 0);
+        self.set_f_subtract(true);
+        self.set_f_half_carry(half_carry);
+        result
+    }
+
+    /// Zero is not affected
+    pub(super) fn add_hl(&mut self, value: u16) {
+        let (result, half_carry, carry) = add_u16(self.get_hl(), value);
+        self.set_hl(result);
+        self.set_f_subtract(false);
+        self.set_f_half_carry(half_carry);
+        self.set_f_carry(carry);
+    }
+
+    /// Shared by ADD SP,r8 and LD HL,SP+r8, returns the result without storing it
+    pub(super) fn add_sp_offset(&mut self, offset: i8) -> u16 {
+        let (result, half_carry, carry) = add_u16_i8(self.get_sp(), offset);
+        self.set_flags(false, false, half_carry, carry);
+        result
+    }
+
+    /// Adjusts A to binary-coded decimal after an addition or subtraction
+    pub(super) fn daa(&mut self) {
+        let mut a = self.get_a();
+        let mut carry = self.get_f_carry();
+
+        if self.get_f_subtract() {
+            if carry {
+                a = a.wrapping_sub(0x60);
+            }
+            if self.get_f_half_carry() {
+                a = a.wrapping_sub(0x06);
+            }
+        } else {
+            if carry || a > 0x99 {
+                a = a.wrapping_add(0x60);
+                carry = true;
+            }
+            if self.get_f_half_carry() || (a & 0x0F) > 0x09 {
+                a = a.wrapping_add(0x06);
+            }
+        }
+
+        self.set_a(a);
+        self.set_f_zero(a == 0);
+        self.set_f_half_carry(false);
+        self.set_f_carry(carry);
+    }
+
+    pub(super) fn set_flags(&mut self, zero: bool, subtract: bool, half_carry: bool, carry: bool) {
+        self.set_f_zero(zero);
+        self.set_f_subtract(subtract);
+        self.set_f_half_carry(half_carry);
+        self.set_f_carry(carry);
+    }
+}
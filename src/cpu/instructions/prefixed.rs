@@ -0,0 +1,53 @@
+use crate::circuitry::interface::CircuitryInterface;
+use crate::cpu::CPU;
+use crate::cpu::registers::CpuRegistersAccessTrait;
+use crate::helpers::bit_operations::{
+    get_bit_u8, rotate_left_get_carry_u8, rotate_left_through_carry_u8, rotate_right_get_carry_u8,
+    rotate_right_through_carry_u8, set_bit_u8,
+};
+
+impl CPU {
+    /// Executes the opcode following the 0xCB prefix.
+    /// The upper 2 bits select the operation group, bits 3-5 the operation or bit index and the lower 3 bits the register.
+    pub(super) fn execute_prefixed(&mut self, c: &mut impl CircuitryInterface, opcode: u8) {
+        let operation = (opcode >> 3) & 0b111;
+        let value = self.read_register(c, opcode);
+
+        match opcode >> 6 {
+            // RLC/RRC/RL/RR/SLA/SRA/SWAP/SRL
+            0 => {
+                let (result, carry) = self.shift_operation(operation, value);
+                self.set_flags(result == 0, false, false, carry);
+                self.write_register(c, opcode, result);
+            }
+            // BIT, carry is not affected
+            1 => {
+                self.set_f_zero(!get_bit_u8(value, operation as usize));
+                self.set_f_subtract(false);
+                self.set_f_half_carry(true);
+            }
+            // RES
+            2 => self.write_register(c, opcode, set_bit_u8(value, operation as usize, false)),
+            // SET
+            _ => self.write_register(c, opcode, set_bit_u8(value, operation as usize, true)),
+        }
+    }
+
+    /// Returns (result, carry)
+    fn shift_operation(&self, operation: u8, value: u8) -> (u8, bool) {
+        match operation {
+            0 => rotate_left_get_carry_u8(value),
+            1 => rotate_right_get_carry_u8(value),
+            2 => rotate_left_through_carry_u8(value, self.get_f_carry()),
+            3 => rotate_right_through_carry_u8(value, self.get_f_carry()),
+            // SLA
+            4 => (value << 1, get_bit_u8(value, 7)),
+            // SRA, bit 7 is kept
+            5 => ((value >> 1) | (value & 0x80), get_bit_u8(value, 0)),
+            // SWAP
+            6 => (value.rotate_left(4), false),
+            // SRL
+            _ => (value >> 1, get_bit_u8(value, 0)),
+        }
+    }
+}
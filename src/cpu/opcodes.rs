@@ -0,0 +1,645 @@
+// Static metadata for every SM83 opcode, shared by the executor, disassembler and assembler.
+// Tables according to: https://gbdev.io/gb-opcodes/optables/
+//
+// Operand placeholders in mnemonics:
+// - `d8` / `d16`: immediate 8/16-bit data
+// - `a8`: 8-bit offset into 0xFF00-0xFFFF
+// - `a16`: 16-bit address
+// - `r8`: signed 8-bit offset
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlagEffect {
+    Unaffected,
+    Reset,
+    Set,
+    /// Depends on the result of the operation
+    Affected,
+}
+
+impl FlagEffect {
+    const fn from_char(char: u8) -> Self {
+        match char {
+            b'-' => Self::Unaffected,
+            b'0' => Self::Reset,
+            b'1' => Self::Set,
+            _ => Self::Affected,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FlagEffects {
+    zero: FlagEffect,
+    subtract: FlagEffect,
+    half_carry: FlagEffect,
+    carry: FlagEffect,
+}
+
+impl FlagEffects {
+    /// Parses the notation used in opcode tables, e.g. "Z0H-"
+    const fn parse(flags: &str) -> Self {
+        let flags = flags.as_bytes();
+        Self {
+            zero: FlagEffect::from_char(flags[0]),
+            subtract: FlagEffect::from_char(flags[1]),
+            half_carry: FlagEffect::from_char(flags[2]),
+            carry: FlagEffect::from_char(flags[3]),
+        }
+    }
+
+    pub fn get_zero(&self) -> FlagEffect {
+        self.zero
+    }
+
+    pub fn get_subtract(&self) -> FlagEffect {
+        self.subtract
+    }
+
+    pub fn get_half_carry(&self) -> FlagEffect {
+        self.half_carry
+    }
+
+    pub fn get_carry(&self) -> FlagEffect {
+        self.carry
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OpcodeInfo {
+    mnemonic: &'static str,
+    /// Length in bytes including operands, prefixed opcodes include the 0xCB prefix
+    length: u8,
+    /// M-cycles if a conditional branch is not taken, prefixed opcodes include the prefix fetch
+    cycles: u8,
+    /// M-cycles if a conditional branch is taken, equal to cycles for all other instructions
+    cycles_taken: u8,
+    flags: FlagEffects,
+}
+
+impl OpcodeInfo {
+    pub fn get_mnemonic(&self) -> &'static str {
+        self.mnemonic
+    }
+
+    pub fn get_length(&self) -> u8 {
+        self.length
+    }
+
+    pub fn get_cycles(&self) -> u8 {
+        self.cycles
+    }
+
+    pub fn get_cycles_taken(&self) -> u8 {
+        self.cycles_taken
+    }
+
+    pub fn get_flags(&self) -> FlagEffects {
+        self.flags
+    }
+
+    pub fn is_conditional(&self) -> bool {
+        self.cycles != self.cycles_taken
+    }
+}
+
+const fn info(mnemonic: &'static str, length: u8, cycles: u8, cycles_taken: u8, flags: &str) -> OpcodeInfo {
+    OpcodeInfo {
+        mnemonic,
+        length,
+        cycles,
+        cycles_taken,
+        flags: FlagEffects::parse(flags),
+    }
+}
+
+pub const CB_PREFIX: u8 = 0xCB;
+
+/// Returns None for the 11 opcodes that don't exist and lock up the CPU.
+pub fn get_opcode_info(opcode: u8) -> Option<&'static OpcodeInfo> {
+    OPCODES[opcode as usize].as_ref()
+}
+
+pub fn get_cb_opcode_info(opcode: u8) -> &'static OpcodeInfo {
+    &CB_OPCODES[opcode as usize]
+}
+
+/// Unprefixed opcodes, indexed by opcode, None for illegal opcodes.
+/// Entry 0xCB only describes the prefix itself, see [`CB_OPCODES`].
+pub static OPCODES: [Option<OpcodeInfo>; 256] = [
+    /* 0x00 */ Some(info("NOP", 1, 1, 1, "----")),
+    /* 0x01 */ Some(info("LD BC,d16", 3, 3, 3, "----")),
+    /* 0x02 */ Some(info("LD (BC),A", 1, 2, 2, "----")),
+    /* 0x03 */ Some(info("INC BC", 1, 2, 2, "----")),
+    /* 0x04 */ Some(info("INC B", 1, 1, 1, "Z0H-")),
+    /* 0x05 */ Some(info("DEC B", 1, 1, 1, "Z1H-")),
+    /* 0x06 */ Some(info("LD B,d8", 2, 2, 2, "----")),
+    /* 0x07 */ Some(info("RLCA", 1, 1, 1, "000C")),
+    /* 0x08 */ Some(info("LD (a16),SP", 3, 5, 5, "----")),
+    /* 0x09 */ Some(info("ADD HL,BC", 1, 2, 2, "-0HC")),
+    /* 0x0A */ Some(info("LD A,(BC)", 1, 2, 2, "----")),
+    /* 0x0B */ Some(info("DEC BC", 1, 2, 2, "----")),
+    /* 0x0C */ Some(info("INC C", 1, 1, 1, "Z0H-")),
+    /* 0x0D */ Some(info("DEC C", 1, 1, 1, "Z1H-")),
+    /* 0x0E */ Some(info("LD C,d8", 2, 2, 2, "----")),
+    /* 0x0F */ Some(info("RRCA", 1, 1, 1, "000C")),
+    /* 0x10 */ Some(info("STOP", 2, 1, 1, "----")),
+    /* 0x11 */ Some(info("LD DE,d16", 3, 3, 3, "----")),
+    /* 0x12 */ Some(info("LD (DE),A", 1, 2, 2, "----")),
+    /* 0x13 */ Some(info("INC DE", 1, 2, 2, "----")),
+    /* 0x14 */ Some(info("INC D", 1, 1, 1, "Z0H-")),
+    /* 0x15 */ Some(info("DEC D", 1, 1, 1, "Z1H-")),
+    /* 0x16 */ Some(info("LD D,d8", 2, 2, 2, "----")),
+    /* 0x17 */ Some(info("RLA", 1, 1, 1, "000C")),
+    /* 0x18 */ Some(info("JR r8", 2, 3, 3, "----")),
+    /* 0x19 */ Some(info("ADD HL,DE", 1, 2, 2, "-0HC")),
+    /* 0x1A */ Some(info("LD A,(DE)", 1, 2, 2, "----")),
+    /* 0x1B */ Some(info("DEC DE", 1, 2, 2, "----")),
+    /* 0x1C */ Some(info("INC E", 1, 1, 1, "Z0H-")),
+    /* 0x1D */ Some(info("DEC E", 1, 1, 1, "Z1H-")),
+    /* 0x1E */ Some(info("LD E,d8", 2, 2, 2, "----")),
+    /* 0x1F */ Some(info("RRA", 1, 1, 1, "000C")),
+    /* 0x20 */ Some(info("JR NZ,r8", 2, 2, 3, "----")),
+    /* 0x21 */ Some(info("LD HL,d16", 3, 3, 3, "----")),
+    /* 0x22 */ Some(info("LD (HL+),A", 1, 2, 2, "----")),
+    /* 0x23 */ Some(info("INC HL", 1, 2, 2, "----")),
+    /* 0x24 */ Some(info("INC H", 1, 1, 1, "Z0H-")),
+    /* 0x25 */ Some(info("DEC H", 1, 1, 1, "Z1H-")),
+    /* 0x26 */ Some(info("LD H,d8", 2, 2, 2, "----")),
+    /* 0x27 */ Some(info("DAA", 1, 1, 1, "Z-0C")),
+    /* 0x28 */ Some(info("JR Z,r8", 2, 2, 3, "----")),
+    /* 0x29 */ Some(info("ADD HL,HL", 1, 2, 2, "-0HC")),
+    /* 0x2A */ Some(info("LD A,(HL+)", 1, 2, 2, "----")),
+    /* 0x2B */ Some(info("DEC HL", 1, 2, 2, "----")),
+    /* 0x2C */ Some(info("INC L", 1, 1, 1, "Z0H-")),
+    /* 0x2D */ Some(info("DEC L", 1, 1, 1, "Z1H-")),
+    /* 0x2E */ Some(info("LD L,d8", 2, 2, 2, "----")),
+    /* 0x2F */ Some(info("CPL", 1, 1, 1, "-11-")),
+    /* 0x30 */ Some(info("JR NC,r8", 2, 2, 3, "----")),
+    /* 0x31 */ Some(info("LD SP,d16", 3, 3, 3, "----")),
+    /* 0x32 */ Some(info("LD (HL-),A", 1, 2, 2, "----")),
+    /* 0x33 */ Some(info("INC SP", 1, 2, 2, "----")),
+    /* 0x34 */ Some(info("INC (HL)", 1, 3, 3, "Z0H-")),
+    /* 0x35 */ Some(info("DEC (HL)", 1, 3, 3, "Z1H-")),
+    /* 0x36 */ Some(info("LD (HL),d8", 2, 3, 3, "----")),
+    /* 0x37 */ Some(info("SCF", 1, 1, 1, "-001")),
+    /* 0x38 */ Some(info("JR C,r8", 2, 2, 3, "----")),
+    /* 0x39 */ Some(info("ADD HL,SP", 1, 2, 2, "-0HC")),
+    /* 0x3A */ Some(info("LD A,(HL-)", 1, 2, 2, "----")),
+    /* 0x3B */ Some(info("DEC SP", 1, 2, 2, "----")),
+    /* 0x3C */ Some(info("INC A", 1, 1, 1, "Z0H-")),
+    /* 0x3D */ Some(info("DEC A", 1, 1, 1, "Z1H-")),
+    /* 0x3E */ Some(info("LD A,d8", 2, 2, 2, "----")),
+    /* 0x3F */ Some(info("CCF", 1, 1, 1, "-00C")),
+    /* 0x40 */ Some(info("LD B,B", 1, 1, 1, "----")),
+    /* 0x41 */ Some(info("LD B,C", 1, 1, 1, "----")),
+    /* 0x42 */ Some(info("LD B,D", 1, 1, 1, "----")),
+    /* 0x43 */ Some(info("LD B,E", 1, 1, 1, "----")),
+    /* 0x44 */ Some(info("LD B,H", 1, 1, 1, "----")),
+    /* 0x45 */ Some(info("LD B,L", 1, 1, 1, "----")),
+    /* 0x46 */ Some(info("LD B,(HL)", 1, 2, 2, "----")),
+    /* 0x47 */ Some(info("LD B,A", 1, 1, 1, "----")),
+    /* 0x48 */ Some(info("LD C,B", 1, 1, 1, "----")),
+    /* 0x49 */ Some(info("LD C,C", 1, 1, 1, "----")),
+    /* 0x4A */ Some(info("LD C,D", 1, 1, 1, "----")),
+    /* 0x4B */ Some(info("LD C,E", 1, 1, 1, "----")),
+    /* 0x4C */ Some(info("LD C,H", 1, 1, 1, "----")),
+    /* 0x4D */ Some(info("LD C,L", 1, 1, 1, "----")),
+    /* 0x4E */ Some(info("LD C,(HL)", 1, 2, 2, "----")),
+    /* 0x4F */ Some(info("LD C,A", 1, 1, 1, "----")),
+    /* 0x50 */ Some(info("LD D,B", 1, 1, 1, "----")),
+    /* 0x51 */ Some(info("LD D,C", 1, 1, 1, "----")),
+    /* 0x52 */ Some(info("LD D,D", 1, 1, 1, "----")),
+    /* 0x53 */ Some(info("LD D,E", 1, 1, 1, "----")),
+    /* 0x54 */ Some(info("LD D,H", 1, 1, 1, "----")),
+    /* 0x55 */ Some(info("LD D,L", 1, 1, 1, "----")),
+    /* 0x56 */ Some(info("LD D,(HL)", 1, 2, 2, "----")),
+    /* 0x57 */ Some(info("LD D,A", 1, 1, 1, "----")),
+    /* 0x58 */ Some(info("LD E,B", 1, 1, 1, "----")),
+    /* 0x59 */ Some(info("LD E,C", 1, 1, 1, "----")),
+    /* 0x5A */ Some(info("LD E,D", 1, 1, 1, "----")),
+    /* 0x5B */ Some(info("LD E,E", 1, 1, 1, "----")),
+    /* 0x5C */ Some(info("LD E,H", 1, 1, 1, "----")),
+    /* 0x5D */ Some(info("LD E,L", 1, 1, 1, "----")),
+    /* 0x5E */ Some(info("LD E,(HL)", 1, 2, 2, "----")),
+    /* 0x5F */ Some(info("LD E,A", 1, 1, 1, "----")),
+    /* 0x60 */ Some(info("LD H,B", 1, 1, 1, "----")),
+    /* 0x61 */ Some(info("LD H,C", 1, 1, 1, "----")),
+    /* 0x62 */ Some(info("LD H,D", 1, 1, 1, "----")),
+    /* 0x63 */ Some(info("LD H,E", 1, 1, 1, "----")),
+    /* 0x64 */ Some(info("LD H,H", 1, 1, 1, "----")),
+    /* 0x65 */ Some(info("LD H,L", 1, 1, 1, "----")),
+    /* 0x66 */ Some(info("LD H,(HL)", 1, 2, 2, "----")),
+    /* 0x67 */ Some(info("LD H,A", 1, 1, 1, "----")),
+    /* 0x68 */ Some(info("LD L,B", 1, 1, 1, "----")),
+    /* 0x69 */ Some(info("LD L,C", 1, 1, 1, "----")),
+    /* 0x6A */ Some(info("LD L,D", 1, 1, 1, "----")),
+    /* 0x6B */ Some(info("LD L,E", 1, 1, 1, "----")),
+    /* 0x6C */ Some(info("LD L,H", 1, 1, 1, "----")),
+    /* 0x6D */ Some(info("LD L,L", 1, 1, 1, "----")),
+    /* 0x6E */ Some(info("LD L,(HL)", 1, 2, 2, "----")),
+    /* 0x6F */ Some(info("LD L,A", 1, 1, 1, "----")),
+    /* 0x70 */ Some(info("LD (HL),B", 1, 2, 2, "----")),
+    /* 0x71 */ Some(info("LD (HL),C", 1, 2, 2, "----")),
+    /* 0x72 */ Some(info("LD (HL),D", 1, 2, 2, "----")),
+    /* 0x73 */ Some(info("LD (HL),E", 1, 2, 2, "----")),
+    /* 0x74 */ Some(info("LD (HL),H", 1, 2, 2, "----")),
+    /* 0x75 */ Some(info("LD (HL),L", 1, 2, 2, "----")),
+    /* 0x76 */ Some(info("HALT", 1, 1, 1, "----")),
+    /* 0x77 */ Some(info("LD (HL),A", 1, 2, 2, "----")),
+    /* 0x78 */ Some(info("LD A,B", 1, 1, 1, "----")),
+    /* 0x79 */ Some(info("LD A,C", 1, 1, 1, "----")),
+    /* 0x7A */ Some(info("LD A,D", 1, 1, 1, "----")),
+    /* 0x7B */ Some(info("LD A,E", 1, 1, 1, "----")),
+    /* 0x7C */ Some(info("LD A,H", 1, 1, 1, "----")),
+    /* 0x7D */ Some(info("LD A,L", 1, 1, 1, "----")),
+    /* 0x7E */ Some(info("LD A,(HL)", 1, 2, 2, "----")),
+    /* 0x7F */ Some(info("LD A,A", 1, 1, 1, "----")),
+    /* 0x80 */ Some(info("ADD A,B", 1, 1, 1, "Z0HC")),
+    /* 0x81 */ Some(info("ADD A,C", 1, 1, 1, "Z0HC")),
+    /* 0x82 */ Some(info("ADD A,D", 1, 1, 1, "Z0HC")),
+    /* 0x83 */ Some(info("ADD A,E", 1, 1, 1, "Z0HC")),
+    /* 0x84 */ Some(info("ADD A,H", 1, 1, 1, "Z0HC")),
+    /* 0x85 */ Some(info("ADD A,L", 1, 1, 1, "Z0HC")),
+    /* 0x86 */ Some(info("ADD A,(HL)", 1, 2, 2, "Z0HC")),
+    /* 0x87 */ Some(info("ADD A,A", 1, 1, 1, "Z0HC")),
+    /* 0x88 */ Some(info("ADC A,B", 1, 1, 1, "Z0HC")),
+    /* 0x89 */ Some(info("ADC A,C", 1, 1, 1, "Z0HC")),
+    /* 0x8A */ Some(info("ADC A,D", 1, 1, 1, "Z0HC")),
+    /* 0x8B */ Some(info("ADC A,E", 1, 1, 1, "Z0HC")),
+    /* 0x8C */ Some(info("ADC A,H", 1, 1, 1, "Z0HC")),
+    /* 0x8D */ Some(info("ADC A,L", 1, 1, 1, "Z0HC")),
+    /* 0x8E */ Some(info("ADC A,(HL)", 1, 2, 2, "Z0HC")),
+    /* 0x8F */ Some(info("ADC A,A", 1, 1, 1, "Z0HC")),
+    /* 0x90 */ Some(info("SUB B", 1, 1, 1, "Z1HC")),
+    /* 0x91 */ Some(info("SUB C", 1, 1, 1, "Z1HC")),
+    /* 0x92 */ Some(info("SUB D", 1, 1, 1, "Z1HC")),
+    /* 0x93 */ Some(info("SUB E", 1, 1, 1, "Z1HC")),
+    /* 0x94 */ Some(info("SUB H", 1, 1, 1, "Z1HC")),
+    /* 0x95 */ Some(info("SUB L", 1, 1, 1, "Z1HC")),
+    /* 0x96 */ Some(info("SUB (HL)", 1, 2, 2, "Z1HC")),
+    /* 0x97 */ Some(info("SUB A", 1, 1, 1, "Z1HC")),
+    /* 0x98 */ Some(info("SBC A,B", 1, 1, 1, "Z1HC")),
+    /* 0x99 */ Some(info("SBC A,C", 1, 1, 1, "Z1HC")),
+    /* 0x9A */ Some(info("SBC A,D", 1, 1, 1, "Z1HC")),
+    /* 0x9B */ Some(info("SBC A,E", 1, 1, 1, "Z1HC")),
+    /* 0x9C */ Some(info("SBC A,H", 1, 1, 1, "Z1HC")),
+    /* 0x9D */ Some(info("SBC A,L", 1, 1, 1, "Z1HC")),
+    /* 0x9E */ Some(info("SBC A,(HL)", 1, 2, 2, "Z1HC")),
+    /* 0x9F */ Some(info("SBC A,A", 1, 1, 1, "Z1HC")),
+    /* 0xA0 */ Some(info("AND B", 1, 1, 1, "Z010")),
+    /* 0xA1 */ Some(info("AND C", 1, 1, 1, "Z010")),
+    /* 0xA2 */ Some(info("AND D", 1, 1, 1, "Z010")),
+    /* 0xA3 */ Some(info("AND E", 1, 1, 1, "Z010")),
+    /* 0xA4 */ Some(info("AND H", 1, 1, 1, "Z010")),
+    /* 0xA5 */ Some(info("AND L", 1, 1, 1, "Z010")),
+    /* 0xA6 */ Some(info("AND (HL)", 1, 2, 2, "Z010")),
+    /* 0xA7 */ Some(info("AND A", 1, 1, 1, "Z010")),
+    /* 0xA8 */ Some(info("XOR B", 1, 1, 1, "Z000")),
+    /* 0xA9 */ Some(info("XOR C", 1, 1, 1, "Z000")),
+    /* 0xAA */ Some(info("XOR D", 1, 1, 1, "Z000")),
+    /* 0xAB */ Some(info("XOR E", 1, 1, 1, "Z000")),
+    /* 0xAC */ Some(info("XOR H", 1, 1, 1, "Z000")),
+    /* 0xAD */ Some(info("XOR L", 1, 1, 1, "Z000")),
+    /* 0xAE */ Some(info("XOR (HL)", 1, 2, 2, "Z000")),
+    /* 0xAF */ Some(info("XOR A", 1, 1, 1, "Z000")),
+    /* 0xB0 */ Some(info("OR B", 1, 1, 1, "Z000")),
+    /* 0xB1 */ Some(info("OR C", 1, 1, 1, "Z000")),
+    /* 0xB2 */ Some(info("OR D", 1, 1, 1, "Z000")),
+    /* 0xB3 */ Some(info("OR E", 1, 1, 1, "Z000")),
+    /* 0xB4 */ Some(info("OR H", 1, 1, 1, "Z000")),
+    /* 0xB5 */ Some(info("OR L", 1, 1, 1, "Z000")),
+    /* 0xB6 */ Some(info("OR (HL)", 1, 2, 2, "Z000")),
+    /* 0xB7 */ Some(info("OR A", 1, 1, 1, "Z000")),
+    /* 0xB8 */ Some(info("CP B", 1, 1, 1, "Z1HC")),
+    /* 0xB9 */ Some(info("CP C", 1, 1, 1, "Z1HC")),
+    /* 0xBA */ Some(info("CP D", 1, 1, 1, "Z1HC")),
+    /* 0xBB */ Some(info("CP E", 1, 1, 1, "Z1HC")),
+    /* 0xBC */ Some(info("CP H", 1, 1, 1, "Z1HC")),
+    /* 0xBD */ Some(info("CP L", 1, 1, 1, "Z1HC")),
+    /* 0xBE */ Some(info("CP (HL)", 1, 2, 2, "Z1HC")),
+    /* 0xBF */ Some(info("CP A", 1, 1, 1, "Z1HC")),
+    /* 0xC0 */ Some(info("RET NZ", 1, 2, 5, "----")),
+    /* 0xC1 */ Some(info("POP BC", 1, 3, 3, "----")),
+    /* 0xC2 */ Some(info("JP NZ,a16", 3, 3, 4, "----")),
+    /* 0xC3 */ Some(info("JP a16", 3, 4, 4, "----")),
+    /* 0xC4 */ Some(info("CALL NZ,a16", 3, 3, 6, "----")),
+    /* 0xC5 */ Some(info("PUSH BC", 1, 4, 4, "----")),
+    /* 0xC6 */ Some(info("ADD A,d8", 2, 2, 2, "Z0HC")),
+    /* 0xC7 */ Some(info("RST 00H", 1, 4, 4, "----")),
+    /* 0xC8 */ Some(info("RET Z", 1, 2, 5, "----")),
+    /* 0xC9 */ Some(info("RET", 1, 4, 4, "----")),
+    /* 0xCA */ Some(info("JP Z,a16", 3, 3, 4, "----")),
+    /* 0xCB */ Some(info("PREFIX CB", 1, 1, 1, "----")),
+    /* 0xCC */ Some(info("CALL Z,a16", 3, 3, 6, "----")),
+    /* 0xCD */ Some(info("CALL a16", 3, 6, 6, "----")),
+    /* 0xCE */ Some(info("ADC A,d8", 2, 2, 2, "Z0HC")),
+    /* 0xCF */ Some(info("RST 08H", 1, 4, 4, "----")),
+    /* 0xD0 */ Some(info("RET NC", 1, 2, 5, "----")),
+    /* 0xD1 */ Some(info("POP DE", 1, 3, 3, "----")),
+    /* 0xD2 */ Some(info("JP NC,a16", 3, 3, 4, "----")),
+    /* 0xD3 */ None,
+    /* 0xD4 */ Some(info("CALL NC,a16", 3, 3, 6, "----")),
+    /* 0xD5 */ Some(info("PUSH DE", 1, 4, 4, "----")),
+    /* 0xD6 */ Some(info("SUB d8", 2, 2, 2, "Z1HC")),
+    /* 0xD7 */ Some(info("RST 10H", 1, 4, 4, "----")),
+    /* 0xD8 */ Some(info("RET C", 1, 2, 5, "----")),
+    /* 0xD9 */ Some(info("RETI", 1, 4, 4, "----")),
+    /* 0xDA */ Some(info("JP C,a16", 3, 3, 4, "----")),
+    /* 0xDB */ None,
+    /* 0xDC */ Some(info("CALL C,a16", 3, 3, 6, "----")),
+    /* 0xDD */ None,
+    /* 0xDE */ Some(info("SBC A,d8", 2, 2, 2, "Z1HC")),
+    /* 0xDF */ Some(info("RST 18H", 1, 4, 4, "----")),
+    /* 0xE0 */ Some(info("LDH (a8),A", 2, 3, 3, "----")),
+    /* 0xE1 */ Some(info("POP HL", 1, 3, 3, "----")),
+    /* 0xE2 */ Some(info("LD (C),A", 1, 2, 2, "----")),
+    /* 0xE3 */ None,
+    /* 0xE4 */ None,
+    /* 0xE5 */ Some(info("PUSH HL", 1, 4, 4, "----")),
+    /* 0xE6 */ Some(info("AND d8", 2, 2, 2, "Z010")),
+    /* 0xE7 */ Some(info("RST 20H", 1, 4, 4, "----")),
+    /* 0xE8 */ Some(info("ADD SP,r8", 2, 4, 4, "00HC")),
+    /* 0xE9 */ Some(info("JP HL", 1, 1, 1, "----")),
+    /* 0xEA */ Some(info("LD (a16),A", 3, 4, 4, "----")),
+    /* 0xEB */ None,
+    /* 0xEC */ None,
+    /* 0xED */ None,
+    /* 0xEE */ Some(info("XOR d8", 2, 2, 2, "Z000")),
+    /* 0xEF */ Some(info("RST 28H", 1, 4, 4, "----")),
+    /* 0xF0 */ Some(info("LDH A,(a8)", 2, 3, 3, "----")),
+    /* 0xF1 */ Some(info("POP AF", 1, 3, 3, "ZNHC")),
+    /* 0xF2 */ Some(info("LD A,(C)", 1, 2, 2, "----")),
+    /* 0xF3 */ Some(info("DI", 1, 1, 1, "----")),
+    /* 0xF4 */ None,
+    /* 0xF5 */ Some(info("PUSH AF", 1, 4, 4, "----")),
+    /* 0xF6 */ Some(info("OR d8", 2, 2, 2, "Z000")),
+    /* 0xF7 */ Some(info("RST 30H", 1, 4, 4, "----")),
+    /* 0xF8 */ Some(info("LD HL,SP+r8", 2, 3, 3, "00HC")),
+    /* 0xF9 */ Some(info("LD SP,HL", 1, 2, 2, "----")),
+    /* 0xFA */ Some(info("LD A,(a16)", 3, 4, 4, "----")),
+    /* 0xFB */ Some(info("EI", 1, 1, 1, "----")),
+    /* 0xFC */ None,
+    /* 0xFD */ None,
+    /* 0xFE */ Some(info("CP d8", 2, 2, 2, "Z1HC")),
+    /* 0xFF */ Some(info("RST 38H", 1, 4, 4, "----")),
+];
+
+/// Opcodes following the 0xCB prefix, indexed by the second byte.
+pub static CB_OPCODES: [OpcodeInfo; 256] = [
+    /* 0x00 */ info("RLC B", 2, 2, 2, "Z00C"),
+    /* 0x01 */ info("RLC C", 2, 2, 2, "Z00C"),
+    /* 0x02 */ info("RLC D", 2, 2, 2, "Z00C"),
+    /* 0x03 */ info("RLC E", 2, 2, 2, "Z00C"),
+    /* 0x04 */ info("RLC H", 2, 2, 2, "Z00C"),
+    /* 0x05 */ info("RLC L", 2, 2, 2, "Z00C"),
+    /* 0x06 */ info("RLC (HL)", 2, 4, 4, "Z00C"),
+    /* 0x07 */ info("RLC A", 2, 2, 2, "Z00C"),
+    /* 0x08 */ info("RRC B", 2, 2, 2, "Z00C"),
+    /* 0x09 */ info("RRC C", 2, 2, 2, "Z00C"),
+    /* 0x0A */ info("RRC D", 2, 2, 2, "Z00C"),
+    /* 0x0B */ info("RRC E", 2, 2, 2, "Z00C"),
+    /* 0x0C */ info("RRC H", 2, 2, 2, "Z00C"),
+    /* 0x0D */ info("RRC L", 2, 2, 2, "Z00C"),
+    /* 0x0E */ info("RRC (HL)", 2, 4, 4, "Z00C"),
+    /* 0x0F */ info("RRC A", 2, 2, 2, "Z00C"),
+    /* 0x10 */ info("RL B", 2, 2, 2, "Z00C"),
+    /* 0x11 */ info("RL C", 2, 2, 2, "Z00C"),
+    /* 0x12 */ info("RL D", 2, 2, 2, "Z00C"),
+    /* 0x13 */ info("RL E", 2, 2, 2, "Z00C"),
+    /* 0x14 */ info("RL H", 2, 2, 2, "Z00C"),
+    /* 0x15 */ info("RL L", 2, 2, 2, "Z00C"),
+    /* 0x16 */ info("RL (HL)", 2, 4, 4, "Z00C"),
+    /* 0x17 */ info("RL A", 2, 2, 2, "Z00C"),
+    /* 0x18 */ info("RR B", 2, 2, 2, "Z00C"),
+    /* 0x19 */ info("RR C", 2, 2, 2, "Z00C"),
+    /* 0x1A */ info("RR D", 2, 2, 2, "Z00C"),
+    /* 0x1B */ info("RR E", 2, 2, 2, "Z00C"),
+    /* 0x1C */ info("RR H", 2, 2, 2, "Z00C"),
+    /* 0x1D */ info("RR L", 2, 2, 2, "Z00C"),
+    /* 0x1E */ info("RR (HL)", 2, 4, 4, "Z00C"),
+    /* 0x1F */ info("RR A", 2, 2, 2, "Z00C"),
+    /* 0x20 */ info("SLA B", 2, 2, 2, "Z00C"),
+    /* 0x21 */ info("SLA C", 2, 2, 2, "Z00C"),
+    /* 0x22 */ info("SLA D", 2, 2, 2, "Z00C"),
+    /* 0x23 */ info("SLA E", 2, 2, 2, "Z00C"),
+    /* 0x24 */ info("SLA H", 2, 2, 2, "Z00C"),
+    /* 0x25 */ info("SLA L", 2, 2, 2, "Z00C"),
+    /* 0x26 */ info("SLA (HL)", 2, 4, 4, "Z00C"),
+    /* 0x27 */ info("SLA A", 2, 2, 2, "Z00C"),
+    /* 0x28 */ info("SRA B", 2, 2, 2, "Z00C"),
+    /* 0x29 */ info("SRA C", 2, 2, 2, "Z00C"),
+    /* 0x2A */ info("SRA D", 2, 2, 2, "Z00C"),
+    /* 0x2B */ info("SRA E", 2, 2, 2, "Z00C"),
+    /* 0x2C */ info("SRA H", 2, 2, 2, "Z00C"),
+    /* 0x2D */ info("SRA L", 2, 2, 2, "Z00C"),
+    /* 0x2E */ info("SRA (HL)", 2, 4, 4, "Z00C"),
+    /* 0x2F */ info("SRA A", 2, 2, 2, "Z00C"),
+    /* 0x30 */ info("SWAP B", 2, 2, 2, "Z000"),
+    /* 0x31 */ info("SWAP C", 2, 2, 2, "Z000"),
+    /* 0x32 */ info("SWAP D", 2, 2, 2, "Z000"),
+    /* 0x33 */ info("SWAP E", 2, 2, 2, "Z000"),
+    /* 0x34 */ info("SWAP H", 2, 2, 2, "Z000"),
+    /* 0x35 */ info("SWAP L", 2, 2, 2, "Z000"),
+    /* 0x36 */ info("SWAP (HL)", 2, 4, 4, "Z000"),
+    /* 0x37 */ info("SWAP A", 2, 2, 2, "Z000"),
+    /* 0x38 */ info("SRL B", 2, 2, 2, "Z00C"),
+    /* 0x39 */ info("SRL C", 2, 2, 2, "Z00C"),
+    /* 0x3A */ info("SRL D", 2, 2, 2, "Z00C"),
+    /* 0x3B */ info("SRL E", 2, 2, 2, "Z00C"),
+    /* 0x3C */ info("SRL H", 2, 2, 2, "Z00C"),
+    /* 0x3D */ info("SRL L", 2, 2, 2, "Z00C"),
+    /* 0x3E */ info("SRL (HL)", 2, 4, 4, "Z00C"),
+    /* 0x3F */ info("SRL A", 2, 2, 2, "Z00C"),
+    /* 0x40 */ info("BIT 0,B", 2, 2, 2, "Z01-"),
+    /* 0x41 */ info("BIT 0,C", 2, 2, 2, "Z01-"),
+    /* 0x42 */ info("BIT 0,D", 2, 2, 2, "Z01-"),
+    /* 0x43 */ info("BIT 0,E", 2, 2, 2, "Z01-"),
+    /* 0x44 */ info("BIT 0,H", 2, 2, 2, "Z01-"),
+    /* 0x45 */ info("BIT 0,L", 2, 2, 2, "Z01-"),
+    /* 0x46 */ info("BIT 0,(HL)", 2, 3, 3, "Z01-"),
+    /* 0x47 */ info("BIT 0,A", 2, 2, 2, "Z01-"),
+    /* 0x48 */ info("BIT 1,B", 2, 2, 2, "Z01-"),
+    /* 0x49 */ info("BIT 1,C", 2, 2, 2, "Z01-"),
+    /* 0x4A */ info("BIT 1,D", 2, 2, 2, "Z01-"),
+    /* 0x4B */ info("BIT 1,E", 2, 2, 2, "Z01-"),
+    /* 0x4C */ info("BIT 1,H", 2, 2, 2, "Z01-"),
+    /* 0x4D */ info("BIT 1,L", 2, 2, 2, "Z01-"),
+    /* 0x4E */ info("BIT 1,(HL)", 2, 3, 3, "Z01-"),
+    /* 0x4F */ info("BIT 1,A", 2, 2, 2, "Z01-"),
+    /* 0x50 */ info("BIT 2,B", 2, 2, 2, "Z01-"),
+    /* 0x51 */ info("BIT 2,C", 2, 2, 2, "Z01-"),
+    /* 0x52 */ info("BIT 2,D", 2, 2, 2, "Z01-"),
+    /* 0x53 */ info("BIT 2,E", 2, 2, 2, "Z01-"),
+    /* 0x54 */ info("BIT 2,H", 2, 2, 2, "Z01-"),
+    /* 0x55 */ info("BIT 2,L", 2, 2, 2, "Z01-"),
+    /* 0x56 */ info("BIT 2,(HL)", 2, 3, 3, "Z01-"),
+    /* 0x57 */ info("BIT 2,A", 2, 2, 2, "Z01-"),
+    /* 0x58 */ info("BIT 3,B", 2, 2, 2, "Z01-"),
+    /* 0x59 */ info("BIT 3,C", 2, 2, 2, "Z01-"),
+    /* 0x5A */ info("BIT 3,D", 2, 2, 2, "Z01-"),
+    /* 0x5B */ info("BIT 3,E", 2, 2, 2, "Z01-"),
+    /* 0x5C */ info("BIT 3,H", 2, 2, 2, "Z01-"),
+    /* 0x5D */ info("BIT 3,L", 2, 2, 2, "Z01-"),
+    /* 0x5E */ info("BIT 3,(HL)", 2, 3, 3, "Z01-"),
+    /* 0x5F */ info("BIT 3,A", 2, 2, 2, "Z01-"),
+    /* 0x60 */ info("BIT 4,B", 2, 2, 2, "Z01-"),
+    /* 0x61 */ info("BIT 4,C", 2, 2, 2, "Z01-"),
+    /* 0x62 */ info("BIT 4,D", 2, 2, 2, "Z01-"),
+    /* 0x63 */ info("BIT 4,E", 2, 2, 2, "Z01-"),
+    /* 0x64 */ info("BIT 4,H", 2, 2, 2, "Z01-"),
+    /* 0x65 */ info("BIT 4,L", 2, 2, 2, "Z01-"),
+    /* 0x66 */ info("BIT 4,(HL)", 2, 3, 3, "Z01-"),
+    /* 0x67 */ info("BIT 4,A", 2, 2, 2, "Z01-"),
+    /* 0x68 */ info("BIT 5,B", 2, 2, 2, "Z01-"),
+    /* 0x69 */ info("BIT 5,C", 2, 2, 2, "Z01-"),
+    /* 0x6A */ info("BIT 5,D", 2, 2, 2, "Z01-"),
+    /* 0x6B */ info("BIT 5,E", 2, 2, 2, "Z01-"),
+    /* 0x6C */ info("BIT 5,H", 2, 2, 2, "Z01-"),
+    /* 0x6D */ info("BIT 5,L", 2, 2, 2, "Z01-"),
+    /* 0x6E */ info("BIT 5,(HL)", 2, 3, 3, "Z01-"),
+    /* 0x6F */ info("BIT 5,A", 2, 2, 2, "Z01-"),
+    /* 0x70 */ info("BIT 6,B", 2, 2, 2, "Z01-"),
+    /* 0x71 */ info("BIT 6,C", 2, 2, 2, "Z01-"),
+    /* 0x72 */ info("BIT 6,D", 2, 2, 2, "Z01-"),
+    /* 0x73 */ info("BIT 6,E", 2, 2, 2, "Z01-"),
+    /* 0x74 */ info("BIT 6,H", 2, 2, 2, "Z01-"),
+    /* 0x75 */ info("BIT 6,L", 2, 2, 2, "Z01-"),
+    /* 0x76 */ info("BIT 6,(HL)", 2, 3, 3, "Z01-"),
+    /* 0x77 */ info("BIT 6,A", 2, 2, 2, "Z01-"),
+    /* 0x78 */ info("BIT 7,B", 2, 2, 2, "Z01-"),
+    /* 0x79 */ info("BIT 7,C", 2, 2, 2, "Z01-"),
+    /* 0x7A */ info("BIT 7,D", 2, 2, 2, "Z01-"),
+    /* 0x7B */ info("BIT 7,E", 2, 2, 2, "Z01-"),
+    /* 0x7C */ info("BIT 7,H", 2, 2, 2, "Z01-"),
+    /* 0x7D */ info("BIT 7,L", 2, 2, 2, "Z01-"),
+    /* 0x7E */ info("BIT 7,(HL)", 2, 3, 3, "Z01-"),
+    /* 0x7F */ info("BIT 7,A", 2, 2, 2, "Z01-"),
+    /* 0x80 */ info("RES 0,B", 2, 2, 2, "----"),
+    /* 0x81 */ info("RES 0,C", 2, 2, 2, "----"),
+    /* 0x82 */ info("RES 0,D", 2, 2, 2, "----"),
+    /* 0x83 */ info("RES 0,E", 2, 2, 2, "----"),
+    /* 0x84 */ info("RES 0,H", 2, 2, 2, "----"),
+    /* 0x85 */ info("RES 0,L", 2, 2, 2, "----"),
+    /* 0x86 */ info("RES 0,(HL)", 2, 4, 4, "----"),
+    /* 0x87 */ info("RES 0,A", 2, 2, 2, "----"),
+    /* 0x88 */ info("RES 1,B", 2, 2, 2, "----"),
+    /* 0x89 */ info("RES 1,C", 2, 2, 2, "----"),
+    /* 0x8A */ info("RES 1,D", 2, 2, 2, "----"),
+    /* 0x8B */ info("RES 1,E", 2, 2, 2, "----"),
+    /* 0x8C */ info("RES 1,H", 2, 2, 2, "----"),
+    /* 0x8D */ info("RES 1,L", 2, 2, 2, "----"),
+    /* 0x8E */ info("RES 1,(HL)", 2, 4, 4, "----"),
+    /* 0x8F */ info("RES 1,A", 2, 2, 2, "----"),
+    /* 0x90 */ info("RES 2,B", 2, 2, 2, "----"),
+    /* 0x91 */ info("RES 2,C", 2, 2, 2, "----"),
+    /* 0x92 */ info("RES 2,D", 2, 2, 2, "----"),
+    /* 0x93 */ info("RES 2,E", 2, 2, 2, "----"),
+    /* 0x94 */ info("RES 2,H", 2, 2, 2, "----"),
+    /* 0x95 */ info("RES 2,L", 2, 2, 2, "----"),
+    /* 0x96 */ info("RES 2,(HL)", 2, 4, 4, "----"),
+    /* 0x97 */ info("RES 2,A", 2, 2, 2, "----"),
+    /* 0x98 */ info("RES 3,B", 2, 2, 2, "----"),
+    /* 0x99 */ info("RES 3,C", 2, 2, 2, "----"),
+    /* 0x9A */ info("RES 3,D", 2, 2, 2, "----"),
+    /* 0x9B */ info("RES 3,E", 2, 2, 2, "----"),
+    /* 0x9C */ info("RES 3,H", 2, 2, 2, "----"),
+    /* 0x9D */ info("RES 3,L", 2, 2, 2, "----"),
+    /* 0x9E */ info("RES 3,(HL)", 2, 4, 4, "----"),
+    /* 0x9F */ info("RES 3,A", 2, 2, 2, "----"),
+    /* 0xA0 */ info("RES 4,B", 2, 2, 2, "----"),
+    /* 0xA1 */ info("RES 4,C", 2, 2, 2, "----"),
+    /* 0xA2 */ info("RES 4,D", 2, 2, 2, "----"),
+    /* 0xA3 */ info("RES 4,E", 2, 2, 2, "----"),
+    /* 0xA4 */ info("RES 4,H", 2, 2, 2, "----"),
+    /* 0xA5 */ info("RES 4,L", 2, 2, 2, "----"),
+    /* 0xA6 */ info("RES 4,(HL)", 2, 4, 4, "----"),
+    /* 0xA7 */ info("RES 4,A", 2, 2, 2, "----"),
+    /* 0xA8 */ info("RES 5,B", 2, 2, 2, "----"),
+    /* 0xA9 */ info("RES 5,C", 2, 2, 2, "----"),
+    /* 0xAA */ info("RES 5,D", 2, 2, 2, "----"),
+    /* 0xAB */ info("RES 5,E", 2, 2, 2, "----"),
+    /* 0xAC */ info("RES 5,H", 2, 2, 2, "----"),
+    /* 0xAD */ info("RES 5,L", 2, 2, 2, "----"),
+    /* 0xAE */ info("RES 5,(HL)", 2, 4, 4, "----"),
+    /* 0xAF */ info("RES 5,A", 2, 2, 2, "----"),
+    /* 0xB0 */ info("RES 6,B", 2, 2, 2, "----"),
+    /* 0xB1 */ info("RES 6,C", 2, 2, 2, "----"),
+    /* 0xB2 */ info("RES 6,D", 2, 2, 2, "----"),
+    /* 0xB3 */ info("RES 6,E", 2, 2, 2, "----"),
+    /* 0xB4 */ info("RES 6,H", 2, 2, 2, "----"),
+    /* 0xB5 */ info("RES 6,L", 2, 2, 2, "----"),
+    /* 0xB6 */ info("RES 6,(HL)", 2, 4, 4, "----"),
+    /* 0xB7 */ info("RES 6,A", 2, 2, 2, "----"),
+    /* 0xB8 */ info("RES 7,B", 2, 2, 2, "----"),
+    /* 0xB9 */ info("RES 7,C", 2, 2, 2, "----"),
+    /* 0xBA */ info("RES 7,D", 2, 2, 2, "----"),
+    /* 0xBB */ info("RES 7,E", 2, 2, 2, "----"),
+    /* 0xBC */ info("RES 7,H", 2, 2, 2, "----"),
+    /* 0xBD */ info("RES 7,L", 2, 2, 2, "----"),
+    /* 0xBE */ info("RES 7,(HL)", 2, 4, 4, "----"),
+    /* 0xBF */ info("RES 7,A", 2, 2, 2, "----"),
+    /* 0xC0 */ info("SET 0,B", 2, 2, 2, "----"),
+    /* 0xC1 */ info("SET 0,C", 2, 2, 2, "----"),
+    /* 0xC2 */ info("SET 0,D", 2, 2, 2, "----"),
+    /* 0xC3 */ info("SET 0,E", 2, 2, 2, "----"),
+    /* 0xC4 */ info("SET 0,H", 2, 2, 2, "----"),
+    /* 0xC5 */ info("SET 0,L", 2, 2, 2, "----"),
+    /* 0xC6 */ info("SET 0,(HL)", 2, 4, 4, "----"),
+    /* 0xC7 */ info("SET 0,A", 2, 2, 2, "----"),
+    /* 0xC8 */ info("SET 1,B", 2, 2, 2, "----"),
+    /* 0xC9 */ info("SET 1,C", 2, 2, 2, "----"),
+    /* 0xCA */ info("SET 1,D", 2, 2, 2, "----"),
+    /* 0xCB */ info("SET 1,E", 2, 2, 2, "----"),
+    /* 0xCC */ info("SET 1,H", 2, 2, 2, "----"),
+    /* 0xCD */ info("SET 1,L", 2, 2, 2, "----"),
+    /* 0xCE */ info("SET 1,(HL)", 2, 4, 4, "----"),
+    /* 0xCF */ info("SET 1,A", 2, 2, 2, "----"),
+    /* 0xD0 */ info("SET 2,B", 2, 2, 2, "----"),
+    /* 0xD1 */ info("SET 2,C", 2, 2, 2, "----"),
+    /* 0xD2 */ info("SET 2,D", 2, 2, 2, "----"),
+    /* 0xD3 */ info("SET 2,E", 2, 2, 2, "----"),
+    /* 0xD4 */ info("SET 2,H", 2, 2, 2, "----"),
+    /* 0xD5 */ info("SET 2,L", 2, 2, 2, "----"),
+    /* 0xD6 */ info("SET 2,(HL)", 2, 4, 4, "----"),
+    /* 0xD7 */ info("SET 2,A", 2, 2, 2, "----"),
+    /* 0xD8 */ info("SET 3,B", 2, 2, 2, "----"),
+    /* 0xD9 */ info("SET 3,C", 2, 2, 2, "----"),
+    /* 0xDA */ info("SET 3,D", 2, 2, 2, "----"),
+    /* 0xDB */ info("SET 3,E", 2, 2, 2, "----"),
+    /* 0xDC */ info("SET 3,H", 2, 2, 2, "----"),
+    /* 0xDD */ info("SET 3,L", 2, 2, 2, "----"),
+    /* 0xDE */ info("SET 3,(HL)", 2, 4, 4, "----"),
+    /* 0xDF */ info("SET 3,A", 2, 2, 2, "----"),
+    /* 0xE0 */ info("SET 4,B", 2, 2, 2, "----"),
+    /* 0xE1 */ info("SET 4,C", 2, 2, 2, "----"),
+    /* 0xE2 */ info("SET 4,D", 2, 2, 2, "----"),
+    /* 0xE3 */ info("SET 4,E", 2, 2, 2, "----"),
+    /* 0xE4 */ info("SET 4,H", 2, 2, 2, "----"),
+    /* 0xE5 */ info("SET 4,L", 2, 2, 2, "----"),
+    /* 0xE6 */ info("SET 4,(HL)", 2, 4, 4, "----"),
+    /* 0xE7 */ info("SET 4,A", 2, 2, 2, "----"),
+    /* 0xE8 */ info("SET 5,B", 2, 2, 2, "----"),
+    /* 0xE9 */ info("SET 5,C", 2, 2, 2, "----"),
+    /* 0xEA */ info("SET 5,D", 2, 2, 2, "----"),
+    /* 0xEB */ info("SET 5,E", 2, 2, 2, "----"),
+    /* 0xEC */ info("SET 5,H", 2, 2, 2, "----"),
+    /* 0xED */ info("SET 5,L", 2, 2, 2, "----"),
+    /* 0xEE */ info("SET 5,(HL)", 2, 4, 4, "----"),
+    /* 0xEF */ info("SET 5,A", 2, 2, 2, "----"),
+    /* 0xF0 */ info("SET 6,B", 2, 2, 2, "----"),
+    /* 0xF1 */ info("SET 6,C", 2, 2, 2, "----"),
+    /* 0xF2 */ info("SET 6,D", 2, 2, 2, "----"),
+    /* 0xF3 */ info("SET 6,E", 2, 2, 2, "----"),
+    /* 0xF4 */ info("SET 6,H", 2, 2, 2, "----"),
+    /* 0xF5 */ info("SET 6,L", 2, 2, 2, "----"),
+    /* 0xF6 */ info("SET 6,(HL)", 2, 4, 4, "----"),
+    /* 0xF7 */ info("SET 6,A", 2, 2, 2, "----"),
+    /* 0xF8 */ info("SET 7,B", 2, 2, 2, "----"),
+    /* 0xF9 */ info("SET 7,C", 2, 2, 2, "----"),
+    /* 0xFA */ info("SET 7,D", 2, 2, 2, "----"),
+    /* 0xFB */ info("SET 7,E", 2, 2, 2, "----"),
+    /* 0xFC */ info("SET 7,H", 2, 2, 2, "----"),
+    /* 0xFD */ info("SET 7,L", 2, 2, 2, "----"),
+    /* 0xFE */ info("SET 7,(HL)", 2, 4, 4, "----"),
+    /* 0xFF */ info("SET 7,A", 2, 2, 2, "----"),
+];
\ No newline at end of file
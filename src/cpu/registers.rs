@@ -1,19 +1,81 @@
 use crate::cpu::registers::flags::CPUFlagsRegister;
+use crate::hardware_model::HardwareModel;
 use crate::helpers::bit_operations::{construct_u16, deconstruct_u16};
 
 mod flags;
 
 // Initial CPU register values according to: https://gbdev.io/pandocs/Power_Up_Sequence.html?highlight=state#console-state-after-boot-rom-hand-off
-// Model: DMG0
-const INITIAL_A: u8 = 0x01;
-const INITIAL_B: u8 = 0xFF;
-const INITIAL_C: u8 = 0x13;
-const INITIAL_D: u8 = 0x00;
-const INITIAL_E: u8 = 0xC1;
-const INITIAL_H: u8 = 0x84;
-const INITIAL_L: u8 = 0x03;
-const INITIAL_PC: u16 = 0x0100;
-const INITIAL_SP: u16 = 0xFFFE;
+const DMG0_REGISTERS: CPURegisters = CPURegisters {
+    a: 0x01,
+    b: 0xFF,
+    c: 0x13,
+    d: 0x00,
+    e: 0xC1,
+    f: CPUFlagsRegister::new(false, false, false, false),
+    h: 0x84,
+    l: 0x03,
+    pc: 0x0100,
+    sp: 0xFFFE,
+};
+
+// Half carry and carry depend on the cartridge header checksum, a non-zero checksum (both set) is assumed
+const DMG_REGISTERS: CPURegisters = CPURegisters {
+    a: 0x01,
+    b: 0x00,
+    c: 0x13,
+    d: 0x00,
+    e: 0xD8,
+    f: CPUFlagsRegister::new(true, false, true, true),
+    h: 0x01,
+    l: 0x4D,
+    pc: 0x0100,
+    sp: 0xFFFE,
+};
+
+const MGB_REGISTERS: CPURegisters = CPURegisters {
+    a: 0xFF,
+    ..DMG_REGISTERS
+};
+
+const SGB_REGISTERS: CPURegisters = CPURegisters {
+    a: 0x01,
+    b: 0x00,
+    c: 0x14,
+    d: 0x00,
+    e: 0x00,
+    f: CPUFlagsRegister::new(false, false, false, false),
+    h: 0xC0,
+    l: 0x60,
+    pc: 0x0100,
+    sp: 0xFFFE,
+};
+
+const SGB2_REGISTERS: CPURegisters = CPURegisters {
+    a: 0xFF,
+    ..SGB_REGISTERS
+};
+
+// Values for CGB-compatible cartridges
+const CGB_REGISTERS: CPURegisters = CPURegisters {
+    a: 0x11,
+    b: 0x00,
+    c: 0x00,
+    d: 0xFF,
+    e: 0x56,
+    f: CPUFlagsRegister::new(true, false, false, false),
+    h: 0x00,
+    l: 0x0D,
+    pc: 0x0100,
+    sp: 0xFFFE,
+};
+
+// The AGB boot ROM ends with an additional INC B, which games use to detect a GBA.
+// The flags are the result of that increment.
+const AGB_REGISTERS: CPURegisters = CPURegisters {
+    b: 0x01,
+    f: CPUFlagsRegister::new(false, false, false, false),
+    ..CGB_REGISTERS
+};
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct CPURegisters {
@@ -32,18 +94,15 @@ pub struct CPURegisters {
 }
 
 impl CPURegisters {
-    pub fn initialize() -> Self {
-        Self {
-            a: INITIAL_A,
-            b: INITIAL_B,
-            c: INITIAL_C,
-            d: INITIAL_D,
-            e: INITIAL_E,
-            f: CPUFlagsRegister::initialize(),
-            h: INITIAL_H,
-            l: INITIAL_L,
-            pc: INITIAL_PC,
-            sp: INITIAL_SP,
+    pub fn initialize(model: HardwareModel) -> Self {
+        match model {
+            HardwareModel::DMG0 => DMG0_REGISTERS,
+            HardwareModel::DMG => DMG_REGISTERS,
+            HardwareModel::MGB => MGB_REGISTERS,
+            HardwareModel::SGB => SGB_REGISTERS,
+            HardwareModel::SGB2 => SGB2_REGISTERS,
+            HardwareModel::CGB => CGB_REGISTERS,
+            HardwareModel::AGB => AGB_REGISTERS,
         }
     }
 }
@@ -3,13 +3,6 @@ const SUBTRACT_FLAG: u8 = 0b0100_0000;
 const HALF_CARRY_FLAG: u8 = 0b0010_0000;
 const CARRY_FLAG: u8 = 0b0001_0000;
 
-// Initial Flags register values according to: https://gbdev.io/pandocs/Power_Up_Sequence.html?highlight=state#console-state-after-boot-rom-hand-off
-// Model: DMG0
-const INITIAL_Z: bool = false;
-const INITIAL_N: bool = false;
-const INITIAL_H: bool = false;
-const INITIAL_C: bool = false;
-
 #[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub struct CPUFlagsRegister {
     /// Set to true if the result of the operation is equal to 0
@@ -23,12 +16,12 @@ pub struct CPUFlagsRegister {
 }
 
 impl CPUFlagsRegister {
-    pub fn initialize() -> Self {
+    pub const fn new(zero: bool, subtract: bool, half_carry: bool, carry: bool) -> Self {
         Self {
-            zero: INITIAL_Z,
-            subtract: INITIAL_N,
-            half_carry: INITIAL_H,
-            carry: INITIAL_C,
+            zero,
+            subtract,
+            half_carry,
+            carry,
         }
     }
 
@@ -0,0 +1,57 @@
+/// Read-only copy of the CPU state at an instruction boundary, for debuggers and trace tools.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct CpuSnapshot {
+    pub a: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    /// Raw flags register, see `flags` for the decoded values
+    pub f: u8,
+    pub h: u8,
+    pub l: u8,
+    pub pc: u16,
+    pub sp: u16,
+    pub flags: FlagsSnapshot,
+    /// Interrupt master enable
+    pub ime: bool,
+    /// EI was just executed, IME will be enabled after the next instruction
+    pub ime_scheduled: bool,
+    pub halted: bool,
+    pub stopped: bool,
+    /// An illegal opcode was executed and the CPU hangs
+    pub locked: bool,
+    /// Bytes of the instruction at PC, only the first `instruction_length` bytes are valid
+    pub instruction: [u8; 3],
+    pub instruction_length: u8,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct FlagsSnapshot {
+    pub zero: bool,
+    pub subtract: bool,
+    pub half_carry: bool,
+    pub carry: bool,
+}
+
+impl CpuSnapshot {
+    pub fn get_instruction_bytes(&self) -> &[u8] {
+        &self.instruction[..self.instruction_length as usize]
+    }
+
+    pub fn get_af(&self) -> u16 {
+        u16::from_be_bytes([self.a, self.f])
+    }
+
+    pub fn get_bc(&self) -> u16 {
+        u16::from_be_bytes([self.b, self.c])
+    }
+
+    pub fn get_de(&self) -> u16 {
+        u16::from_be_bytes([self.d, self.e])
+    }
+
+    pub fn get_hl(&self) -> u16 {
+        u16::from_be_bytes([self.h, self.l])
+    }
+}
@@ -0,0 +1,676 @@
+use crate::circuitry::Circuitry;
+use crate::circuitry::interface::CircuitryInterface;
+use crate::circuitry::interrupts::Interrupt;
+use crate::circuitry::ppu::{PPU, PpuMode};
+use crate::cpu::snapshot::CpuSnapshot;
+use crate::debug::bank_usage::BankUsage;
+use crate::debug::disassembler::DisassembledInstruction;
+use crate::debug::io_changes::IoChanges;
+use crate::debug::io_register::IoRegister;
+use crate::debug::trace_buffer::TraceBuffer;
+use crate::save_state::{SaveState, SaveStateError, StateReader, StateWriter};
+use std::collections::BTreeSet;
+
+pub mod bank_usage;
+pub mod disassembler;
+pub mod io_changes;
+pub mod io_register;
+pub mod trace_buffer;
+
+/// How save states treat the breakpoints and watchpoints of the [`Debugger`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum DebuggerStatePolicy {
+    /// Not saved, loading a state keeps the current configuration
+    #[default]
+    Keep,
+    /// Saved with the state, loading a state that contains them replaces the current configuration
+    Include,
+}
+
+/// Breakpoints and watchpoints checked by [`GameBoy::run_until_break`](crate::game_boy::GameBoy::run_until_break).
+/// Memory protections are enforced on every step while any are set.
+/// They are tool configuration and only part of save states if the [`DebuggerStatePolicy`] includes them.
+/// Dot tracing, the trace buffer, bank usage and I/O changes are never saved.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Debugger {
+    breakpoints: BTreeSet<u16>,
+    opcode_breakpoints: BTreeSet<u8>,
+    watchpoints: Vec<Watchpoint>,
+    register_watches: Vec<RegisterWatch>,
+    protections: Vec<MemoryProtection>,
+    dot_tracing: bool,
+    trace_buffer: Option<TraceBuffer>,
+    bank_usage: Option<BankUsage>,
+    io_changes: Option<IoChanges>,
+    state_policy: DebuggerStatePolicy,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Breaks before the instruction at the address is executed, returns false if it already existed
+    pub fn add_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.insert(address)
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    pub fn get_breakpoints(&self) -> &BTreeSet<u16> {
+        &self.breakpoints
+    }
+
+    /// Breaks before any instruction with the opcode is executed, returns false if it already existed
+    pub fn add_opcode_breakpoint(&mut self, opcode: u8) -> bool {
+        self.opcode_breakpoints.insert(opcode)
+    }
+
+    pub fn remove_opcode_breakpoint(&mut self, opcode: u8) -> bool {
+        self.opcode_breakpoints.remove(&opcode)
+    }
+
+    pub fn get_opcode_breakpoints(&self) -> &BTreeSet<u8> {
+        &self.opcode_breakpoints
+    }
+
+    /// Breaks after an instruction accessed the watched memory
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        if !self.watchpoints.contains(&watchpoint) {
+            self.watchpoints.push(watchpoint);
+        }
+    }
+
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        let count = self.watchpoints.len();
+        self.watchpoints.retain(|existing| existing != watchpoint);
+        self.watchpoints.len() != count
+    }
+
+    pub fn get_watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Breaks after an instruction wrote a value matching the condition to the register
+    pub fn add_register_watch(&mut self, watch: RegisterWatch) {
+        if !self.register_watches.contains(&watch) {
+            self.register_watches.push(watch);
+        }
+    }
+
+    pub fn remove_register_watch(&mut self, watch: &RegisterWatch) -> bool {
+        let count = self.register_watches.len();
+        self.register_watches.retain(|existing| existing != watch);
+        self.register_watches.len() != count
+    }
+
+    pub fn get_register_watches(&self) -> &[RegisterWatch] {
+        &self.register_watches
+    }
+
+    /// Blocks CPU writes to the range, later protections take precedence where they overlap
+    pub fn add_protection(&mut self, protection: MemoryProtection) {
+        if !self.protections.contains(&protection) {
+            self.protections.push(protection);
+        }
+    }
+
+    pub fn remove_protection(&mut self, protection: &MemoryProtection) -> bool {
+        let count = self.protections.len();
+        self.protections.retain(|existing| existing != protection);
+        self.protections.len() != count
+    }
+
+    pub fn get_protections(&self) -> &[MemoryProtection] {
+        &self.protections
+    }
+
+    pub fn has_protections(&self) -> bool {
+        !self.protections.is_empty()
+    }
+
+    pub fn is_dot_tracing(&self) -> bool {
+        self.dot_tracing
+    }
+
+    /// Records the PPU state after every dot in [`TraceRecord::dots`]
+    pub fn set_dot_tracing(&mut self, dot_tracing: bool) {
+        self.dot_tracing = dot_tracing;
+    }
+
+    /// Starts keeping the last executed instructions, replacing a previous buffer
+    pub fn enable_trace_buffer(&mut self, capacity: usize) {
+        self.trace_buffer = Some(TraceBuffer::new(capacity));
+    }
+
+    pub fn disable_trace_buffer(&mut self) -> Option<TraceBuffer> {
+        self.trace_buffer.take()
+    }
+
+    pub fn get_trace_buffer(&self) -> Option<&TraceBuffer> {
+        self.trace_buffer.as_ref()
+    }
+
+    pub fn get_trace_buffer_mut(&mut self) -> Option<&mut TraceBuffer> {
+        self.trace_buffer.as_mut()
+    }
+
+    /// Starts counting the M-cycles spent executing from each ROM bank, keeps the counts if already enabled
+    pub fn enable_bank_usage(&mut self) {
+        self.bank_usage.get_or_insert_default();
+    }
+
+    pub fn disable_bank_usage(&mut self) -> Option<BankUsage> {
+        self.bank_usage.take()
+    }
+
+    pub fn get_bank_usage(&self) -> Option<&BankUsage> {
+        self.bank_usage.as_ref()
+    }
+
+    pub fn get_bank_usage_mut(&mut self) -> Option<&mut BankUsage> {
+        self.bank_usage.as_mut()
+    }
+
+    /// Compares the I/O registers at every completed frame, see [`IoChanges`]
+    pub fn enable_io_changes(&mut self) {
+        self.io_changes.get_or_insert_default();
+    }
+
+    pub fn disable_io_changes(&mut self) -> Option<IoChanges> {
+        self.io_changes.take()
+    }
+
+    pub fn get_io_changes(&self) -> Option<&IoChanges> {
+        self.io_changes.as_ref()
+    }
+
+    pub(crate) fn get_io_changes_mut(&mut self) -> Option<&mut IoChanges> {
+        self.io_changes.as_mut()
+    }
+
+    pub fn get_state_policy(&self) -> DebuggerStatePolicy {
+        self.state_policy
+    }
+
+    pub fn set_state_policy(&mut self, policy: DebuggerStatePolicy) {
+        self.state_policy = policy;
+    }
+
+    pub fn clear(&mut self) {
+        self.breakpoints.clear();
+        self.opcode_breakpoints.clear();
+        self.watchpoints.clear();
+        self.register_watches.clear();
+        self.protections.clear();
+    }
+
+    pub fn check_breakpoints(&self, pc: u16, opcode: u8) -> Option<BreakReason> {
+        if self.breakpoints.contains(&pc) {
+            Some(BreakReason::Breakpoint(pc))
+        } else if self.opcode_breakpoints.contains(&opcode) {
+            Some(BreakReason::OpcodeBreakpoint(opcode))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_watched(&self, address: u16, access: MemoryAccess) -> bool {
+        self.watchpoints.iter().any(|watchpoint| watchpoint.matches(address, access))
+    }
+
+    /// Whether any register watch applies to writes to the address
+    pub fn is_register_watched(&self, address: u16) -> bool {
+        self.register_watches
+            .iter()
+            .any(|watch| watch.get_register().get_address() == address)
+    }
+
+    /// The mode of the protection covering the address, if a CPU write to it is blocked
+    pub fn check_protection(&self, address: u16) -> Option<ProtectionMode> {
+        self.protections
+            .iter()
+            .rev()
+            .find(|protection| protection.contains(address))
+            .map(|protection| protection.mode)
+    }
+
+    pub fn check_register_write(&self, address: u16, old_value: u8, value: u8) -> Option<RegisterWriteHit> {
+        let register = IoRegister::from_address(address)?;
+        self.register_watches
+            .iter()
+            .any(|watch| watch.get_register() == register && watch.matches(old_value, value))
+            .then_some(RegisterWriteHit {
+                register,
+                old_value,
+                value,
+            })
+    }
+}
+
+/// Only the breakpoints, watchpoints and protections, see [`DebuggerStatePolicy`]
+impl SaveState for Debugger {
+    fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u32(self.breakpoints.len() as u32);
+        self.breakpoints.iter().for_each(|&address| writer.write_u16(address));
+        writer.write_u32(self.opcode_breakpoints.len() as u32);
+        self.opcode_breakpoints.iter().for_each(|&opcode| writer.write_u8(opcode));
+        writer.write_u32(self.watchpoints.len() as u32);
+        for watchpoint in &self.watchpoints {
+            writer.write_u16(watchpoint.start);
+            writer.write_u16(watchpoint.end);
+            writer.write_u8(watchpoint.access as u8);
+        }
+        writer.write_u32(self.register_watches.len() as u32);
+        for watch in &self.register_watches {
+            writer.write_u16(watch.register.get_address());
+            writer.write_u8(watch.mask);
+            let (condition, value) = match watch.condition {
+                RegisterCondition::Any => (0, 0),
+                RegisterCondition::Equals(value) => (1, value),
+                RegisterCondition::Changed => (2, 0),
+            };
+            writer.write_u8(condition);
+            writer.write_u8(value);
+        }
+        writer.write_u32(self.protections.len() as u32);
+        for protection in &self.protections {
+            writer.write_u16(protection.start);
+            writer.write_u16(protection.end);
+            writer.write_u8(protection.mode as u8);
+        }
+    }
+
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+        let mut breakpoints = BTreeSet::new();
+        for _ in 0..reader.read_u32()? {
+            breakpoints.insert(reader.read_u16()?);
+        }
+        let mut opcode_breakpoints = BTreeSet::new();
+        for _ in 0..reader.read_u32()? {
+            opcode_breakpoints.insert(reader.read_u8()?);
+        }
+        let mut watchpoints = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            let (start, end) = (reader.read_u16()?, reader.read_u16()?);
+            let access = match reader.read_u8()? {
+                0 => WatchAccess::Read,
+                1 => WatchAccess::Write,
+                2 => WatchAccess::ReadWrite,
+                _ => return Err(SaveStateError::InvalidData),
+            };
+            watchpoints.push(Watchpoint::new(start, end, access));
+        }
+        let mut register_watches = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            let register = IoRegister::from_address(reader.read_u16()?).ok_or(SaveStateError::InvalidData)?;
+            let mask = reader.read_u8()?;
+            let condition = match (reader.read_u8()?, reader.read_u8()?) {
+                (0, _) => RegisterCondition::Any,
+                (1, value) => RegisterCondition::Equals(value),
+                (2, _) => RegisterCondition::Changed,
+                _ => return Err(SaveStateError::InvalidData),
+            };
+            register_watches.push(RegisterWatch::new(register, mask, condition));
+        }
+        let mut protections = Vec::new();
+        for _ in 0..reader.read_u32()? {
+            let (start, end) = (reader.read_u16()?, reader.read_u16()?);
+            let mode = match reader.read_u8()? {
+                0 => ProtectionMode::ReadOnly,
+                1 => ProtectionMode::Trap,
+                _ => return Err(SaveStateError::InvalidData),
+            };
+            protections.push(MemoryProtection::new(start, end, mode));
+        }
+
+        self.breakpoints = breakpoints;
+        self.opcode_breakpoints = opcode_breakpoints;
+        self.watchpoints = watchpoints;
+        self.register_watches = register_watches;
+        self.protections = protections;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MemoryAccess {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WatchAccess {
+    Read = 0,
+    Write = 1,
+    ReadWrite = 2,
+}
+
+/// Watches CPU accesses to an inclusive address range
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    start: u16,
+    end: u16,
+    access: WatchAccess,
+}
+
+impl Watchpoint {
+    pub fn new(start: u16, end: u16, access: WatchAccess) -> Self {
+        Self {
+            start: start.min(end),
+            end: start.max(end),
+            access,
+        }
+    }
+
+    pub fn read(address: u16) -> Self {
+        Self::new(address, address, WatchAccess::Read)
+    }
+
+    pub fn write(address: u16) -> Self {
+        Self::new(address, address, WatchAccess::Write)
+    }
+
+    pub fn get_start(&self) -> u16 {
+        self.start
+    }
+
+    pub fn get_end(&self) -> u16 {
+        self.end
+    }
+
+    pub fn get_access(&self) -> WatchAccess {
+        self.access
+    }
+
+    pub fn matches(&self, address: u16, access: MemoryAccess) -> bool {
+        let access_matches = matches!(
+            (self.access, access),
+            (WatchAccess::ReadWrite, _) | (WatchAccess::Read, MemoryAccess::Read) | (WatchAccess::Write, MemoryAccess::Write)
+        );
+        access_matches && (self.start..=self.end).contains(&address)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProtectionMode {
+    /// Writes are silently dropped
+    ReadOnly = 0,
+    /// Writes are dropped and [`GameBoy::run_until_break`](crate::game_boy::GameBoy::run_until_break) breaks after them
+    Trap = 1,
+}
+
+/// Blocks CPU writes to an inclusive address range, unlike a [`Watchpoint`] the memory keeps its value
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MemoryProtection {
+    start: u16,
+    end: u16,
+    mode: ProtectionMode,
+}
+
+impl MemoryProtection {
+    pub fn new(start: u16, end: u16, mode: ProtectionMode) -> Self {
+        Self {
+            start: start.min(end),
+            end: start.max(end),
+            mode,
+        }
+    }
+
+    pub fn read_only(address: u16) -> Self {
+        Self::new(address, address, ProtectionMode::ReadOnly)
+    }
+
+    pub fn trap(address: u16) -> Self {
+        Self::new(address, address, ProtectionMode::Trap)
+    }
+
+    pub fn get_start(&self) -> u16 {
+        self.start
+    }
+
+    pub fn get_end(&self) -> u16 {
+        self.end
+    }
+
+    pub fn get_mode(&self) -> ProtectionMode {
+        self.mode
+    }
+
+    pub fn contains(&self, address: u16) -> bool {
+        (self.start..=self.end).contains(&address)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RegisterCondition {
+    /// Every write matches
+    Any,
+    /// The written value with the mask applied equals the given value
+    Equals(u8),
+    /// Any of the masked bits differ from the value read before the write
+    Changed,
+}
+
+/// Watches CPU writes to an I/O register, only the bits selected by the mask are compared
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RegisterWatch {
+    register: IoRegister,
+    mask: u8,
+    condition: RegisterCondition,
+}
+
+impl RegisterWatch {
+    pub fn new(register: IoRegister, mask: u8, condition: RegisterCondition) -> Self {
+        Self {
+            register,
+            mask,
+            condition,
+        }
+    }
+
+    /// Matches every write to the register
+    pub fn any(register: IoRegister) -> Self {
+        Self::new(register, 0xFF, RegisterCondition::Any)
+    }
+
+    pub fn get_register(&self) -> IoRegister {
+        self.register
+    }
+
+    pub fn get_mask(&self) -> u8 {
+        self.mask
+    }
+
+    pub fn get_condition(&self) -> RegisterCondition {
+        self.condition
+    }
+
+    pub fn matches(&self, old_value: u8, value: u8) -> bool {
+        match self.condition {
+            RegisterCondition::Any => true,
+            RegisterCondition::Equals(expected) => value & self.mask == expected & self.mask,
+            RegisterCondition::Changed => (old_value ^ value) & self.mask != 0,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RegisterWriteHit {
+    pub register: IoRegister,
+    /// The value read from the register before the write
+    pub old_value: u8,
+    pub value: u8,
+}
+
+/// A CPU write that was blocked by a [`MemoryProtection`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BlockedWrite {
+    pub address: u16,
+    /// The value that would have been written
+    pub value: u8,
+    pub mode: ProtectionMode,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WatchpointHit {
+    pub address: u16,
+    /// The value read or written
+    pub value: u8,
+    pub access: MemoryAccess,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BreakReason {
+    Breakpoint(u16),
+    OpcodeBreakpoint(u8),
+    Watchpoint(WatchpointHit),
+    RegisterWrite(RegisterWriteHit),
+    /// A write was blocked by a protection in [`ProtectionMode::Trap`]
+    ProtectedWrite(BlockedWrite),
+    /// The given amount of M-cycles passed without hitting a breakpoint
+    CycleLimit,
+}
+
+/// What the CPU does in a step
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StepKind {
+    Instruction,
+    Interrupt(Interrupt),
+    /// Halted, stopped or locked
+    Idle,
+}
+
+/// A single step of the CPU, as returned by [`GameBoy::step_instruction`](crate::game_boy::GameBoy::step_instruction)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecord {
+    pub kind: StepKind,
+    /// The instruction at PC, only executed if the kind is [`StepKind::Instruction`]
+    pub instruction: DisassembledInstruction,
+    /// CPU state before the step
+    pub registers: CpuSnapshot,
+    /// M-cycles the step took
+    pub cycles: u8,
+    pub watchpoint_hits: Vec<WatchpointHit>,
+    pub register_hits: Vec<RegisterWriteHit>,
+    pub blocked_writes: Vec<BlockedWrite>,
+    /// The PPU after every dot of the step, only recorded while dot tracing is enabled
+    pub dots: Vec<DotSnapshot>,
+}
+
+/// The PPU state after a single dot
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DotSnapshot {
+    /// The M-cycle of the step the dot belongs to, starting at 0
+    pub cycle: u8,
+    pub ly: u8,
+    pub dot: u16,
+    pub mode: PpuMode,
+    pub stat: u8,
+}
+
+impl DotSnapshot {
+    fn capture(ppu: &PPU, cycle: u8) -> Self {
+        Self {
+            cycle,
+            ly: ppu.get_ly(),
+            dot: ppu.get_dot(),
+            mode: ppu.get_mode(),
+            stat: ppu.get_stat(),
+        }
+    }
+}
+
+/// Everything the debugger noticed during a step
+#[derive(Debug, Default)]
+pub(crate) struct StepHits {
+    pub(crate) watchpoints: Vec<WatchpointHit>,
+    pub(crate) registers: Vec<RegisterWriteHit>,
+    pub(crate) blocked_writes: Vec<BlockedWrite>,
+}
+
+/// Passes all accesses through to the circuitry while recording the ones that hit a watchpoint
+/// and dropping the writes blocked by a protection
+pub(crate) struct WatchedCircuitry<'a> {
+    circuitry: &'a mut Circuitry,
+    debugger: &'a Debugger,
+    hits: StepHits,
+    dots: Vec<DotSnapshot>,
+    cycle: u8,
+}
+
+impl<'a> WatchedCircuitry<'a> {
+    pub(crate) fn new(circuitry: &'a mut Circuitry, debugger: &'a Debugger) -> Self {
+        Self {
+            circuitry,
+            debugger,
+            hits: StepHits::default(),
+            dots: Vec::new(),
+            cycle: 0,
+        }
+    }
+
+    pub(crate) fn into_hits(self) -> StepHits {
+        self.hits
+    }
+
+    pub(crate) fn into_trace(self) -> (StepHits, Vec<DotSnapshot>) {
+        (self.hits, self.dots)
+    }
+
+    fn record(&mut self, address: u16, value: u8, access: MemoryAccess) {
+        if self.debugger.is_watched(address, access) {
+            self.hits.watchpoints.push(WatchpointHit { address, value, access });
+        }
+    }
+}
+
+impl CircuitryInterface for WatchedCircuitry<'_> {
+    fn tick(&mut self) {
+        if self.debugger.is_dot_tracing() {
+            let cycle = self.cycle;
+            let dots = &mut self.dots;
+            self.circuitry.tick_observed(|ppu| dots.push(DotSnapshot::capture(ppu, cycle)));
+        } else {
+            self.circuitry.tick();
+        }
+        self.cycle = self.cycle.wrapping_add(1);
+    }
+
+    fn read(&mut self, address: u16) -> u8 {
+        let value = self.circuitry.read(address);
+        self.record(address, value, MemoryAccess::Read);
+        value
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if let Some(mode) = self.debugger.check_protection(address) {
+            self.hits.blocked_writes.push(BlockedWrite { address, value, mode });
+            return;
+        }
+
+        let old_value = self
+            .debugger
+            .is_register_watched(address)
+            .then(|| self.circuitry.peek(address));
+        self.circuitry.write(address, value);
+        self.record(address, value, MemoryAccess::Write);
+        if let Some(hit) = old_value.and_then(|old_value| self.debugger.check_register_write(address, old_value, value)) {
+            self.hits.registers.push(hit);
+        }
+    }
+
+    fn get_interrupt_enable(&self) -> u8 {
+        self.circuitry.get_interrupt_enable()
+    }
+
+    fn get_interrupt_flag(&self) -> u8 {
+        self.circuitry.get_interrupt_flag()
+    }
+
+    fn acknowledge_interrupt(&mut self, interrupt: Interrupt) {
+        self.circuitry.acknowledge_interrupt(interrupt);
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt::Write;
+
+/// M-cycles spent executing from each ROM bank.
+/// Steps are attributed to the bank the instruction was fetched from, including the time spent halted on it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BankUsage {
+    /// Indexed by bank, grows with the highest bank that was executed from
+    rom_cycles: Vec<u64>,
+    /// Executing from RAM or without a cartridge
+    other_cycles: u64,
+}
+
+impl BankUsage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the cycles of a step to the bank, None if it wasn't executed from ROM
+    pub fn record(&mut self, bank: Option<usize>, cycles: u8) {
+        let Some(bank) = bank else {
+            self.other_cycles += cycles as u64;
+            return;
+        };
+        if bank >= self.rom_cycles.len() {
+            self.rom_cycles.resize(bank + 1, 0);
+        }
+        self.rom_cycles[bank] += cycles as u64;
+    }
+
+    pub fn get_rom_cycles(&self, bank: usize) -> u64 {
+        self.rom_cycles.get(bank).copied().unwrap_or_default()
+    }
+
+    pub fn get_other_cycles(&self) -> u64 {
+        self.other_cycles
+    }
+
+    pub fn get_total_cycles(&self) -> u64 {
+        self.rom_cycles.iter().sum::<u64>() + self.other_cycles
+    }
+
+    /// The banks that were executed from with their cycles, in bank order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, u64)> {
+        self.rom_cycles
+            .iter()
+            .enumerate()
+            .filter(|(_, cycles)| **cycles > 0)
+            .map(|(bank, &cycles)| (bank, cycles))
+    }
+
+    pub fn clear(&mut self) {
+        self.rom_cycles.clear();
+        self.other_cycles = 0;
+    }
+
+    /// Renders one line per bank that was executed from and one for everything outside of ROM
+    pub fn report(&self) -> String {
+        let total = self.get_total_cycles().max(1) as f64;
+        let mut output = String::new();
+        let lines = self
+            .iter()
+            .map(|(bank, cycles)| (format!("Bank 0x{bank:03X}"), cycles))
+            .chain([("Other".to_string(), self.other_cycles)]);
+        for (name, cycles) in lines {
+            let _ = writeln!(output, "{name:<10} {cycles:>12} cycles {:>6.2}%", cycles as f64 * 100.0 / total);
+        }
+        output
+    }
+}
@@ -0,0 +1,81 @@
+use crate::cpu::opcodes::{CB_PREFIX, get_cb_opcode_info, get_opcode_info};
+
+/// A single decoded instruction.
+/// Operands are rendered as hexadecimal values, relative jumps show their target address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisassembledInstruction {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub text: String,
+}
+
+/// Decodes the instruction at the given address, bytes are read via the given peek function.
+/// Illegal opcodes are rendered as a single data byte.
+pub fn disassemble_instruction(peek: impl Fn(u16) -> u8, address: u16) -> DisassembledInstruction {
+    let opcode = peek(address);
+    if opcode == CB_PREFIX {
+        let cb_opcode = peek(address.wrapping_add(1));
+        return DisassembledInstruction {
+            address,
+            bytes: vec![opcode, cb_opcode],
+            text: get_cb_opcode_info(cb_opcode).get_mnemonic().to_string(),
+        };
+    }
+
+    let Some(info) = get_opcode_info(opcode) else {
+        return DisassembledInstruction {
+            address,
+            bytes: vec![opcode],
+            text: format!("DB 0x{opcode:02X}"),
+        };
+    };
+
+    let bytes: Vec<u8> = (0..info.get_length() as u16)
+        .map(|offset| peek(address.wrapping_add(offset)))
+        .collect();
+    let next_address = address.wrapping_add(bytes.len() as u16);
+    DisassembledInstruction {
+        address,
+        text: render_operands(info.get_mnemonic(), &bytes, next_address),
+        bytes,
+    }
+}
+
+/// Decodes all instructions starting within the inclusive range, the last one may extend beyond it
+pub fn disassemble_range(peek: impl Fn(u16) -> u8, start: u16, end: u16) -> Vec<DisassembledInstruction> {
+    let mut instructions = Vec::new();
+    let mut address = start as u32;
+    while address <= end as u32 {
+        let instruction = disassemble_instruction(&peek, address as u16);
+        address += instruction.bytes.len() as u32;
+        instructions.push(instruction);
+    }
+    instructions
+}
+
+fn render_operands(mnemonic: &str, bytes: &[u8], next_address: u16) -> String {
+    let immediate_u8 = bytes.get(1).copied().unwrap_or_default();
+    let immediate_u16 = u16::from_le_bytes([immediate_u8, bytes.get(2).copied().unwrap_or_default()]);
+    let offset = immediate_u8 as i8;
+
+    if mnemonic.starts_with("JR") {
+        let target = next_address.wrapping_add_signed(offset as i16);
+        return mnemonic.replace("r8", &format!("0x{target:04X}"));
+    }
+
+    mnemonic
+        .replace("+r8", &format_signed(offset, "+"))
+        .replace("r8", &format_signed(offset, ""))
+        .replace("d16", &format!("0x{immediate_u16:04X}"))
+        .replace("a16", &format!("0x{immediate_u16:04X}"))
+        .replace("d8", &format!("0x{immediate_u8:02X}"))
+        .replace("a8", &format!("0xFF{immediate_u8:02X}"))
+}
+
+fn format_signed(value: i8, positive_sign: &str) -> String {
+    if value < 0 {
+        format!("-0x{:02X}", value.unsigned_abs())
+    } else {
+        format!("{positive_sign}0x{value:02X}")
+    }
+}
@@ -0,0 +1,60 @@
+use crate::debug::io_register::IoRegister;
+
+/// An I/O register whose value differs between two completed frames
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IoRegisterChange {
+    pub register: IoRegister,
+    pub old: u8,
+    pub new: u8,
+}
+
+/// The values of all I/O registers at the end of the last completed frame and how they differ from the frame before.
+/// Only the values at frame boundaries are compared, registers that changed and were restored within a frame are
+/// not reported.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IoChanges {
+    /// The frame count the values were captured at, None until the first capture
+    frame_count: Option<u64>,
+    /// In the order of [`IoRegister::all`]
+    values: Vec<u8>,
+    changes: Vec<IoRegisterChange>,
+}
+
+impl IoChanges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Captures the registers if a frame was completed since the last capture.
+    /// The first capture only records the values, changes are reported from the next frame on.
+    pub fn capture(&mut self, frame_count: u64, peek: impl Fn(u16) -> u8) {
+        if self.frame_count == Some(frame_count) {
+            return;
+        }
+
+        let values: Vec<u8> = IoRegister::all().map(|register| peek(register.get_address())).collect();
+        self.changes = IoRegister::all()
+            .zip(self.values.iter().zip(&values))
+            .filter(|(_, (old, new))| old != new)
+            .map(|(register, (&old, &new))| IoRegisterChange { register, old, new })
+            .collect();
+        self.values = values;
+        self.frame_count = Some(frame_count);
+    }
+
+    /// The registers that changed during the last completed frame, in address order
+    pub fn get_changes(&self) -> &[IoRegisterChange] {
+        &self.changes
+    }
+
+    /// The frame count the last capture was made at
+    pub fn get_frame_count(&self) -> Option<u64> {
+        self.frame_count
+    }
+
+    /// The value of the register at the end of the last completed frame, None before the first capture
+    pub fn get_value(&self, register: IoRegister) -> Option<u8> {
+        let index = IoRegister::all().position(|other| other == register)?;
+        self.values.get(index).copied()
+    }
+}
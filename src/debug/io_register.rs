@@ -0,0 +1,128 @@
+// Hardware registers according to: https://gbdev.io/pandocs/Hardware_Reg_List.html
+/// The DMG I/O registers, for watching them by name instead of by address
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum IoRegister {
+    P1,
+    SB,
+    SC,
+    DIV,
+    TIMA,
+    TMA,
+    TAC,
+    IF,
+    NR10,
+    NR11,
+    NR12,
+    NR13,
+    NR14,
+    NR21,
+    NR22,
+    NR23,
+    NR24,
+    NR30,
+    NR31,
+    NR32,
+    NR33,
+    NR34,
+    NR41,
+    NR42,
+    NR43,
+    NR44,
+    NR50,
+    NR51,
+    NR52,
+    LCDC,
+    STAT,
+    SCY,
+    SCX,
+    LY,
+    LYC,
+    DMA,
+    BGP,
+    OBP0,
+    OBP1,
+    WY,
+    WX,
+    IE,
+}
+
+const REGISTERS: [(IoRegister, u16, &str); 42] = [
+    (IoRegister::P1, 0xFF00, "P1"),
+    (IoRegister::SB, 0xFF01, "SB"),
+    (IoRegister::SC, 0xFF02, "SC"),
+    (IoRegister::DIV, 0xFF04, "DIV"),
+    (IoRegister::TIMA, 0xFF05, "TIMA"),
+    (IoRegister::TMA, 0xFF06, "TMA"),
+    (IoRegister::TAC, 0xFF07, "TAC"),
+    (IoRegister::IF, 0xFF0F, "IF"),
+    (IoRegister::NR10, 0xFF10, "NR10"),
+    (IoRegister::NR11, 0xFF11, "NR11"),
+    (IoRegister::NR12, 0xFF12, "NR12"),
+    (IoRegister::NR13, 0xFF13, "NR13"),
+    (IoRegister::NR14, 0xFF14, "NR14"),
+    (IoRegister::NR21, 0xFF16, "NR21"),
+    (IoRegister::NR22, 0xFF17, "NR22"),
+    (IoRegister::NR23, 0xFF18, "NR23"),
+    (IoRegister::NR24, 0xFF19, "NR24"),
+    (IoRegister::NR30, 0xFF1A, "NR30"),
+    (IoRegister::NR31, 0xFF1B, "NR31"),
+    (IoRegister::NR32, 0xFF1C, "NR32"),
+    (IoRegister::NR33, 0xFF1D, "NR33"),
+    (IoRegister::NR34, 0xFF1E, "NR34"),
+    (IoRegister::NR41, 0xFF20, "NR41"),
+    (IoRegister::NR42, 0xFF21, "NR42"),
+    (IoRegister::NR43, 0xFF22, "NR43"),
+    (IoRegister::NR44, 0xFF23, "NR44"),
+    (IoRegister::NR50, 0xFF24, "NR50"),
+    (IoRegister::NR51, 0xFF25, "NR51"),
+    (IoRegister::NR52, 0xFF26, "NR52"),
+    (IoRegister::LCDC, 0xFF40, "LCDC"),
+    (IoRegister::STAT, 0xFF41, "STAT"),
+    (IoRegister::SCY, 0xFF42, "SCY"),
+    (IoRegister::SCX, 0xFF43, "SCX"),
+    (IoRegister::LY, 0xFF44, "LY"),
+    (IoRegister::LYC, 0xFF45, "LYC"),
+    (IoRegister::DMA, 0xFF46, "DMA"),
+    (IoRegister::BGP, 0xFF47, "BGP"),
+    (IoRegister::OBP0, 0xFF48, "OBP0"),
+    (IoRegister::OBP1, 0xFF49, "OBP1"),
+    (IoRegister::WY, 0xFF4A, "WY"),
+    (IoRegister::WX, 0xFF4B, "WX"),
+    (IoRegister::IE, 0xFFFF, "IE"),
+];
+
+impl IoRegister {
+    pub fn all() -> impl Iterator<Item = IoRegister> {
+        REGISTERS.iter().map(|(register, _, _)| *register)
+    }
+
+    pub fn from_address(address: u16) -> Option<Self> {
+        REGISTERS
+            .iter()
+            .find(|(_, register_address, _)| *register_address == address)
+            .map(|(register, _, _)| *register)
+    }
+
+    /// Case-insensitive, JOYP is accepted as an alias of P1
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("JOYP") {
+            return Some(Self::P1);
+        }
+        REGISTERS
+            .iter()
+            .find(|(_, _, register_name)| register_name.eq_ignore_ascii_case(name))
+            .map(|(register, _, _)| *register)
+    }
+
+    pub fn get_address(&self) -> u16 {
+        self.get_entry().1
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        self.get_entry().2
+    }
+
+    fn get_entry(&self) -> &'static (IoRegister, u16, &'static str) {
+        REGISTERS.iter().find(|(register, _, _)| register == self).unwrap()
+    }
+}
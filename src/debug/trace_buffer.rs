@@ -0,0 +1,94 @@
+use crate::debug::disassembler::{DisassembledInstruction, disassemble_instruction};
+use std::fmt::Write;
+
+/// An executed instruction with the registers before its execution, packed into 16 bytes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub pc: u16,
+    pub af: u16,
+    pub bc: u16,
+    pub de: u16,
+    pub hl: u16,
+    pub sp: u16,
+    /// The opcode and the bytes following it, unused bytes are included as well
+    pub bytes: [u8; 3],
+}
+
+impl TraceEntry {
+    pub fn disassemble(&self) -> DisassembledInstruction {
+        disassemble_instruction(
+            |address| self.bytes.get(address.wrapping_sub(self.pc) as usize).copied().unwrap_or_default(),
+            self.pc,
+        )
+    }
+}
+
+/// Keeps the last executed instructions, the oldest entry is overwritten once the capacity is reached
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceBuffer {
+    entries: Vec<TraceEntry>,
+    capacity: usize,
+    /// Index the next entry is written to once the buffer is full
+    next: usize,
+}
+
+impl TraceBuffer {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            entries: Vec::with_capacity(capacity),
+            capacity,
+            next: 0,
+        }
+    }
+
+    pub fn push(&mut self, entry: TraceEntry) {
+        if self.entries.len() < self.capacity {
+            self.entries.push(entry);
+        } else {
+            self.entries[self.next] = entry;
+            self.next = (self.next + 1) % self.capacity;
+        }
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.next = 0;
+    }
+
+    /// Oldest entry first
+    pub fn iter(&self) -> impl Iterator<Item = &TraceEntry> {
+        self.entries[self.next..].iter().chain(&self.entries[..self.next])
+    }
+
+    /// Renders one line per entry, oldest first, for example after the CPU locked up
+    pub fn dump(&self) -> String {
+        let mut output = String::new();
+        for entry in self.iter() {
+            let _ = writeln!(
+                output,
+                "0x{:04X}: {:<16} AF=0x{:04X} BC=0x{:04X} DE=0x{:04X} HL=0x{:04X} SP=0x{:04X}",
+                entry.pc,
+                entry.disassemble().text,
+                entry.af,
+                entry.bc,
+                entry.de,
+                entry.hl,
+                entry.sp
+            );
+        }
+        output
+    }
+}
@@ -1,14 +1,412 @@
+use crate::cartridge::{Cartridge, CartridgeError};
 use crate::circuitry::Circuitry;
+use crate::circuitry::apu::{APU, AudioBufferStats, AudioCallback, AudioChannel, StereoSample};
+use crate::circuitry::ppu::layers::LayerBuffer;
+use crate::circuitry::ppu::screen_tiles::ScreenTiles;
+use crate::circuitry::ppu::{DOTS_PER_LINE, FrameBuffer, FrameMetadata, LINES_PER_FRAME};
+use crate::circuitry::profiling::SubsystemTimes;
 use crate::cpu::CPU;
+use crate::cpu::registers::CpuRegistersAccessTrait;
+use crate::cpu::snapshot::CpuSnapshot;
+use crate::debug::{Debugger, DebuggerStatePolicy};
+use crate::game_boy::builder::GameBoyBuilder;
+use crate::game_boy::checkpoints::ErrorCheckpoints;
+use crate::game_boy::events::ScheduledEvents;
+use crate::hardware_model::HardwareModel;
+use crate::hardware_model::revision::Revision;
+use crate::save_state::{
+    OLDEST_MIGRATABLE_VERSION, SAVE_STATE_MAGIC, SAVE_STATE_VERSION, SaveState, SaveStateError, StateMigration,
+    StateReader, StateWriter,
+};
+use std::time::Instant;
 
-#[derive(Debug, Default, PartialEq)]
+pub mod builder;
+pub mod checkpoints;
+pub mod comparison;
+mod debugging;
+pub mod events;
+pub mod frame_mailbox;
+pub mod group;
+
+/// M-cycles it takes the PPU to draw a frame
+pub const CYCLES_PER_FRAME: u32 = DOTS_PER_LINE as u32 * LINES_PER_FRAME as u32 / 4;
+
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct GameBoy {
+    model: HardwareModel,
+    revision: Revision,
     cpu: CPU,
-    circuitry: Circuitry
+    circuitry: Circuitry,
+    debugger: Debugger,
+    cycle_count: u64,
+    events: ScheduledEvents,
+    checkpoints: Option<ErrorCheckpoints>,
+}
+
+/// A state read by [`GameBoy::read_state`] that wasn't applied yet
+struct LoadedState {
+    game_boy: GameBoy,
+    version: u16,
+    included_debugger: bool,
+    defaulted: Vec<&'static str>,
+}
+
+/// The CPU state before a step, for the bookkeeping after it
+#[derive(Debug, Copy, Clone)]
+struct StepStart {
+    pc: u16,
+    locked: bool,
+    /// When the step started, only measured while profiling
+    profiled: Option<Instant>,
 }
 
 impl GameBoy {
-    pub fn step(&mut self) {
-        self.cpu.step(&mut self.circuitry)
+    pub fn new(model: HardwareModel) -> Self {
+        Self::builder().model(model).build()
+    }
+
+    pub fn builder() -> GameBoyBuilder {
+        GameBoyBuilder::new()
+    }
+
+    pub fn get_model(&self) -> HardwareModel {
+        self.model
+    }
+
+    pub fn get_revision(&self) -> Revision {
+        self.revision
+    }
+
+    /// Loads the ROM and inserts it, replacing any cartridge that was inserted before
+    pub fn insert_cartridge(&mut self, rom: Vec<u8>) -> Result<(), CartridgeError> {
+        self.circuitry.insert_cartridge(Cartridge::load(rom)?);
+        Ok(())
+    }
+
+    /// Applies an IPS or BPS patch to the ROM before inserting it, see [`Cartridge::load_patched`]
+    pub fn insert_patched_cartridge(&mut self, rom: &[u8], patch: &[u8]) -> Result<(), CartridgeError> {
+        self.circuitry.insert_cartridge(Cartridge::load_patched(rom, patch)?);
+        Ok(())
+    }
+
+    /// Inserts an already loaded cartridge, for example one using a different clock source.
+    /// Returns the previously inserted cartridge.
+    pub fn set_cartridge(&mut self, cartridge: Cartridge) -> Option<Cartridge> {
+        self.circuitry.insert_cartridge(cartridge)
+    }
+
+    pub fn remove_cartridge(&mut self) -> Option<Cartridge> {
+        self.circuitry.remove_cartridge()
+    }
+
+    pub fn get_cartridge(&self) -> Option<&Cartridge> {
+        self.circuitry.get_cartridge()
+    }
+
+    pub fn get_cartridge_mut(&mut self) -> Option<&mut Cartridge> {
+        self.circuitry.get_cartridge_mut()
+    }
+
+    /// The external RAM of the inserted cartridge, if it is battery backed and should be persisted
+    pub fn get_save_data(&self) -> Option<&[u8]> {
+        self.get_cartridge()
+            .filter(|cartridge| cartridge.has_battery())
+            .map(|cartridge| cartridge.get_ram())
+    }
+
+    /// Restores the external RAM of the inserted cartridge from a `.sav` file.
+    /// Does nothing if no cartridge is inserted.
+    pub fn load_save_data(&mut self, data: &[u8]) -> Result<(), CartridgeError> {
+        match self.get_cartridge_mut() {
+            Some(cartridge) => cartridge.load_ram(data),
+            None => Ok(()),
+        }
+    }
+
+    pub fn get_cpu_snapshot(&self) -> CpuSnapshot {
+        self.cpu.snapshot(|address| self.circuitry.peek(address))
+    }
+
+    /// The last completed frame, shades from 0 (white) to 3 (black)
+    pub fn get_frame_buffer(&self) -> &FrameBuffer {
+        self.circuitry.get_ppu().get_frame_buffer()
+    }
+
+    /// The background and window tile in every 8x8 cell of the screen, for checking the text on screen in scripts.
+    /// None while the background and window are disabled.
+    pub fn get_screen_tiles(&self) -> Option<ScreenTiles> {
+        self.circuitry.get_ppu().get_screen_tiles()
+    }
+
+    /// Records which layer drew each pixel for debugging the PPU,
+    /// the layers can be colorized with [`LayerPalettes`](crate::circuitry::ppu::layers::LayerPalettes)
+    pub fn set_layer_tracking(&mut self, enabled: bool) {
+        self.circuitry.get_ppu_mut().set_layer_tracking(enabled);
+    }
+
+    /// The layer of every pixel in the frame buffer, None unless layer tracking is enabled
+    pub fn get_layer_buffer(&self) -> Option<&LayerBuffer> {
+        self.circuitry.get_ppu().get_layer_buffer()
+    }
+
+    /// Describes how the last completed frame was rendered
+    pub fn get_frame_metadata(&self) -> &FrameMetadata {
+        self.circuitry.get_ppu().get_frame_metadata()
+    }
+
+    /// Puts only the PPU back into its state after boot while everything else keeps running,
+    /// see [`PPU::reset`](crate::circuitry::ppu::PPU::reset)
+    pub fn reset_ppu(&mut self) {
+        self.circuitry.get_ppu_mut().reset();
+    }
+
+    /// Measures how much host time the CPU, PPU, APU and bus take per frame, for finding out what slows down
+    /// emulation. Profiling has a small overhead of its own.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.circuitry.set_profiling(enabled);
+    }
+
+    /// The host time spent on the last completed frame, None unless a frame was completed while profiling
+    pub fn get_frame_profile(&self) -> Option<&SubsystemTimes> {
+        self.circuitry.get_frame_profile()
+    }
+
+    pub fn get_frame_count(&self) -> u64 {
+        self.circuitry.get_ppu().get_frame_count()
+    }
+
+    /// Runs until the PPU completes the next frame.
+    /// While the LCD is off no frames are completed, in that case it returns after the duration of one frame.
+    ///
+    /// # Returns
+    ///
+    /// The amount of M-cycles that passed
+    pub fn run_until_frame(&mut self) -> u32 {
+        let frame_count = self.get_frame_count();
+        let mut cycles = 0;
+        while self.get_frame_count() == frame_count && cycles < CYCLES_PER_FRAME {
+            cycles += self.step() as u32;
+        }
+        cycles
+    }
+
+    /// Runs frame by frame until the predicate holds after a frame was completed,
+    /// then returns a copy of that frame. Gives up after the given amount of frames.
+    pub fn capture_when(
+        &mut self,
+        mut predicate: impl FnMut(&GameBoy) -> bool,
+        frame_limit: u32,
+    ) -> Option<Box<FrameBuffer>> {
+        for _ in 0..frame_limit {
+            self.run_until_frame();
+            if predicate(self) {
+                return Some(Box::new(*self.get_frame_buffer()));
+            }
+        }
+        None
+    }
+
+    pub fn get_apu(&self) -> &APU {
+        self.circuitry.get_apu()
+    }
+
+    /// Puts only the APU back into its state after boot while everything else keeps running,
+    /// for recovering from stuck channels. See [`APU::reset`]
+    pub fn reset_apu(&mut self) {
+        self.circuitry.get_apu_mut().reset();
+    }
+
+    /// Takes all audio samples generated since the last call, at the configured sample rate.
+    /// Up to one second of samples is buffered by default, older ones are dropped.
+    pub fn drain_audio_samples(&mut self) -> Vec<StereoSample> {
+        self.circuitry.get_apu_mut().drain_samples()
+    }
+
+    /// Takes up to the given amount of the oldest audio samples, for frontends that request fixed-size chunks.
+    /// Requesting more samples than are buffered counts as an underrun in the [`AudioBufferStats`].
+    pub fn take_audio_samples(&mut self, count: usize) -> Vec<StereoSample> {
+        self.circuitry.get_apu_mut().take_samples(count)
+    }
+
+    pub fn get_audio_buffer_size(&self) -> usize {
+        self.circuitry.get_apu().get_buffer_size()
+    }
+
+    /// Sets how many audio samples are buffered at most, None buffers one second of samples
+    pub fn set_audio_buffer_size(&mut self, buffer_size: Option<usize>) {
+        self.circuitry.get_apu_mut().set_buffer_size(buffer_size);
+    }
+
+    pub fn get_audio_buffer_stats(&self) -> AudioBufferStats {
+        self.circuitry.get_apu().get_buffer_stats()
+    }
+
+    pub fn reset_audio_buffer_stats(&mut self) {
+        self.circuitry.get_apu_mut().reset_buffer_stats();
+    }
+
+    /// Passes every audio sample to the callback as soon as it is generated, instead of buffering it.
+    /// Clones of this [`GameBoy`] share the callback.
+    pub fn set_audio_callback(&mut self, callback: impl FnMut(StereoSample) + Send + 'static) {
+        self.circuitry.get_apu_mut().set_callback(Some(AudioCallback::new(callback)));
+    }
+
+    /// Samples are buffered for [`GameBoy::drain_audio_samples`] again
+    pub fn clear_audio_callback(&mut self) {
+        self.circuitry.get_apu_mut().set_callback(None);
+    }
+
+    pub fn get_audio_sample_rate(&self) -> u32 {
+        self.circuitry.get_apu().get_sample_rate()
     }
-}
\ No newline at end of file
+
+    pub fn set_audio_sample_rate(&mut self, sample_rate: u32) {
+        self.circuitry.get_apu_mut().set_sample_rate(sample_rate);
+    }
+
+    /// Mutes or unmutes a single channel in the audio output, the emulation isn't affected
+    pub fn set_audio_channel_enabled(&mut self, channel: AudioChannel, enabled: bool) {
+        self.circuitry.get_apu_mut().set_channel_muted(channel, !enabled);
+    }
+
+    /// Captures the complete emulation state.
+    /// The ROM and the clock source of the cartridge are not included,
+    /// states can only be loaded with the same ROM inserted, which is identified by its CRC-32.
+    /// Breakpoints and watchpoints are included depending on the [`DebuggerStatePolicy`].
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        writer.write_bytes(&SAVE_STATE_MAGIC);
+        writer.write_u16(SAVE_STATE_VERSION);
+        writer.write_u8(self.model as u8);
+        let rom_crc32 = self.get_cartridge().map(|cartridge| cartridge.get_rom_crc32());
+        writer.write_bool(rom_crc32.is_some());
+        writer.write_u32(rom_crc32.unwrap_or_default());
+        writer.write_u64(self.cycle_count);
+        self.cpu.save_state(&mut writer);
+        self.circuitry.save_state(&mut writer);
+        let include_debugger = self.debugger.get_state_policy() == DebuggerStatePolicy::Include;
+        writer.write_bool(include_debugger);
+        if include_debugger {
+            self.debugger.save_state(&mut writer);
+        }
+        writer.into_bytes()
+    }
+
+    /// Restores a state created by [`GameBoy::save_state`].
+    /// The state is validated completely before anything is applied, on error the emulation continues unchanged.
+    /// Frontend settings like the audio output, scheduled events and the debugger policy are kept.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), SaveStateError> {
+        self.load_state_checked(state, true)
+    }
+
+    /// Like [`GameBoy::load_state`], but also accepts states created with a different ROM, for example another
+    /// revision of the same game. The cartridge state still has to match the inserted mapper.
+    pub fn load_state_ignoring_rom(&mut self, state: &[u8]) -> Result<(), SaveStateError> {
+        self.load_state_checked(state, false)
+    }
+
+    /// Upgrades a state of an older version to the current one, so it can be loaded with [`GameBoy::load_state`].
+    /// Fields that didn't exist yet get their power-on values, the migration reports which ones.
+    /// The same ROM has to be inserted as for loading, the emulation itself isn't changed.
+    pub fn migrate_state(&self, state: &[u8]) -> Result<StateMigration, SaveStateError> {
+        let mut loaded = self.read_state(state, true, true)?;
+        let policy = if loaded.included_debugger {
+            DebuggerStatePolicy::Include
+        } else {
+            DebuggerStatePolicy::Keep
+        };
+        loaded.game_boy.debugger.set_state_policy(policy);
+        Ok(StateMigration {
+            from_version: loaded.version,
+            state: loaded.game_boy.save_state(),
+            defaulted: loaded.defaulted,
+        })
+    }
+
+    fn load_state_checked(&mut self, state: &[u8], check_rom: bool) -> Result<(), SaveStateError> {
+        *self = self.read_state(state, check_rom, false)?.game_boy;
+        Ok(())
+    }
+
+    /// Loads the state into a copy of this instance, older versions are only accepted when migrating
+    fn read_state(&self, state: &[u8], check_rom: bool, migrate: bool) -> Result<LoadedState, SaveStateError> {
+        let mut reader = StateReader::new(state);
+        if reader.read_array()? != SAVE_STATE_MAGIC {
+            return Err(SaveStateError::InvalidMagic);
+        }
+        let version = reader.read_u16()?;
+        let migratable = migrate && (OLDEST_MIGRATABLE_VERSION..SAVE_STATE_VERSION).contains(&version);
+        if version != SAVE_STATE_VERSION && !migratable {
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+        reader.set_version(version);
+        if reader.read_u8()? != self.model as u8 {
+            return Err(SaveStateError::ModelMismatch);
+        }
+
+        let rom_crc32 = self.get_cartridge().map(|cartridge| cartridge.get_rom_crc32());
+        let state_has_cartridge = reader.read_bool()?;
+        let state_crc32 = reader.read_u32()?;
+        match rom_crc32 {
+            _ if state_has_cartridge != rom_crc32.is_some() => return Err(SaveStateError::CartridgeMismatch),
+            Some(current_crc32) if check_rom && state_crc32 != current_crc32 => {
+                return Err(SaveStateError::RomMismatch {
+                    state_crc32,
+                    current_crc32,
+                });
+            }
+            _ => {}
+        }
+
+        let mut loaded = self.clone();
+        loaded.cycle_count = reader.read_u64()?;
+        loaded.cpu.load_state(&mut reader)?;
+        loaded.circuitry.load_state(&mut reader)?;
+        let included_debugger = reader.read_bool()?;
+        if included_debugger {
+            match self.debugger.get_state_policy() {
+                // A migrated state keeps its breakpoints and watchpoints
+                _ if migrate => loaded.debugger.load_state(&mut reader)?,
+                DebuggerStatePolicy::Include => loaded.debugger.load_state(&mut reader)?,
+                DebuggerStatePolicy::Keep => Debugger::default().load_state(&mut reader)?,
+            }
+        }
+        if !reader.is_at_end() {
+            return Err(SaveStateError::InvalidData);
+        }
+        Ok(LoadedState {
+            game_boy: loaded,
+            version,
+            included_debugger,
+            defaulted: reader.get_defaulted().to_vec(),
+        })
+    }
+
+    /// Executes a single instruction, see [`CPU::step`].
+    /// Writes blocked by a [`MemoryProtection`](crate::debug::MemoryProtection) are dropped.
+    ///
+    /// # Returns
+    ///
+    /// The amount of M-cycles that passed
+    pub fn step(&mut self) -> u8 {
+        // Only protections have to be enforced outside of the debugging methods
+        if self.debugger.has_protections() {
+            return self.step_watched().0;
+        }
+
+        let start = self.begin_step();
+        let cycles = self.cpu.step(&mut self.circuitry);
+        self.finish_step(start, cycles);
+        cycles
+    }
+
+    /// Records the trace buffer entry of the step and captures what its bookkeeping needs
+    fn begin_step(&mut self) -> StepStart {
+        self.record_trace();
+        StepStart {
+            pc: self.cpu.get_pc(),
+            locked: self.cpu.is_locked(),
+            profiled: self.circuitry.begin_profiled_step(),
+        }
+    }
+}
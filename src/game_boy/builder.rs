@@ -0,0 +1,92 @@
+use crate::circuitry::Circuitry;
+use crate::circuitry::apu::DEFAULT_SAMPLE_RATE;
+use crate::circuitry::hardware_variance::HardwareVariance;
+use crate::circuitry::ram_initialization::RamInitialization;
+use crate::cpu::CPU;
+use crate::game_boy::GameBoy;
+use crate::hardware_model::HardwareModel;
+use crate::hardware_model::revision::Revision;
+
+/// Configures a [`GameBoy`] before power-on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameBoyBuilder {
+    model: HardwareModel,
+    ram_initialization: RamInitialization,
+    hardware_variance: HardwareVariance,
+    audio_sample_rate: u32,
+    audio_buffer_size: Option<usize>,
+    revision: Option<Revision>,
+}
+
+impl GameBoyBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn model(mut self, model: HardwareModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Contents of WRAM and HRAM at power-on, zeroed by default
+    pub fn ram_initialization(mut self, ram_initialization: RamInitialization) -> Self {
+        self.ram_initialization = ram_initialization;
+        self
+    }
+
+    /// Whether behavior that varies on real hardware is deterministic, which it is by default
+    pub fn hardware_variance(mut self, hardware_variance: HardwareVariance) -> Self {
+        self.hardware_variance = hardware_variance;
+        self
+    }
+
+    /// The chip revision whose quirks are emulated, the default revision of the model if not set.
+    /// A revision of another family than the model is accepted on purpose, for testing its quirks in isolation.
+    pub fn revision(mut self, revision: Revision) -> Self {
+        self.revision = Some(revision);
+        self
+    }
+
+    /// Rate of the generated audio samples, 48 kHz by default
+    pub fn audio_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.audio_sample_rate = sample_rate;
+        self
+    }
+
+    /// Maximum amount of buffered audio samples, one second of samples by default
+    pub fn audio_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.audio_buffer_size = Some(buffer_size);
+        self
+    }
+
+    pub fn build(self) -> GameBoy {
+        let mut circuitry = Circuitry::initialize(self.model, self.ram_initialization, self.hardware_variance);
+        circuitry.get_apu_mut().set_sample_rate(self.audio_sample_rate);
+        circuitry.get_apu_mut().set_buffer_size(self.audio_buffer_size);
+        let revision = self.revision.unwrap_or(self.model.get_default_revision());
+        circuitry.set_quirks(revision.get_quirks());
+        GameBoy {
+            model: self.model,
+            revision,
+            cpu: CPU::initialize(self.model),
+            circuitry,
+            debugger: Default::default(),
+            cycle_count: 0,
+            events: Default::default(),
+            checkpoints: None,
+        }
+    }
+}
+
+impl Default for GameBoyBuilder {
+    fn default() -> Self {
+        Self {
+            model: HardwareModel::default(),
+            ram_initialization: RamInitialization::default(),
+            hardware_variance: HardwareVariance::default(),
+            audio_sample_rate: DEFAULT_SAMPLE_RATE,
+            audio_buffer_size: None,
+            revision: None,
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::game_boy::{GameBoy, StepStart};
+
+/// Errors of the emulated program that the core detects
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EmulationError {
+    /// An illegal opcode was executed and the CPU locked up
+    IllegalOpcode { address: u16, opcode: u8 },
+}
+
+/// A save state for reproducing an error, see [`GameBoy::enable_error_checkpoints`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorCheckpoint {
+    pub error: EmulationError,
+    /// When the error occurred
+    pub cycle_count: u64,
+    pub frame_count: u64,
+    /// Created at the start of the frame the error occurred in, running it again reproduces the error.
+    /// If checkpoints were enabled during that frame, the state is from that point instead.
+    pub state: Vec<u8>,
+}
+
+/// Not part of the emulated state, so it is ignored when comparing and isn't saved
+#[derive(Debug, Clone)]
+pub(super) struct ErrorCheckpoints {
+    /// State at the start of the current frame, or when checkpoints were enabled
+    frame_state: Vec<u8>,
+    frame_count: u64,
+    checkpoint: Option<ErrorCheckpoint>,
+}
+
+impl PartialEq for ErrorCheckpoints {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl GameBoy {
+    /// Keeps a save state of the start of every frame, so a checkpoint can be created once an error occurs.
+    /// Saving a state every frame has a cost, so this is meant for debugging sessions and bug reports.
+    pub fn enable_error_checkpoints(&mut self) {
+        if self.checkpoints.is_none() {
+            self.checkpoints = Some(ErrorCheckpoints {
+                frame_state: self.save_state(),
+                frame_count: self.get_frame_count(),
+                checkpoint: None,
+            });
+        }
+    }
+
+    pub fn disable_error_checkpoints(&mut self) {
+        self.checkpoints = None;
+    }
+
+    /// The checkpoint of the last error since it was taken, later errors replace earlier ones
+    pub fn take_error_checkpoint(&mut self) -> Option<ErrorCheckpoint> {
+        self.checkpoints.as_mut()?.checkpoint.take()
+    }
+
+    /// Creates a checkpoint if the step locked the CPU and keeps the state of a newly started frame
+    pub(super) fn update_checkpoints(&mut self, start: StepStart) {
+        if self.checkpoints.is_none() {
+            return;
+        }
+
+        let frame_count = self.get_frame_count();
+        let error = (!start.locked && self.cpu.is_locked()).then(|| EmulationError::IllegalOpcode {
+            address: start.pc,
+            opcode: self.peek(start.pc),
+        });
+        let state = self
+            .checkpoints
+            .as_ref()
+            .is_some_and(|checkpoints| checkpoints.frame_count != frame_count)
+            .then(|| self.save_state());
+
+        let cycle_count = self.cycle_count;
+        let Some(checkpoints) = &mut self.checkpoints else {
+            return;
+        };
+        if let Some(error) = error {
+            checkpoints.checkpoint = Some(ErrorCheckpoint {
+                error,
+                cycle_count,
+                frame_count,
+                state: checkpoints.frame_state.clone(),
+            });
+        }
+        if let Some(state) = state {
+            checkpoints.frame_state = state;
+            checkpoints.frame_count = frame_count;
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::cartridge::clock_source::CycleClock;
+use crate::cartridge::{Cartridge, CartridgeError};
+use crate::circuitry::ppu::SCREEN_WIDTH;
+use crate::game_boy::GameBoy;
+use crate::game_boy::builder::GameBoyBuilder;
+
+/// The first frame two configurations rendered differently
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FrameDivergence {
+    /// How many frames both ran, including the diverging one
+    pub frame: u32,
+    pub differing_pixels: usize,
+    /// X and Y of the first differing pixel in reading order
+    pub first_pixel: (usize, usize),
+}
+
+/// Runs the same ROM on two configurations frame by frame and compares their output,
+/// for checking that a faster or less accurate configuration still renders the same frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameComparison {
+    a: GameBoy,
+    b: GameBoy,
+    frames: u32,
+}
+
+impl FrameComparison {
+    /// Both cartridges use a [`CycleClock`], so an RTC ticks at the same emulated time in both
+    pub fn new(a: GameBoyBuilder, b: GameBoyBuilder, rom: &[u8]) -> Result<Self, CartridgeError> {
+        let mut a = a.build();
+        let mut b = b.build();
+        a.set_cartridge(Cartridge::load_with_clock_source(rom.to_vec(), Box::new(CycleClock::new()))?);
+        b.set_cartridge(Cartridge::load_with_clock_source(rom.to_vec(), Box::new(CycleClock::new()))?);
+        Ok(Self { a, b, frames: 0 })
+    }
+
+    pub fn get_a(&self) -> &GameBoy {
+        &self.a
+    }
+
+    pub fn get_b(&self) -> &GameBoy {
+        &self.b
+    }
+
+    /// How many frames were compared so far
+    pub fn get_frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Runs both for one frame, see [`GameBoy::run_until_frame`], and compares the frame buffers
+    pub fn run_frame(&mut self) -> Option<FrameDivergence> {
+        self.a.run_until_frame();
+        self.b.run_until_frame();
+        self.frames += 1;
+
+        let mut differing = self
+            .a
+            .get_frame_buffer()
+            .iter()
+            .zip(self.b.get_frame_buffer().iter())
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(index, _)| index);
+        let first = differing.next()?;
+        Some(FrameDivergence {
+            frame: self.frames,
+            differing_pixels: differing.count() + 1,
+            first_pixel: (first % SCREEN_WIDTH, first / SCREEN_WIDTH),
+        })
+    }
+
+    /// Compares up to the given amount of frames, stopping at the first divergence
+    pub fn run(&mut self, frames: u32) -> Option<FrameDivergence> {
+        (0..frames).find_map(|_| self.run_frame())
+    }
+}
@@ -0,0 +1,157 @@
+use crate::cartridge::{ROM_END, ROM_START};
+use crate::circuitry::interface::CircuitryInterface;
+use crate::circuitry::interrupts::Interrupt;
+use crate::cpu::registers::CpuRegistersAccessTrait;
+use crate::debug::disassembler::{DisassembledInstruction, disassemble_instruction, disassemble_range};
+use crate::debug::trace_buffer::TraceEntry;
+use crate::debug::{BreakReason, Debugger, ProtectionMode, StepHits, StepKind, TraceRecord, WatchedCircuitry};
+use crate::game_boy::GameBoy;
+
+impl GameBoy {
+    pub fn get_debugger(&self) -> &Debugger {
+        &self.debugger
+    }
+
+    pub fn get_debugger_mut(&mut self) -> &mut Debugger {
+        &mut self.debugger
+    }
+
+    /// Reads memory without any side effects
+    pub fn peek(&self, address: u16) -> u8 {
+        self.circuitry.peek(address)
+    }
+
+    /// Disassembles all instructions starting within the inclusive range
+    pub fn disassemble(&self, start: u16, end: u16) -> Vec<DisassembledInstruction> {
+        disassemble_range(|address| self.peek(address), start, end)
+    }
+
+    /// Executes a single step like [`GameBoy::step`] and describes what happened
+    pub fn step_instruction(&mut self) -> TraceRecord {
+        let start = self.begin_step();
+        let registers = self.get_cpu_snapshot();
+        let kind = self.get_next_step_kind();
+        let instruction = disassemble_instruction(|address| self.peek(address), registers.pc);
+        let mut circuitry = WatchedCircuitry::new(&mut self.circuitry, &self.debugger);
+        let cycles = self.cpu.step(&mut circuitry);
+        let (hits, dots) = circuitry.into_trace();
+        self.finish_step(start, cycles);
+        TraceRecord {
+            kind,
+            instruction,
+            registers,
+            cycles,
+            watchpoint_hits: hits.watchpoints,
+            register_hits: hits.registers,
+            blocked_writes: hits.blocked_writes,
+            dots,
+        }
+    }
+
+    /// Runs until a breakpoint, watchpoint, register watch or trap is hit, or the given amount of M-cycles passed.
+    /// At least one step is executed, so execution can be resumed from a breakpoint.
+    /// Breakpoints stop before the instruction is executed, the others after the accessing instruction.
+    pub fn run_until_break(&mut self, cycle_limit: u64) -> BreakReason {
+        let mut cycles = 0u64;
+        loop {
+            let (step_cycles, hits) = self.step_watched();
+            if let Some(&hit) = hits.watchpoints.first() {
+                return BreakReason::Watchpoint(hit);
+            }
+            if let Some(&hit) = hits.registers.first() {
+                return BreakReason::RegisterWrite(hit);
+            }
+            if let Some(&write) = hits.blocked_writes.iter().find(|write| write.mode == ProtectionMode::Trap) {
+                return BreakReason::ProtectedWrite(write);
+            }
+
+            if self.get_next_step_kind() == StepKind::Instruction {
+                let pc = self.cpu.get_pc();
+                if let Some(reason) = self.debugger.check_breakpoints(pc, self.peek(pc)) {
+                    return reason;
+                }
+            }
+
+            cycles += step_cycles as u64;
+            if cycles >= cycle_limit {
+                return BreakReason::CycleLimit;
+            }
+        }
+    }
+
+    /// Adds the next instruction to the trace buffer, if it is enabled and an instruction is executed next
+    pub(super) fn record_trace(&mut self) {
+        if self.debugger.get_trace_buffer().is_none() || self.get_next_step_kind() != StepKind::Instruction {
+            return;
+        }
+
+        let pc = self.cpu.get_pc();
+        let entry = TraceEntry {
+            pc,
+            af: self.cpu.get_af(),
+            bc: self.cpu.get_bc(),
+            de: self.cpu.get_de(),
+            hl: self.cpu.get_hl(),
+            sp: self.cpu.get_sp(),
+            bytes: [0, 1, 2].map(|offset| self.peek(pc.wrapping_add(offset))),
+        };
+        if let Some(buffer) = self.debugger.get_trace_buffer_mut() {
+            buffer.push(entry);
+        }
+    }
+
+    /// Attributes the cycles of a step to the ROM bank at the PC it started at, if bank usage is enabled
+    pub(super) fn record_bank_usage(&mut self, pc: u16, cycles: u8) {
+        if self.debugger.get_bank_usage().is_none() {
+            return;
+        }
+
+        let bank = self
+            .get_cartridge()
+            .filter(|_| (ROM_START..=ROM_END).contains(&pc))
+            .map(|cartridge| cartridge.get_rom_bank(pc));
+        if let Some(usage) = self.debugger.get_bank_usage_mut() {
+            usage.record(bank, cycles);
+        }
+    }
+
+    /// Captures the I/O registers once a frame was completed, if I/O changes are enabled
+    pub(super) fn capture_io_changes(&mut self) {
+        let frame_count = self.get_frame_count();
+        let captured = self.debugger.get_io_changes().map(|changes| changes.get_frame_count() == Some(frame_count));
+        if captured != Some(false) {
+            return;
+        }
+
+        let Some(mut changes) = self.debugger.get_io_changes_mut().map(std::mem::take) else {
+            return;
+        };
+        changes.capture(frame_count, |address| self.circuitry.peek(address));
+        if let Some(io_changes) = self.debugger.get_io_changes_mut() {
+            *io_changes = changes;
+        }
+    }
+
+    pub(super) fn step_watched(&mut self) -> (u8, StepHits) {
+        let start = self.begin_step();
+        let mut circuitry = WatchedCircuitry::new(&mut self.circuitry, &self.debugger);
+        let cycles = self.cpu.step(&mut circuitry);
+        let hits = circuitry.into_hits();
+        self.finish_step(start, cycles);
+        (cycles, hits)
+    }
+
+    /// Mirrors the order in which [`CPU::step`](crate::cpu::CPU::step) checks its state
+    fn get_next_step_kind(&self) -> StepKind {
+        let pending = self.circuitry.get_pending_interrupts();
+        let stopped = self.cpu.is_stopped() && self.circuitry.get_interrupt_flag() & Interrupt::Joypad.get_bit() == 0;
+        if self.cpu.is_locked() || stopped || (self.cpu.is_halted() && pending == 0) {
+            return StepKind::Idle;
+        }
+
+        match Interrupt::highest_priority(pending) {
+            Some(interrupt) if self.cpu.get_ime() => StepKind::Interrupt(interrupt),
+            _ => StepKind::Instruction,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::game_boy::{GameBoy, StepStart};
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+type Callback = Box<dyn FnOnce(&mut GameBoy) + Send>;
+
+/// Identifies a scheduled event, for cancelling it
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct EventId(u64);
+
+/// Emulated point in time an event is due at
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EventTime {
+    /// Total M-cycles since power-on, see [`GameBoy::get_cycle_count`]
+    Cycle(u64),
+    /// Frame count, see [`GameBoy::get_frame_count`]
+    Frame(u64),
+}
+
+#[derive(Clone)]
+struct ScheduledEvent {
+    id: EventId,
+    time: EventTime,
+    /// Shared between clones of the emulator, the first one reaching the time runs it
+    callback: Arc<Mutex<Option<Callback>>>,
+}
+
+/// Callbacks waiting for their emulated time.
+/// They are not part of the emulated state, so they are ignored when comparing and aren't saved.
+#[derive(Default, Clone)]
+pub(super) struct ScheduledEvents {
+    next_id: u64,
+    events: Vec<ScheduledEvent>,
+}
+
+impl ScheduledEvents {
+    fn is_due(time: EventTime, cycle_count: u64, frame_count: u64) -> bool {
+        match time {
+            EventTime::Cycle(cycle) => cycle_count >= cycle,
+            EventTime::Frame(frame) => frame_count >= frame,
+        }
+    }
+
+    /// Removes the next due event, in the order they were scheduled
+    fn take_due(&mut self, cycle_count: u64, frame_count: u64) -> Option<ScheduledEvent> {
+        let index = self
+            .events
+            .iter()
+            .position(|event| Self::is_due(event.time, cycle_count, frame_count))?;
+        Some(self.events.remove(index))
+    }
+}
+
+impl Debug for ScheduledEvents {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScheduledEvents").field("pending", &self.events.len()).finish()
+    }
+}
+
+impl PartialEq for ScheduledEvents {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl GameBoy {
+    /// Total M-cycles executed since power-on
+    pub fn get_cycle_count(&self) -> u64 {
+        self.cycle_count
+    }
+
+    /// Calls the callback once after the step that reaches the given time.
+    /// Times that already passed are due after the next step.
+    pub fn schedule_event(&mut self, time: EventTime, callback: impl FnOnce(&mut GameBoy) + Send + 'static) -> EventId {
+        let id = EventId(self.events.next_id);
+        self.events.next_id += 1;
+        self.events.events.push(ScheduledEvent {
+            id,
+            time,
+            callback: Arc::new(Mutex::new(Some(Box::new(callback)))),
+        });
+        id
+    }
+
+    /// Returns false if the event already ran or doesn't exist
+    pub fn cancel_event(&mut self, id: EventId) -> bool {
+        let count = self.events.events.len();
+        self.events.events.retain(|event| event.id != id);
+        self.events.events.len() != count
+    }
+
+    pub fn get_pending_event_count(&self) -> usize {
+        self.events.events.len()
+    }
+
+    /// Counts the cycles of a completed step and runs the events that became due
+    pub(super) fn finish_step(&mut self, start: StepStart, cycles: u8) {
+        if let Some(started) = start.profiled {
+            self.circuitry.finish_profiled_step(started);
+        }
+        self.cycle_count += cycles as u64;
+        self.record_bank_usage(start.pc, cycles);
+        self.capture_io_changes();
+        self.update_checkpoints(start);
+        if self.events.events.is_empty() {
+            return;
+        }
+
+        let frame_count = self.get_frame_count();
+        // Events scheduled by a callback for a time that already passed run right away as well
+        while let Some(event) = self.events.take_due(self.cycle_count, frame_count) {
+            let callback = event.callback.lock().ok().and_then(|mut callback| callback.take());
+            if let Some(callback) = callback {
+                callback(self);
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::circuitry::ppu::{FrameBuffer, SCREEN_HEIGHT, SCREEN_WIDTH};
+use std::cell::UnsafeCell;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Marks the spare slot as holding a frame the consumer hasn't picked up yet
+const NEW_FRAME_BIT: u8 = 0b100;
+const SLOT_MASK: u8 = 0b011;
+
+/// Triple-buffered handover of frames from the emulation thread to a render thread, without locks.
+/// The publisher writes into its own slot and swaps it with the spare one, the consumer swaps its slot with the spare
+/// one whenever a new frame arrived. Neither side ever waits for the other, the consumer always sees the latest
+/// complete frame and frames it didn't pick up in time are skipped.
+pub struct FrameMailbox<T = FrameBuffer> {
+    slots: [UnsafeCell<Box<T>>; 3],
+    /// Index of the slot owned by neither side, with the new frame bit
+    spare: AtomicU8,
+}
+
+// Each slot is only accessed by the side owning its index, ownership is handed over through the atomic swap
+unsafe impl<T: Send> Sync for FrameMailbox<T> {}
+
+impl<T: Clone + Send> FrameMailbox<T> {
+    /// Creates the two ends of a mailbox, every slot starts with the initial frame
+    pub fn create(initial: T) -> (FramePublisher<T>, FrameConsumer<T>) {
+        let mailbox = Arc::new(Self {
+            slots: [
+                UnsafeCell::new(Box::new(initial.clone())),
+                UnsafeCell::new(Box::new(initial.clone())),
+                UnsafeCell::new(Box::new(initial)),
+            ],
+            spare: AtomicU8::new(1),
+        });
+        let publisher = FramePublisher {
+            mailbox: mailbox.clone(),
+            slot: 0,
+        };
+        let consumer = FrameConsumer { mailbox, slot: 2 };
+        (publisher, consumer)
+    }
+}
+
+impl FrameMailbox {
+    /// A mailbox for frame buffers, starting with a white screen
+    pub fn for_frames() -> (FramePublisher, FrameConsumer) {
+        Self::create([0; SCREEN_WIDTH * SCREEN_HEIGHT])
+    }
+}
+
+impl<T> Debug for FrameMailbox<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameMailbox").field("spare", &self.spare.load(Ordering::Relaxed)).finish()
+    }
+}
+
+/// The emulation thread's end of a [`FrameMailbox`]
+#[derive(Debug)]
+pub struct FramePublisher<T = FrameBuffer> {
+    mailbox: Arc<FrameMailbox<T>>,
+    slot: u8,
+}
+
+impl<T: Clone + Send> FramePublisher<T> {
+    /// Copies the frame into the mailbox, replacing a previous frame the consumer hasn't picked up yet
+    pub fn publish(&mut self, frame: &T) {
+        self.publish_with(|slot| slot.clone_from(frame));
+    }
+
+    /// Lets the closure write the frame in place, the slot still contains the frame published two frames ago
+    pub fn publish_with(&mut self, write: impl FnOnce(&mut T)) {
+        // SAFETY: Only the publisher accesses the slot with its index until it hands it over below
+        write(unsafe { &mut *self.mailbox.slots[self.slot as usize].get() });
+        let previous = self.mailbox.spare.swap(self.slot | NEW_FRAME_BIT, Ordering::AcqRel);
+        self.slot = previous & SLOT_MASK;
+    }
+}
+
+/// The render thread's end of a [`FrameMailbox`]
+#[derive(Debug)]
+pub struct FrameConsumer<T = FrameBuffer> {
+    mailbox: Arc<FrameMailbox<T>>,
+    slot: u8,
+}
+
+impl<T: Send> FrameConsumer<T> {
+    /// Whether a frame was published since the last one was picked up
+    pub fn has_new_frame(&self) -> bool {
+        self.mailbox.spare.load(Ordering::Acquire) & NEW_FRAME_BIT != 0
+    }
+
+    /// Picks up the latest published frame, None if there was no new one
+    pub fn take_new_frame(&mut self) -> Option<&T> {
+        if !self.has_new_frame() {
+            return None;
+        }
+        let previous = self.mailbox.spare.swap(self.slot, Ordering::AcqRel);
+        self.slot = previous & SLOT_MASK;
+        Some(self.get_frame())
+    }
+
+    /// The latest published frame, picking it up if it is new
+    pub fn latest_frame(&mut self) -> &T {
+        self.take_new_frame();
+        self.get_frame()
+    }
+
+    fn get_frame(&self) -> &T {
+        // SAFETY: Only the consumer accesses the slot with its index until it hands it over
+        unsafe { &*self.mailbox.slots[self.slot as usize].get() }
+    }
+}
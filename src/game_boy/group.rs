@@ -0,0 +1,92 @@
+use crate::game_boy::GameBoy;
+
+/// Steps several emulators in lockstep, for linked instances, netplay or comparing two emulators.
+/// The instance that is furthest behind always steps next, ties are resolved by index,
+/// so the interleaving only depends on the emulated cycles and is the same on every run.
+/// Steps execute whole instructions, so instances can be ahead of each other by a few M-cycles.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GameBoyGroup {
+    game_boys: Vec<GameBoy>,
+    /// M-cycles each instance ran since it joined the group
+    elapsed: Vec<u64>,
+    /// M-cycles every instance reached at least
+    target: u64,
+}
+
+impl GameBoyGroup {
+    pub fn new(game_boys: Vec<GameBoy>) -> Self {
+        Self {
+            elapsed: vec![0; game_boys.len()],
+            game_boys,
+            target: 0,
+        }
+    }
+
+    /// Adds an instance that starts at the current time of the group, returns its index
+    pub fn push(&mut self, game_boy: GameBoy) -> usize {
+        self.game_boys.push(game_boy);
+        self.elapsed.push(self.target);
+        self.game_boys.len() - 1
+    }
+
+    pub fn get(&self, index: usize) -> Option<&GameBoy> {
+        self.game_boys.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut GameBoy> {
+        self.game_boys.get_mut(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &GameBoy> {
+        self.game_boys.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.game_boys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.game_boys.is_empty()
+    }
+
+    pub fn into_inner(self) -> Vec<GameBoy> {
+        self.game_boys
+    }
+
+    /// M-cycles the instance ran since it joined the group
+    pub fn get_elapsed_cycles(&self, index: usize) -> Option<u64> {
+        self.elapsed.get(index).copied()
+    }
+
+    /// M-cycles all instances ran at least
+    pub fn get_target_cycles(&self) -> u64 {
+        self.target
+    }
+
+    /// Steps the instance that is furthest behind
+    ///
+    /// # Returns
+    ///
+    /// The index of the stepped instance, None if the group is empty
+    pub fn step(&mut self) -> Option<usize> {
+        let index = self.get_furthest_behind()?;
+        self.elapsed[index] += self.game_boys[index].step() as u64;
+        Some(index)
+    }
+
+    /// Runs all instances for at least the given amount of M-cycles, interleaved step by step
+    pub fn run_cycles(&mut self, cycles: u64) {
+        self.target += cycles;
+        while self.elapsed.iter().any(|&elapsed| elapsed < self.target) {
+            self.step();
+        }
+    }
+
+    fn get_furthest_behind(&self) -> Option<usize> {
+        self.elapsed
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &elapsed)| elapsed)
+            .map(|(index, _)| index)
+    }
+}
@@ -0,0 +1,22 @@
+pub mod revision;
+
+/// The hardware revision that is being emulated.
+/// Mainly affects the register state after the boot ROM hands off control to the cartridge.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum HardwareModel {
+    /// Original Game Boy with the early boot ROM revision
+    #[default]
+    DMG0,
+    /// Original Game Boy
+    DMG,
+    /// Game Boy Pocket
+    MGB,
+    /// Super Game Boy
+    SGB,
+    /// Super Game Boy 2
+    SGB2,
+    /// Game Boy Color
+    CGB,
+    /// Game Boy Advance and Game Boy Advance SP running Game Boy software
+    AGB,
+}
@@ -0,0 +1,55 @@
+use crate::hardware_model::HardwareModel;
+
+/// The chip family whose hardware quirks are emulated.
+/// Individual revisions like DMG-CPU A/B/C or CGB-CPU 0 to E only get their own variant once a difference between them
+/// is emulated, until then they share the quirks of their family.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Revision {
+    /// DMG, MGB and SGB chips
+    #[default]
+    DMG,
+    /// CGB and AGB chips
+    CGB,
+}
+
+impl Revision {
+    pub fn get_quirks(&self) -> Quirks {
+        match self {
+            Self::DMG => Quirks {
+                stat_write_interrupt: true,
+                apu_length_writable_while_off: true,
+            },
+            Self::CGB => Quirks {
+                stat_write_interrupt: false,
+                apu_length_writable_while_off: false,
+            },
+        }
+    }
+}
+
+impl HardwareModel {
+    /// The revision of the chips the model shipped with
+    pub fn get_default_revision(&self) -> Revision {
+        match self {
+            Self::DMG0 | Self::DMG | Self::MGB | Self::SGB | Self::SGB2 => Revision::DMG,
+            Self::CGB | Self::AGB => Revision::CGB,
+        }
+    }
+}
+
+/// Behavior that differs between hardware revisions
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Quirks {
+    /// Writing STAT enables the HBlank, VBlank and LYC sources for a moment, which can request a spurious interrupt,
+    /// according to: https://gbdev.io/pandocs/STAT.html#spurious-stat-interrupts
+    pub stat_write_interrupt: bool,
+    /// The length timers in NRx1 can be written while the APU is off,
+    /// according to: https://gbdev.io/pandocs/Audio_details.html#power-control
+    pub apu_length_writable_while_off: bool,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Revision::default().get_quirks()
+    }
+}
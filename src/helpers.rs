@@ -1 +1,3 @@
-pub mod bit_operations;
\ No newline at end of file
+pub mod bit_operations;
+pub mod crc32;
+pub mod random;
\ No newline at end of file
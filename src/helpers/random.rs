@@ -0,0 +1,32 @@
+/// Small deterministic PRNG (SplitMix64), the same seed always produces the same sequence on every platform.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Creating a generator with this as the seed continues the sequence, for saving it
+    pub fn get_state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_u8(&mut self) -> u8 {
+        (self.next_u64() >> 56) as u8
+    }
+
+    pub fn fill_bytes(&mut self, data: &mut [u8]) {
+        data.iter_mut().for_each(|byte| *byte = self.next_u8());
+    }
+}
@@ -1,4 +1,8 @@
 pub mod game_boy;
 pub mod cpu;
 pub mod circuitry;
+pub mod cartridge;
 pub mod helpers;
+pub mod debug;
+pub mod hardware_model;
+pub mod save_state;
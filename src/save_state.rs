@@ -0,0 +1,199 @@
+use std::fmt::{Display, Formatter};
+
+/// Identifies save states created by this crate
+pub const SAVE_STATE_MAGIC: [u8; 4] = *b"LGBS";
+/// Incremented whenever the layout changes, states of other versions are rejected.
+/// States from [`OLDEST_MIGRATABLE_VERSION`] on can be upgraded with
+/// [`GameBoy::migrate_state`](crate::game_boy::GameBoy::migrate_state).
+pub const SAVE_STATE_VERSION: u16 = 14;
+/// Older states differ in more than fields that were added since
+pub const OLDEST_MIGRATABLE_VERSION: u16 = 9;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveStateError {
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    /// The state was created on a different hardware model
+    ModelMismatch,
+    /// The state was created with a cartridge while none is inserted or the other way around
+    CartridgeMismatch,
+    /// The state was created with a different ROM,
+    /// it can still be loaded with [`GameBoy::load_state_ignoring_rom`](crate::game_boy::GameBoy::load_state_ignoring_rom)
+    RomMismatch { state_crc32: u32, current_crc32: u32 },
+    UnexpectedEnd,
+    InvalidData,
+}
+
+impl Display for SaveStateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "not a save state"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported save state version {version}, expected {SAVE_STATE_VERSION}")
+            }
+            Self::ModelMismatch => write!(f, "save state was created on a different hardware model"),
+            Self::CartridgeMismatch => write!(f, "save state was created with a different cartridge"),
+            Self::RomMismatch {
+                state_crc32,
+                current_crc32,
+            } => write!(
+                f,
+                "save state was created with a different ROM: expected CRC-32 {current_crc32:08X}, got {state_crc32:08X}"
+            ),
+            Self::UnexpectedEnd => write!(f, "unexpected end of save state data"),
+            Self::InvalidData => write!(f, "invalid save state data"),
+        }
+    }
+}
+
+impl std::error::Error for SaveStateError {}
+
+/// A state upgraded to the current version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateMigration {
+    pub from_version: u16,
+    pub state: Vec<u8>,
+    /// The fields the old state didn't contain, they were filled with their power-on values
+    pub defaulted: Vec<&'static str>,
+}
+
+/// Components that can write their state into a save state and restore it again.
+/// Loading happens into an already initialized component, configuration that isn't part of the state is kept.
+pub trait SaveState {
+    fn save_state(&self, writer: &mut StateWriter);
+    fn load_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError>;
+}
+
+/// Little endian binary writer for save states
+#[derive(Debug, Default)]
+pub struct StateWriter {
+    data: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes the bytes without their length, for regions with a size known when loading
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Reads what [`StateWriter`] has written, failing instead of panicking on truncated or invalid data
+#[derive(Debug)]
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    /// Version of the layout, components skip fields that didn't exist yet in older versions
+    version: u16,
+    defaulted: Vec<&'static str>,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            version: SAVE_STATE_VERSION,
+            defaulted: Vec::new(),
+        }
+    }
+
+    pub fn get_version(&self) -> u16 {
+        self.version
+    }
+
+    pub fn set_version(&mut self, version: u16) {
+        self.version = version;
+    }
+
+    /// Whether the state contains the fields added in the given version
+    pub fn has_version(&self, version: u16) -> bool {
+        self.version >= version
+    }
+
+    /// Notes that a field was missing from an older state and kept its default value
+    pub fn record_default(&mut self, field: &'static str) {
+        if !self.defaulted.contains(&field) {
+            self.defaulted.push(field);
+        }
+    }
+
+    pub fn get_defaulted(&self) -> &[&'static str] {
+        &self.defaulted
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, SaveStateError> {
+        Ok(self.read_slice(1)?[0])
+    }
+
+    /// Only 0 and 1 are valid
+    pub fn read_bool(&mut self) -> Result<bool, SaveStateError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SaveStateError::InvalidData),
+        }
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, SaveStateError> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, SaveStateError> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, SaveStateError> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], SaveStateError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_slice(N)?);
+        Ok(array)
+    }
+
+    /// Fills the whole target, the region size has to be known in advance
+    pub fn read_into(&mut self, target: &mut [u8]) -> Result<(), SaveStateError> {
+        target.copy_from_slice(self.read_slice(target.len())?);
+        Ok(())
+    }
+
+    pub fn read_slice(&mut self, length: usize) -> Result<&'a [u8], SaveStateError> {
+        let end = self.position.checked_add(length).ok_or(SaveStateError::UnexpectedEnd)?;
+        let slice = self.data.get(self.position..end).ok_or(SaveStateError::UnexpectedEnd)?;
+        self.position = end;
+        Ok(slice)
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.position == self.data.len()
+    }
+}
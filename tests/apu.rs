@@ -0,0 +1,296 @@
+mod common;
+
+use common::{boot, boot_with};
+use lemon_gb_core::circuitry::apu::{AudioBufferStats, AudioChannel, M_CYCLES_PER_SECOND, StereoSample};
+use lemon_gb_core::game_boy::GameBoy;
+use lemon_gb_core::hardware_model::HardwareModel;
+use lemon_gb_core::hardware_model::revision::Revision;
+use rstest::rstest;
+use std::sync::{Arc, Mutex};
+
+/// Plays channel 2 at full volume with a length of 2 length clocks
+const PLAY_PULSE: &str = "LD A, 0x3E; LDH (0x16), A; LD A, 0xF0; LDH (0x17), A; LD A, 0xC0; LDH (0x19), A; JR -2";
+
+/// Fills wave RAM with the highest sample and triggers the wave channel at the given NR32 volume
+fn play_wave(volume: u8) -> String {
+    format!(
+        "LD HL, 0xFF30; LD A, 0xFF; LD B, 16; LD (HL+), A; DEC B; JR NZ, -4; \
+         LD A, 0x80; LDH (0x1A), A; LD A, {volume}; LDH (0x1C), A; LD A, 0x87; LDH (0x1E), A"
+    )
+}
+
+#[test]
+fn test_registers_after_boot() {
+    let game_boy = boot("JR -2");
+    assert_eq!(game_boy.peek(0xFF26), 0xF1);
+    assert_eq!(game_boy.peek(0xFF24), 0x77);
+    assert_eq!(game_boy.peek(0xFF25), 0xF3);
+    assert_eq!(game_boy.peek(0xFF11), 0xBF);
+    assert_eq!(game_boy.peek(0xFF12), 0xF3);
+    // Write-only and unused registers read as 0xFF
+    assert_eq!(game_boy.peek(0xFF13), 0xFF);
+    assert_eq!(game_boy.peek(0xFF15), 0xFF);
+    assert_eq!(game_boy.peek(0xFF27), 0xFF);
+}
+
+#[test]
+fn test_power_off_clears_registers_but_keeps_wave_ram() {
+    let source = "LD A, 0x12; LDH (0x30), A; LD A, 0x00; LDH (0x26), A; LD A, 0x77; LDH (0x24), A; JR -2";
+    let mut game_boy = boot(source);
+    game_boy.run_until_frame();
+
+    assert_eq!(game_boy.peek(0xFF26), 0x70);
+    assert_eq!(game_boy.peek(0xFF11), 0x3F);
+    // Writes are ignored while the APU is off
+    assert_eq!(game_boy.peek(0xFF24), 0x00);
+    assert_eq!(game_boy.peek(0xFF30), 0x12);
+}
+
+#[test]
+fn test_reset_apu() {
+    let source = "LD A, 0x12; LDH (0x30), A; LD A, 0x00; LDH (0x26), A; JR -2";
+    let mut game_boy = boot_with(GameBoy::builder().audio_sample_rate(22_050).build(), source);
+    game_boy.set_audio_channel_enabled(AudioChannel::Noise, false);
+    game_boy.run_until_frame();
+    assert_eq!(game_boy.peek(0xFF26), 0x70);
+
+    game_boy.reset_apu();
+    let fresh = GameBoy::new(HardwareModel::DMG);
+    for address in 0xFF10..=0xFF3F {
+        assert_eq!(game_boy.peek(address), fresh.peek(address), "0x{address:04X}");
+    }
+    assert_eq!(game_boy.get_audio_sample_rate(), 22_050);
+    assert!(game_boy.get_apu().is_channel_muted(AudioChannel::Noise));
+}
+
+#[test]
+fn test_length_counter_disables_channel() {
+    let mut game_boy = boot(PLAY_PULSE);
+    // The entry point takes 2 instructions to jump to the code
+    for _ in 0..8 {
+        game_boy.step();
+    }
+    assert_eq!(game_boy.peek(0xFF26), 0xF3);
+
+    game_boy.run_until_frame();
+    assert_eq!(game_boy.peek(0xFF26), 0xF1);
+}
+
+#[rstest]
+#[case(48_000)]
+#[case(44_100)]
+#[case(22_050)]
+fn test_sample_count_matches_rate(#[case] sample_rate: u32) {
+    let game_boy = GameBoy::builder().model(HardwareModel::DMG).audio_sample_rate(sample_rate).build();
+    let mut game_boy = boot_with(game_boy, PLAY_PULSE);
+
+    let mut cycles = 0;
+    for _ in 0..10 {
+        cycles += game_boy.run_until_frame() as u64;
+    }
+    let expected = cycles * sample_rate as u64 / M_CYCLES_PER_SECOND as u64;
+    assert_eq!(game_boy.drain_audio_samples().len() as u64, expected);
+    assert!(game_boy.drain_audio_samples().is_empty());
+}
+
+#[test]
+fn test_audio_buffer_size_and_stats() {
+    let game_boy = GameBoy::builder().model(HardwareModel::DMG).audio_buffer_size(100).build();
+    let mut game_boy = boot_with(game_boy, PLAY_PULSE);
+    assert_eq!(game_boy.get_audio_buffer_size(), 100);
+
+    // A frame generates about 800 samples at 48 kHz
+    game_boy.run_until_frame();
+    let overrun = game_boy.get_audio_buffer_stats().overrun_samples;
+    assert!(overrun > 600);
+    assert_eq!(game_boy.take_audio_samples(60).len(), 60);
+    assert_eq!(game_boy.take_audio_samples(60).len(), 40);
+    assert_eq!(
+        game_boy.get_audio_buffer_stats(),
+        AudioBufferStats {
+            overrun_samples: overrun,
+            underruns: 1,
+            underrun_samples: 20,
+        }
+    );
+
+    game_boy.reset_audio_buffer_stats();
+    game_boy.set_audio_buffer_size(None);
+    game_boy.run_until_frame();
+    assert_eq!(game_boy.get_audio_buffer_size(), 48_000);
+    assert_eq!(game_boy.get_audio_buffer_stats(), AudioBufferStats::default());
+}
+
+#[test]
+fn test_audio_callback_receives_samples() {
+    let mut game_boy = boot(PLAY_PULSE);
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    game_boy.set_audio_callback(move |sample| sink.lock().unwrap().push(sample));
+
+    game_boy.run_until_frame();
+    assert!(game_boy.drain_audio_samples().is_empty());
+    let count = received.lock().unwrap().len();
+    assert!(count > 0);
+
+    game_boy.clear_audio_callback();
+    game_boy.run_until_frame();
+    assert!(!game_boy.drain_audio_samples().is_empty());
+    assert_eq!(received.lock().unwrap().len(), count);
+}
+
+#[test]
+fn test_muted_channels_are_silent() {
+    let mut game_boy = boot(PLAY_PULSE);
+    game_boy.run_until_frame();
+    assert!(game_boy.drain_audio_samples().iter().any(|sample| *sample != StereoSample::default()));
+
+    let mut game_boy = boot(PLAY_PULSE);
+    for channel in [AudioChannel::Pulse1, AudioChannel::Pulse2, AudioChannel::Wave, AudioChannel::Noise] {
+        game_boy.set_audio_channel_enabled(channel, false);
+    }
+    game_boy.run_until_frame();
+    assert!(game_boy.drain_audio_samples().iter().all(|sample| *sample == StereoSample::default()));
+}
+
+#[rstest]
+#[case(Revision::DMG, false)]
+#[case(Revision::CGB, true)]
+fn test_length_writable_while_off(#[case] revision: Revision, #[case] still_active: bool) {
+    let source = "LD A, 0x00; LDH (0x26), A; LD A, 0x3F; LDH (0x11), A; LD A, 0x80; LDH (0x26), A; \
+                  LD A, 0xF0; LDH (0x12), A; LD A, 0xC0; LDH (0x14), A; JR -2";
+    let game_boy = GameBoy::builder().model(HardwareModel::DMG).revision(revision).build();
+    let mut game_boy = boot_with(game_boy, source);
+    game_boy.run_until_frame();
+    game_boy.run_until_frame();
+
+    assert_eq!(game_boy.peek(0xFF26) & 0x01 != 0, still_active);
+}
+
+#[rstest]
+#[case(0x20, 1.0)]
+#[case(0x40, 7.0 / 7.5 - 1.0)]
+#[case(0x60, 3.0 / 7.5 - 1.0)]
+#[case(0x00, -1.0)]
+fn test_wave_volume_shift(#[case] volume: u8, #[case] level: f32) {
+    let mut game_boy = boot(&format!("{}; JR -2", play_wave(volume)));
+    game_boy.run_until_frame();
+    assert!((game_boy.get_apu().get_channel_level(AudioChannel::Wave) - level).abs() < 1e-6);
+}
+
+#[test]
+fn test_disabled_dac_fades_out() {
+    let source = format!("{}; LD B, 0; DEC B; JR NZ, -3; LD A, 0x00; LDH (0x1A), A; JR -2", play_wave(0x20));
+    let mut game_boy = boot(&source);
+    while game_boy.get_apu().get_channel_level(AudioChannel::Wave) < 1.0 {
+        game_boy.step();
+    }
+    while game_boy.peek(0xFF1A) & 0x80 != 0 {
+        game_boy.step();
+    }
+    game_boy.step();
+
+    let level = game_boy.get_apu().get_channel_level(AudioChannel::Wave);
+    assert!(level > 0.9 && level < 1.0);
+    game_boy.run_until_frame();
+    assert!(game_boy.get_apu().get_channel_level(AudioChannel::Wave).abs() < 1e-3);
+}
+
+#[rstest]
+#[case(0x00, true)]
+#[case(0x08, true)]
+#[case(0xE0, false)]
+#[case(0xF0, false)]
+fn test_noise_clock_shift(#[case] polynomial: u8, #[case] changes: bool) {
+    // Runs the LFSR at its fastest for a while before switching to the tested clock shift
+    let source = format!(
+        "LD A, 0xF0; LDH (0x21), A; XOR A; LDH (0x22), A; LD A, 0x80; LDH (0x23), A; \
+         LD B, 0; DEC B; JR NZ, -3; LD A, {polynomial}; LDH (0x22), A; JR -2"
+    );
+    let mut game_boy = boot(&source);
+    while game_boy.get_cycle_count() < 2000 {
+        game_boy.step();
+    }
+
+    let mut levels = Vec::new();
+    while game_boy.get_cycle_count() < 200_000 {
+        game_boy.step();
+        levels.push(game_boy.get_apu().get_channel_level(AudioChannel::Noise));
+    }
+
+    assert!(game_boy.get_apu().is_channel_active(AudioChannel::Noise));
+    assert_eq!(levels.iter().any(|level| *level != levels[0]), changes);
+}
+
+#[rstest]
+#[case::divisor_code_0(0x40, 32)]
+#[case::divisor_code_1(0x41, 64)]
+fn test_noise_period(#[case] polynomial: u8, #[case] period: u64) {
+    // A clock shift of 4 makes the LFSR period 8 << 4 T-cycles for divisor code 0, which acts as a divisor of 8
+    let source =
+        format!("LD A, 0xF0; LDH (0x21), A; LD A, {polynomial}; LDH (0x22), A; LD A, 0x80; LDH (0x23), A; JR -2");
+    let mut game_boy = boot(&source);
+    while !game_boy.get_apu().is_channel_active(AudioChannel::Noise) {
+        game_boy.step();
+    }
+    let start = game_boy.get_cycle_count();
+    let level = game_boy.get_apu().get_channel_level(AudioChannel::Noise);
+    while game_boy.get_apu().get_channel_level(AudioChannel::Noise) == level {
+        game_boy.step();
+    }
+
+    // Starting with all bits set, the lowest bit of the LFSR is cleared by the 15th clock
+    let elapsed = game_boy.get_cycle_count() - start;
+    assert!((15 * period..15 * period + 4).contains(&elapsed));
+}
+
+#[rstest]
+#[case(0x00, 0, false)]
+#[case(0x00, 33, true)]
+#[case(0x01, 33, false)]
+#[case(0x01, 70, true)]
+fn test_noise_short_mode_lock(#[case] polynomial: u8, #[case] delay: usize, #[case] locked: bool) {
+    // After a trigger the lower 7 bits of the LFSR are all 0 from its 15th to its 22nd clock,
+    // switching to the 7 bit mode within those clocks locks the output
+    let nops = vec!["NOP"; delay].join("; ");
+    let source = format!(
+        "LD A, 0xF0; LDH (0x21), A; LD A, {polynomial}; LDH (0x22), A; LD A, 0x80; LDH (0x23), A; {nops}; \
+         LD A, {}; LDH (0x22), A; JR -2",
+        polynomial | 0x08
+    );
+    let mut game_boy = boot(&source);
+    while game_boy.get_cycle_count() < 2000 {
+        game_boy.step();
+    }
+
+    let mut levels = Vec::new();
+    while game_boy.get_cycle_count() < 20_000 {
+        game_boy.step();
+        levels.push(game_boy.get_apu().get_channel_level(AudioChannel::Noise));
+    }
+
+    assert!(game_boy.get_apu().is_channel_active(AudioChannel::Noise));
+    assert_eq!(levels.iter().all(|level| *level == levels[0]), locked);
+}
+
+#[rstest]
+#[case(HardwareModel::DMG, [0xFF, 0xFF, 0xFF, 0xFF])]
+#[case(HardwareModel::CGB, [0x12, 0x34, 0xFF, 0xDF])]
+#[case(HardwareModel::AGB, [0x12, 0x34, 0xFF, 0xDF])]
+fn test_undocumented_registers(#[case] model: HardwareModel, #[case] values: [u8; 4]) {
+    let source = "LD A, 0x12; LDH (0x72), A; LD A, 0x34; LDH (0x73), A; LD A, 0x56; LDH (0x74), A; \
+                  LD A, 0x5A; LDH (0x75), A; JR -2";
+    let mut game_boy = boot_with(GameBoy::new(model), source);
+    game_boy.run_until_frame();
+    assert_eq!([0xFF72, 0xFF73, 0xFF74, 0xFF75].map(|address| game_boy.peek(address)), values);
+}
+
+#[rstest]
+#[case(0x20, 0x0F)]
+#[case(0x60, 0x03)]
+fn test_pcm_amplitudes(#[case] volume: u8, #[case] pcm34: u8) {
+    let mut game_boy = boot_with(GameBoy::new(HardwareModel::CGB), &format!("{}; JR -2", play_wave(volume)));
+    game_boy.run_until_frame();
+    assert_eq!(game_boy.peek(0xFF76), 0x00);
+    assert_eq!(game_boy.peek(0xFF77), pcm34);
+}
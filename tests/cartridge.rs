@@ -0,0 +1,499 @@
+use lemon_gb_core::cartridge::checksum::{
+    compute_global_checksum, compute_header_checksum, fix_checksums, fix_checksums_in_place, read_global_checksum,
+    read_header_checksum, verify_global_checksum, verify_header_checksum,
+};
+use lemon_gb_core::cartridge::clock_source::ManualClock;
+use lemon_gb_core::cartridge::header::MapperType;
+use lemon_gb_core::cartridge::patch::{PatchError, apply_bps, apply_ips, apply_patch};
+use lemon_gb_core::cartridge::rom_builder::RomBuilder;
+use lemon_gb_core::cartridge::{Cartridge, CartridgeError};
+use lemon_gb_core::game_boy::GameBoy;
+use lemon_gb_core::hardware_model::HardwareModel;
+use lemon_gb_core::helpers::crc32::crc32;
+use rstest::rstest;
+
+/// Marks the first byte of every bank with its bank number
+fn banked_rom(cartridge_type: u8, banks: usize, ram_size_code: u8) -> Vec<u8> {
+    let mut builder = RomBuilder::new()
+        .title("BANKS")
+        .cartridge_type(cartridge_type)
+        .rom_banks(banks)
+        .ram_size_code(ram_size_code);
+    for bank in 1..banks {
+        builder = builder.code_at(bank * 0x4000, &[bank as u8]);
+    }
+    builder.build()
+}
+
+/// BPS variable-length integer, see the BPS specification
+fn bps_number(mut value: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let bits = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(0x80 | bits);
+            return bytes;
+        }
+        bytes.push(bits);
+        value -= 1;
+    }
+}
+
+/// Appends the source, target and patch checksums to the patch data
+fn finish_bps(mut patch: Vec<u8>, source: &[u8], target: &[u8]) -> Vec<u8> {
+    patch.extend(crc32(source).to_le_bytes());
+    patch.extend(crc32(target).to_le_bytes());
+    patch.extend(crc32(&patch).to_le_bytes());
+    patch
+}
+
+/// A BPS patch without metadata, turning the source into the target with the given actions
+fn bps_patch(source: &[u8], target: &[u8], actions: &[u8]) -> Vec<u8> {
+    let mut patch = b"BPS1".to_vec();
+    patch.extend(bps_number(source.len()));
+    patch.extend(bps_number(target.len()));
+    patch.extend(bps_number(0));
+    patch.extend_from_slice(actions);
+    finish_bps(patch, source, target)
+}
+
+/// An action, the lowest two bits are the command and the remaining bits the length minus one
+fn bps_action(command: usize, length: usize) -> Vec<u8> {
+    bps_number(((length - 1) << 2) | command)
+}
+
+#[test]
+fn test_header_parsing() {
+    let cartridge = Cartridge::load(banked_rom(0x1B, 8, 0x03)).unwrap();
+    let header = cartridge.get_header();
+    assert_eq!(header.get_title(), "BANKS");
+    assert_eq!(header.get_rom_size(), Some(8 * 0x4000));
+    assert_eq!(header.get_ram_size(), Some(0x8000));
+    assert!(header.is_header_checksum_valid());
+    assert_eq!(cartridge.get_cartridge_type().get_mapper(), MapperType::MBC5);
+    assert!(cartridge.has_battery());
+    assert_eq!(cartridge.get_ram().len(), 0x8000);
+}
+
+/// An empty ROM titled "LEMON", the title bytes add up to 379
+fn lemon_rom() -> Vec<u8> {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0134..0x0139].copy_from_slice(b"LEMON");
+    rom
+}
+
+#[test]
+fn test_checksums() {
+    let rom = lemon_rom();
+    // 0 - 379 - 25 wraps to 0x6C, the header checksum byte itself is part of the global checksum
+    assert_eq!(compute_header_checksum(&rom), Some(0x6C));
+    assert_eq!(compute_global_checksum(&rom), Some(379));
+    assert!(!verify_header_checksum(&rom));
+    assert!(!verify_global_checksum(&rom));
+    assert_eq!(compute_header_checksum(&rom[..0x014C]), None);
+    assert_eq!(compute_global_checksum(&rom[..0x014F]), None);
+
+    let fixed = fix_checksums(&rom).unwrap();
+    assert_eq!(rom, lemon_rom());
+    assert_eq!(read_header_checksum(&fixed), Some(0x6C));
+    assert_eq!(read_global_checksum(&fixed), Some(379 + 0x6C));
+    assert_eq!(&fixed[0x014E..0x0150], &[0x01, 0xE7]);
+    assert!(verify_header_checksum(&fixed));
+    assert!(verify_global_checksum(&fixed));
+    assert_eq!(fix_checksums(&rom[..0x014F]), None);
+
+    let mut in_place = rom.clone();
+    assert!(fix_checksums_in_place(&mut in_place));
+    assert_eq!(in_place, fixed);
+}
+
+#[test]
+fn test_checksums_detect_changed_bytes() {
+    let fixed = fix_checksums(&lemon_rom()).unwrap();
+
+    // Outside of the header only the global checksum changes
+    let mut rom = fixed.clone();
+    rom[0x4000] = 0x01;
+    assert!(verify_header_checksum(&rom));
+    assert!(!verify_global_checksum(&rom));
+
+    let mut rom = fixed.clone();
+    rom[0x0134] = b'M';
+    assert!(!verify_header_checksum(&rom));
+    assert!(!verify_global_checksum(&rom));
+}
+
+#[rstest]
+#[case(Vec::new(), CartridgeError::MissingHeader)]
+#[case(RomBuilder::new().cartridge_type(0x05).build(), CartridgeError::UnsupportedMapper(MapperType::MBC2))]
+#[case(RomBuilder::new().cartridge_type(0x04).build(), CartridgeError::UnknownCartridgeType(0x04))]
+#[case(RomBuilder::new().ram_size_code(0x06).build(), CartridgeError::UnknownRamSize(0x06))]
+fn test_load_errors(#[case] rom: Vec<u8>, #[case] error: CartridgeError) {
+    assert_eq!(Cartridge::load(rom), Err(error));
+}
+
+#[test]
+fn test_rom_size_mismatch() {
+    let mut rom = RomBuilder::new().build();
+    rom.resize(0xC000, 0x00);
+    assert_eq!(
+        Cartridge::load(rom),
+        Err(CartridgeError::RomSizeMismatch {
+            expected: 0x8000,
+            actual: 0xC000
+        })
+    );
+}
+
+#[test]
+fn test_trimmed_rom_is_padded() {
+    let mut rom = banked_rom(0x01, 8, 0x00);
+    rom.truncate(5 * 0x4000 + 0x100);
+    let mut cartridge = Cartridge::load(rom).unwrap();
+    assert_eq!(cartridge.get_rom().len(), 8 * 0x4000);
+    assert_eq!(cartridge.get_rom_padding(), 3 * 0x4000 - 0x100);
+    assert!(cartridge.is_rom_padded());
+
+    cartridge.write(0x2000, 5);
+    assert_eq!(cartridge.read(0x4000), 5);
+    assert_eq!(cartridge.read(0x4100), 0xFF);
+    // Banks beyond the header size wrap around
+    cartridge.write(0x2000, 13);
+    assert_eq!(cartridge.read(0x4000), 5);
+
+    let complete = Cartridge::load(banked_rom(0x01, 8, 0x00)).unwrap();
+    assert_eq!(complete.get_rom_padding(), 0);
+    assert!(!complete.is_rom_padded());
+}
+
+#[test]
+fn test_mbc1_rom_banking() {
+    let mut cartridge = Cartridge::load(banked_rom(0x01, 64, 0x00)).unwrap();
+    assert_eq!(cartridge.read(0x4000), 1);
+
+    cartridge.write(0x2000, 0x05);
+    assert_eq!(cartridge.read(0x4000), 5);
+
+    // Bank 0 is translated to bank 1
+    cartridge.write(0x2000, 0x00);
+    assert_eq!(cartridge.read(0x4000), 1);
+
+    cartridge.write(0x2000, 0x03);
+    cartridge.write(0x4000, 0x01);
+    assert_eq!(cartridge.read(0x4000), 0x23);
+    assert_eq!(cartridge.read(0x0000), 0xFF);
+
+    // Advanced banking mode also switches the first ROM area
+    cartridge.write(0x6000, 0x01);
+    assert_eq!(cartridge.read(0x0000), 0x20);
+}
+
+#[test]
+fn test_mbc1_ram_banking() {
+    let mut cartridge = Cartridge::load(banked_rom(0x03, 4, 0x03)).unwrap();
+    assert_eq!(cartridge.read(0xA000), 0xFF);
+    cartridge.write(0xA000, 0x42);
+    assert_eq!(cartridge.get_ram()[0], 0xFF);
+
+    cartridge.write(0x0000, 0x0A);
+    cartridge.write(0xA000, 0x11);
+    cartridge.write(0x6000, 0x01);
+    cartridge.write(0x4000, 0x02);
+    cartridge.write(0xA000, 0x22);
+    assert_eq!(cartridge.read(0xA000), 0x22);
+    assert_eq!(cartridge.get_ram()[0x0000], 0x11);
+    assert_eq!(cartridge.get_ram()[0x4000], 0x22);
+
+    cartridge.write(0x0000, 0x00);
+    assert_eq!(cartridge.read(0xA000), 0xFF);
+}
+
+#[rstest]
+#[case(0x02, 1, 0x2000)]
+#[case(0x03, 4, 0x2000)]
+#[case(0x01, 1, 0x0800)]
+fn test_cart_ram_views(#[case] ram_size_code: u8, #[case] banks: usize, #[case] bank_size: usize) {
+    let mut cartridge = Cartridge::load(banked_rom(0x03, 4, ram_size_code)).unwrap();
+    let mut ram = cartridge.get_cart_ram_mut();
+    assert!(ram.write(banks - 1, 0x10, 0x42));
+    assert!(!ram.write(banks, 0x10, 0x42));
+    assert!(!ram.write(0, bank_size, 0x42));
+    assert_eq!(ram.view().read(banks - 1, 0x10), Some(0x42));
+
+    let ram = cartridge.get_cart_ram();
+    assert_eq!(ram.get_bank_count(), banks);
+    assert_eq!(ram.get_bank(0).unwrap().len(), bank_size);
+    assert_eq!(ram.as_slice()[(banks - 1) * bank_size + 0x10], 0x42);
+
+    // Visible to the CPU once RAM is enabled and the bank is mapped
+    cartridge.write(0x0000, 0x0A);
+    cartridge.write(0x6000, 0x01);
+    cartridge.write(0x4000, (banks - 1) as u8);
+    assert_eq!(cartridge.read(0xA010), 0x42);
+}
+
+#[test]
+fn test_mbc5_rom_banking() {
+    let mut cartridge = Cartridge::load(banked_rom(0x19, 512, 0x00)).unwrap();
+    // Bank 0 can be mapped to the switchable area, 0x0100 holds the NOP of the entry point
+    cartridge.write(0x2000, 0x00);
+    assert_eq!(cartridge.read(0x4100), 0x00);
+    assert_eq!(cartridge.read(0x5000), 0xFF);
+
+    cartridge.write(0x2000, 0x2A);
+    cartridge.write(0x3000, 0x01);
+    assert_eq!(cartridge.read(0x4000), 0x2A);
+    assert_eq!(cartridge.get_rom()[0x12A * 0x4000], 0x2A);
+}
+
+#[test]
+fn test_mbc3_rtc() {
+    let clock = ManualClock::new(1_000);
+    let rom = banked_rom(0x10, 4, 0x03);
+    let mut cartridge = Cartridge::load_with_clock_source(rom, Box::new(clock.clone())).unwrap();
+    cartridge.write(0x0000, 0x0A);
+
+    clock.advance_seconds(2 * 86_400 + 3 * 3_600 + 4 * 60 + 5);
+    cartridge.write(0x6000, 0x00);
+    cartridge.write(0x6000, 0x01);
+
+    let registers: Vec<u8> = (0x08..=0x0C)
+        .map(|register| {
+            cartridge.write(0x4000, register);
+            cartridge.read(0xA000)
+        })
+        .collect();
+    assert_eq!(registers, [5, 4, 3, 2, 0]);
+
+    // The latched values don't change until the next latch
+    clock.advance_seconds(10);
+    cartridge.write(0x4000, 0x08);
+    assert_eq!(cartridge.read(0xA000), 5);
+    cartridge.write(0x6000, 0x00);
+    cartridge.write(0x6000, 0x01);
+    assert_eq!(cartridge.read(0xA000), 15);
+
+    // Halting stops the clock
+    cartridge.write(0x4000, 0x0C);
+    cartridge.write(0xA000, 0x40);
+    clock.advance_seconds(100);
+    cartridge.write(0x6000, 0x00);
+    cartridge.write(0x6000, 0x01);
+    cartridge.write(0x4000, 0x08);
+    assert_eq!(cartridge.read(0xA000), 15);
+
+    // RAM banks are still accessible
+    cartridge.write(0x4000, 0x01);
+    cartridge.write(0xA000, 0x33);
+    assert_eq!(cartridge.get_ram()[0x2000], 0x33);
+}
+
+#[test]
+fn test_mbc3_day_carry() {
+    let clock = ManualClock::new(0);
+    let rom = banked_rom(0x0F, 4, 0x00);
+    let mut cartridge = Cartridge::load_with_clock_source(rom, Box::new(clock.clone())).unwrap();
+    cartridge.write(0x0000, 0x0A);
+
+    clock.advance_seconds(513 * 86_400);
+    cartridge.write(0x6000, 0x00);
+    cartridge.write(0x6000, 0x01);
+    cartridge.write(0x4000, 0x0B);
+    assert_eq!(cartridge.read(0xA000), 1);
+    cartridge.write(0x4000, 0x0C);
+    assert_eq!(cartridge.read(0xA000), 0x80);
+}
+
+#[test]
+fn test_game_boy_runs_cartridge_code() {
+    // LD A,0x0A; LD (0x0000),A; LD A,0x99; LD (0xA000),A; HALT
+    let rom = RomBuilder::new()
+        .cartridge_type(0x03)
+        .rom_banks(4)
+        .ram_size_code(0x02)
+        .code(&[0x3E, 0x0A, 0xEA, 0x00, 0x00, 0x3E, 0x99, 0xEA, 0x00, 0xA0, 0x76])
+        .build();
+
+    let mut game_boy = GameBoy::new(HardwareModel::DMG);
+    game_boy.insert_cartridge(rom).unwrap();
+    for _ in 0..6 {
+        game_boy.step();
+    }
+
+    let save = game_boy.get_save_data().unwrap();
+    assert_eq!(save[0], 0x99);
+
+    let save = save.to_vec();
+    let mut other = GameBoy::new(HardwareModel::DMG);
+    other.insert_cartridge(RomBuilder::new().cartridge_type(0x03).rom_banks(4).ram_size_code(0x02).build()).unwrap();
+    other.load_save_data(&save).unwrap();
+    assert_eq!(other.get_save_data().unwrap()[0], 0x99);
+    assert_eq!(
+        other.load_save_data(&[0; 3]),
+        Err(CartridgeError::SaveSizeMismatch {
+            expected: 0x2000,
+            actual: 3
+        })
+    );
+}
+
+#[test]
+fn test_mbc1_snapshot() {
+    let mut cartridge = Cartridge::load(banked_rom(0x03, 64, 0x03)).unwrap();
+    cartridge.write(0x0000, 0x0A);
+    cartridge.write(0x2000, 0x05);
+    cartridge.write(0x4000, 0x01);
+    cartridge.write(0x6000, 0x01);
+
+    let snapshot = cartridge.get_mbc_snapshot();
+    assert_eq!(snapshot.mapper, MapperType::MBC1);
+    assert_eq!(snapshot.rom_bank_low, 0x20);
+    assert_eq!(snapshot.rom_bank_high, 0x25);
+    assert_eq!(snapshot.ram_bank, Some(1));
+    assert!(snapshot.ram_enabled);
+    assert!(snapshot.advanced_banking);
+}
+
+#[test]
+fn test_mbc3_snapshot() {
+    let clock = ManualClock::new(0);
+    let rom = banked_rom(0x10, 8, 0x03);
+    let mut cartridge = Cartridge::load_with_clock_source(rom, Box::new(clock.clone())).unwrap();
+    // Bank numbers beyond the ROM size wrap around
+    cartridge.write(0x2000, 0x0B);
+    clock.advance_seconds(61);
+    cartridge.write(0x6000, 0x00);
+    cartridge.write(0x6000, 0x01);
+    cartridge.write(0x4000, 0x09);
+
+    let snapshot = cartridge.get_mbc_snapshot();
+    assert_eq!(snapshot.rom_bank_high, 3);
+    assert_eq!(snapshot.ram_bank, None);
+    assert!(!snapshot.ram_enabled);
+    assert_eq!(snapshot.rtc_register, Some(0x01));
+    assert_eq!(snapshot.rtc_latched, Some([1, 1, 0, 0, 0]));
+}
+
+/// An IPS record, a run of the value if the data is a single byte repeated
+fn ips_record(offset: u32, data: &[u8]) -> Vec<u8> {
+    let mut record = offset.to_be_bytes()[1..].to_vec();
+    if data.len() > 1 && data.iter().all(|&byte| byte == data[0]) {
+        record.extend([0, 0]);
+        record.extend((data.len() as u16).to_be_bytes());
+        record.push(data[0]);
+    } else {
+        record.extend((data.len() as u16).to_be_bytes());
+        record.extend_from_slice(data);
+    }
+    record
+}
+
+#[rstest]
+#[case::record(&[ips_record(2, &[0xAA, 0xBB])], None, vec![0, 0, 0xAA, 0xBB, 0, 0])]
+#[case::run(&[ips_record(1, &[0x55; 3])], None, vec![0, 0x55, 0x55, 0x55, 0, 0])]
+#[case::growing_record(&[ips_record(7, &[0xAA, 0xBB])], None, vec![0, 0, 0, 0, 0, 0, 0, 0xAA, 0xBB])]
+#[case::truncation(&[ips_record(0, &[0xAA, 0xBB])], Some(3), vec![0xAA, 0xBB, 0])]
+fn test_ips_patch(#[case] records: &[Vec<u8>], #[case] truncated_size: Option<u32>, #[case] expected: Vec<u8>) {
+    let mut patch = [b"PATCH".as_slice(), &records.concat(), b"EOF"].concat();
+    if let Some(size) = truncated_size {
+        patch.extend(&size.to_be_bytes()[1..]);
+    }
+    assert_eq!(apply_ips(&[0; 6], &patch), Ok(expected.clone()));
+    assert_eq!(apply_patch(&[0; 6], &patch), Ok(expected));
+}
+
+#[test]
+fn test_truncated_ips_patch() {
+    let patch = [b"PATCH".as_slice(), &ips_record(0, &[0xAA, 0xBB])].concat();
+    assert_eq!(apply_ips(&[0; 6], &patch[..patch.len() - 1]), Err(PatchError::UnexpectedEnd));
+    // The EOF marker is missing
+    assert_eq!(apply_ips(&[0; 6], &patch), Err(PatchError::UnexpectedEnd));
+}
+
+#[rstest]
+#[case::source_read(vec![1, 2, 3, 4], [bps_action(0, 4)].concat())]
+#[case::target_read(vec![1, 2, 8, 9], [bps_action(0, 2), bps_action(1, 2), vec![8, 9]].concat())]
+// Relative offsets are stored with the sign in the lowest bit
+#[case::source_copy(
+    vec![3, 4, 1, 2],
+    [bps_action(2, 2), bps_number(2 << 1), bps_action(2, 2), bps_number((4 << 1) | 1)].concat()
+)]
+#[case::target_copy(vec![9, 9, 9, 9, 9], [bps_action(1, 1), vec![9], bps_action(3, 4), bps_number(0)].concat())]
+fn test_bps_patch(#[case] target: Vec<u8>, #[case] actions: Vec<u8>) {
+    let source = [1, 2, 3, 4];
+    let patch = bps_patch(&source, &target, &actions);
+    assert_eq!(apply_bps(&source, &patch), Ok(target.clone()));
+    assert_eq!(apply_patch(&source, &patch), Ok(target));
+}
+
+#[test]
+fn test_bps_checksum_mismatches() {
+    let source = [1, 2, 3, 4];
+    let patch = bps_patch(&source, &source, &bps_action(0, 4));
+    assert_eq!(
+        apply_bps(&[1, 2, 3, 5], &patch),
+        Err(PatchError::SourceChecksumMismatch {
+            expected: crc32(&source),
+            actual: crc32(&[1, 2, 3, 5])
+        })
+    );
+
+    // The actions produce the source, but the patch claims a different target
+    let patch = bps_patch(&source, &[1, 2, 3, 5], &bps_action(0, 4));
+    assert_eq!(
+        apply_bps(&source, &patch),
+        Err(PatchError::TargetChecksumMismatch {
+            expected: crc32(&[1, 2, 3, 5]),
+            actual: crc32(&source)
+        })
+    );
+
+    let mut patch = bps_patch(&source, &source, &bps_action(0, 4));
+    let expected = u32::from_le_bytes(patch[patch.len() - 4..].try_into().unwrap());
+    patch[4] ^= 0xFF;
+    assert_eq!(
+        apply_bps(&source, &patch),
+        Err(PatchError::PatchChecksumMismatch {
+            expected,
+            actual: crc32(&patch[..patch.len() - 4])
+        })
+    );
+}
+
+#[test]
+fn test_load_patched() {
+    let rom = RomBuilder::new().title("BANKS").build();
+    let patch = [b"PATCH".as_slice(), &ips_record(0x0134, b"LEMON"), b"EOF"].concat();
+    let cartridge = Cartridge::load_patched(&rom, &patch).unwrap();
+    assert_eq!(cartridge.get_header().get_title(), "LEMON");
+
+    let mut game_boy = GameBoy::new(HardwareModel::DMG);
+    assert_eq!(
+        game_boy.insert_patched_cartridge(&rom, b"NOT A PATCH"),
+        Err(CartridgeError::Patch(PatchError::UnknownFormat))
+    );
+    game_boy.insert_patched_cartridge(&rom, &patch).unwrap();
+    assert_eq!(game_boy.peek(0x0134), b'L');
+}
+
+#[rstest]
+#[case::unterminated_number(
+    finish_bps([b"BPS1".as_slice(), &[0x00; 12]].concat(), &[1, 2, 3, 4], &[]),
+    PatchError::InvalidOffset
+)]
+#[case::oversized_target_copy(
+    bps_patch(
+        &[1, 2, 3, 4],
+        &[1, 2, 3, 4],
+        &[bps_action(0, 1), bps_action(3, usize::MAX >> 3), bps_number(0)].concat()
+    ),
+    PatchError::TargetSizeMismatch { expected: 4, actual: (usize::MAX >> 3) + 1 }
+)]
+#[case::source_copy_out_of_range(
+    bps_patch(&[1, 2, 3, 4], &[1, 2, 3, 4], &[bps_action(2, 4), bps_number(usize::MAX >> 2 << 1)].concat()),
+    PatchError::InvalidOffset
+)]
+fn test_malformed_patches(#[case] patch: Vec<u8>, #[case] error: PatchError) {
+    assert_eq!(apply_patch(&[1, 2, 3, 4], &patch), Err(error));
+}
@@ -0,0 +1,22 @@
+#![allow(dead_code)]
+
+use lemon_gb_core::cartridge::rom_builder::RomBuilder;
+use lemon_gb_core::cpu::assembler::assemble;
+use lemon_gb_core::game_boy::GameBoy;
+use lemon_gb_core::hardware_model::HardwareModel;
+
+/// A ROM that runs the assembled source from the entry point
+pub fn rom(source: &str) -> Vec<u8> {
+    RomBuilder::new().code(&assemble(source).unwrap()).build()
+}
+
+/// A DMG running the assembled source
+pub fn boot(source: &str) -> GameBoy {
+    boot_with(GameBoy::new(HardwareModel::DMG), source)
+}
+
+/// Inserts a ROM running the assembled source into the given Game Boy
+pub fn boot_with(mut game_boy: GameBoy, source: &str) -> GameBoy {
+    game_boy.insert_cartridge(rom(source)).unwrap();
+    game_boy
+}
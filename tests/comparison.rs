@@ -0,0 +1,63 @@
+mod common;
+
+use common::rom;
+use lemon_gb_core::cartridge::rom_builder::RomBuilder;
+use lemon_gb_core::circuitry::ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use lemon_gb_core::circuitry::ram_initialization::RamInitialization;
+use lemon_gb_core::cpu::assembler::assemble;
+use lemon_gb_core::game_boy::GameBoy;
+use lemon_gb_core::game_boy::comparison::{FrameComparison, FrameDivergence};
+use lemon_gb_core::hardware_model::revision::Revision;
+
+/// Uses the power-on contents of WRAM as the background palette
+const PROGRAM: &str = "LD A, (0xC000); LDH (0x47), A; JR -2";
+
+#[test]
+fn test_equivalent_configurations() {
+    let a = GameBoy::builder();
+    let b = GameBoy::builder().revision(Revision::DMG);
+    let mut comparison = FrameComparison::new(a, b, &rom(PROGRAM)).unwrap();
+    assert_eq!(comparison.run(5), None);
+    assert_eq!(comparison.get_frames(), 5);
+    assert_eq!(comparison.get_a().get_frame_count(), comparison.get_b().get_frame_count());
+}
+
+#[test]
+fn test_first_divergence() {
+    let a = GameBoy::builder();
+    let b = GameBoy::builder().ram_initialization(RamInitialization::Filled(0xFF));
+    let mut comparison = FrameComparison::new(a, b, &rom(PROGRAM)).unwrap();
+    let divergence = comparison.run(5).unwrap();
+    assert_eq!(
+        divergence,
+        FrameDivergence {
+            frame: 1,
+            differing_pixels: SCREEN_WIDTH * SCREEN_HEIGHT,
+            first_pixel: (0, 0),
+        }
+    );
+    assert_eq!(comparison.get_frames(), 1);
+}
+
+#[test]
+fn test_rtc_uses_emulated_time() {
+    // Latches the RTC of an MBC3 cartridge in a loop and shows the seconds as the background palette
+    let rom = RomBuilder::new()
+        .cartridge_type(0x10)
+        .ram_size_code(0x03)
+        .code(
+            &assemble(
+                "LD A, 0x0A; LD (0x0000), A; LD A, 0x08; LD (0x4000), A; \
+                 XOR A; LD (0x6000), A; INC A; LD (0x6000), A; LD A, (0xA000); LDH (0x47), A; JR -15",
+            )
+            .unwrap(),
+        )
+        .build();
+    let mut comparison = FrameComparison::new(GameBoy::builder(), GameBoy::builder(), &rom).unwrap();
+    assert_eq!(comparison.run(70), None);
+
+    // About 1.17 seconds of emulated time passed, independent of how long running took
+    for game_boy in [comparison.get_a(), comparison.get_b()] {
+        assert_eq!(game_boy.get_cartridge().unwrap().get_rtc().unwrap().get_latched_registers()[0], 1);
+    }
+}
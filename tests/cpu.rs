@@ -0,0 +1,485 @@
+use lemon_gb_core::circuitry::interface::CircuitryInterface;
+use lemon_gb_core::circuitry::interrupts::Interrupt;
+use lemon_gb_core::cpu::CPU;
+use lemon_gb_core::cpu::assembler::assemble_at;
+use lemon_gb_core::cpu::opcodes::{CB_PREFIX, get_cb_opcode_info, get_opcode_info};
+use lemon_gb_core::cpu::snapshot::CpuSnapshot;
+use lemon_gb_core::hardware_model::HardwareModel;
+use rstest::rstest;
+
+const ENTRY_POINT: u16 = 0x0100;
+const MAX_STEPS: usize = 1000;
+
+/// Flat 64 KiB of memory with IE and IF kept separately
+struct TestBus {
+    memory: Vec<u8>,
+    ticks: usize,
+    interrupt_enable: u8,
+    interrupt_flag: u8,
+}
+
+impl TestBus {
+    fn new() -> Self {
+        Self {
+            memory: vec![0; 0x10000],
+            ticks: 0,
+            interrupt_enable: 0,
+            interrupt_flag: 0,
+        }
+    }
+}
+
+impl CircuitryInterface for TestBus {
+    fn tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    fn read(&mut self, address: u16) -> u8 {
+        self.memory[address as usize]
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.memory[address as usize] = value;
+    }
+
+    fn get_interrupt_enable(&self) -> u8 {
+        self.interrupt_enable
+    }
+
+    fn get_interrupt_flag(&self) -> u8 {
+        self.interrupt_flag
+    }
+
+    fn acknowledge_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt_flag &= !interrupt.get_bit();
+    }
+}
+
+struct Harness {
+    cpu: CPU,
+    bus: TestBus,
+}
+
+impl Harness {
+    /// Places the program at the entry point, where the CPU starts after the boot ROM
+    fn new(source: &str) -> Self {
+        let mut bus = TestBus::new();
+        assemble_at(ENTRY_POINT, source).unwrap().write_into(&mut bus.memory);
+        Self {
+            cpu: CPU::initialize(HardwareModel::DMG0),
+            bus,
+        }
+    }
+
+    /// Runs until PC leaves the program at its end
+    fn run(source: &str) -> Self {
+        let end = ENTRY_POINT + assemble_at(ENTRY_POINT, source).unwrap().get_bytes().len() as u16;
+        let mut harness = Self::new(source);
+        for _ in 0..MAX_STEPS {
+            if harness.snapshot().pc == end {
+                return harness;
+            }
+            harness.step();
+        }
+        panic!("program did not finish: {source}");
+    }
+
+    fn step(&mut self) -> u8 {
+        self.cpu.step(&mut self.bus)
+    }
+
+    fn snapshot(&self) -> CpuSnapshot {
+        self.cpu.snapshot(|address| self.bus.memory[address as usize])
+    }
+
+    fn flags(&self) -> String {
+        let flags = self.snapshot().flags;
+        [
+            (flags.zero, 'Z'),
+            (flags.subtract, 'N'),
+            (flags.half_carry, 'H'),
+            (flags.carry, 'C'),
+        ]
+        .iter()
+        .map(|(set, name)| if *set { *name } else { '-' })
+        .collect()
+    }
+}
+
+#[test]
+fn test_8bit_loads() {
+    let harness = Harness::run("LD B, 0x12; LD C, B; LD HL, 0xC000; LD (HL), C; LD A, (HL); LD (0xC001), A; LD E, (HL)");
+    let snapshot = harness.snapshot();
+    assert_eq!(snapshot.a, 0x12);
+    assert_eq!(snapshot.e, 0x12);
+    assert_eq!(harness.bus.memory[0xC000], 0x12);
+    assert_eq!(harness.bus.memory[0xC001], 0x12);
+}
+
+#[test]
+fn test_indirect_loads_with_increment_and_decrement() {
+    let harness = Harness::run("LD HL, 0xC000; LD A, 0x11; LD (HL+), A; LD A, 0x22; LD (HL-), A; LD A, (HL+); LD B, A");
+    let snapshot = harness.snapshot();
+    assert_eq!(harness.bus.memory[0xC000], 0x11);
+    assert_eq!(harness.bus.memory[0xC001], 0x22);
+    assert_eq!(snapshot.b, 0x11);
+    assert_eq!(snapshot.get_hl(), 0xC001);
+}
+
+#[test]
+fn test_high_ram_loads() {
+    let harness = Harness::run("LD A, 0x42; LDH (0x80), A; LD C, 0x81; LD (C), A; LDH A, (0x80); LD A, (C)");
+    assert_eq!(harness.bus.memory[0xFF80], 0x42);
+    assert_eq!(harness.bus.memory[0xFF81], 0x42);
+    assert_eq!(harness.snapshot().a, 0x42);
+}
+
+#[test]
+fn test_16bit_loads() {
+    let harness = Harness::run("LD BC, 0x1234; LD DE, 0x5678; LD SP, 0xDFF0; LD (0xC000), SP; LD HL, 0xABCD; LD SP, HL");
+    let snapshot = harness.snapshot();
+    assert_eq!(snapshot.get_bc(), 0x1234);
+    assert_eq!(snapshot.get_de(), 0x5678);
+    assert_eq!(snapshot.sp, 0xABCD);
+    assert_eq!(&harness.bus.memory[0xC000..0xC002], &[0xF0, 0xDF]);
+}
+
+#[rstest]
+#[case("LD A, 0x3A; ADD A, 0xC6", 0x00, "Z-HC")]
+#[case("LD A, 0x0F; ADD A, 0x01", 0x10, "--H-")]
+#[case("SCF; LD A, 0xE1; ADC A, 0x0F", 0xF1, "--H-")]
+#[case("SCF; LD A, 0xFF; ADC A, 0x00", 0x00, "Z-HC")]
+#[case("LD A, 0x3E; SUB 0x3E", 0x00, "ZN--")]
+#[case("LD A, 0x3E; SUB 0x0F", 0x2F, "-NH-")]
+#[case("LD A, 0x00; SUB 0x01", 0xFF, "-NHC")]
+#[case("SCF; LD A, 0x3B; SBC A, 0x2A", 0x10, "-N--")]
+#[case("SCF; LD A, 0x3B; SBC A, 0x4F", 0xEB, "-NHC")]
+#[case("LD A, 0x5A; AND 0x3F", 0x1A, "--H-")]
+#[case("LD A, 0x5A; AND 0x00", 0x00, "Z-H-")]
+#[case("LD A, 0xFF; XOR 0xFF", 0x00, "Z---")]
+#[case("LD A, 0x5A; OR 0x0F", 0x5F, "----")]
+#[case("LD A, 0x3C; CP 0x40", 0x3C, "-N-C")]
+#[case("LD A, 0x3C; CP 0x3C", 0x3C, "ZN--")]
+#[case("LD A, 0x3C; LD B, 0x2F; CP B", 0x3C, "-NH-")]
+#[case("LD HL, 0xC000; LD (HL), 0x01; LD A, 0xFF; ADD A, (HL)", 0x00, "Z-HC")]
+fn test_8bit_arithmetic_and_logic(#[case] source: &str, #[case] a: u8, #[case] flags: &str) {
+    let harness = Harness::run(source);
+    assert_eq!(harness.snapshot().a, a);
+    assert_eq!(harness.flags(), flags);
+}
+
+#[rstest]
+#[case("SCF; LD B, 0xFF; INC B", 0x00, "Z-HC")]
+#[case("LD B, 0x0F; INC B", 0x10, "--H-")]
+#[case("LD B, 0x01; DEC B", 0x00, "ZN--")]
+#[case("SCF; LD B, 0x10; DEC B", 0x0F, "-NHC")]
+fn test_increment_and_decrement(#[case] source: &str, #[case] b: u8, #[case] flags: &str) {
+    let harness = Harness::run(source);
+    assert_eq!(harness.snapshot().b, b);
+    assert_eq!(harness.flags(), flags);
+}
+
+#[test]
+fn test_increment_indirect() {
+    let harness = Harness::run("LD HL, 0xC000; LD (HL), 0x7F; INC (HL); INC (HL); DEC (HL)");
+    assert_eq!(harness.bus.memory[0xC000], 0x80);
+}
+
+#[rstest]
+#[case("LD HL, 0x8A23; LD BC, 0x0605; ADD HL, BC", 0x9028, "--H-")]
+#[case("LD HL, 0x8A23; ADD HL, HL", 0x1446, "--HC")]
+#[case("XOR A; LD HL, 0xFFFF; LD DE, 0x0001; ADD HL, DE", 0x0000, "Z-HC")]
+#[case("LD SP, 0xFFF8; LD HL, SP+2", 0xFFFA, "----")]
+#[case("LD SP, 0x00FF; LD HL, SP+1", 0x0100, "--HC")]
+#[case("LD SP, 0x0000; LD HL, SP-1", 0xFFFF, "----")]
+fn test_16bit_arithmetic(#[case] source: &str, #[case] hl: u16, #[case] flags: &str) {
+    let harness = Harness::run(source);
+    assert_eq!(harness.snapshot().get_hl(), hl);
+    assert_eq!(harness.flags(), flags);
+}
+
+#[test]
+fn test_16bit_increment_and_decrement() {
+    let harness = Harness::run("LD BC, 0xFFFF; INC BC; LD DE, 0x0000; DEC DE; LD SP, 0xFFF8; ADD SP, -8; INC SP");
+    let snapshot = harness.snapshot();
+    assert_eq!(snapshot.get_bc(), 0x0000);
+    assert_eq!(snapshot.get_de(), 0xFFFF);
+    assert_eq!(snapshot.sp, 0xFFF1);
+}
+
+#[rstest]
+#[case("LD A, 0x85; RLCA", 0x0B, "---C")]
+#[case("LD A, 0x3B; RRCA", 0x9D, "---C")]
+#[case("LD A, 0x95; RLA", 0x2A, "---C")]
+#[case("SCF; LD A, 0x81; RRA", 0xC0, "---C")]
+#[case("XOR A; RLCA", 0x00, "----")]
+#[case("LD A, 0x80; RLC A", 0x01, "---C")]
+#[case("LD A, 0x01; RRC A", 0x80, "---C")]
+#[case("LD A, 0x80; RL A", 0x00, "Z--C")]
+#[case("SCF; LD A, 0x00; RR A", 0x80, "----")]
+#[case("LD A, 0xFF; SLA A", 0xFE, "---C")]
+#[case("LD A, 0x8A; SRA A", 0xC5, "----")]
+#[case("LD A, 0x01; SRL A", 0x00, "Z--C")]
+#[case("SCF; LD A, 0xF0; SWAP A", 0x0F, "----")]
+fn test_rotates_and_shifts(#[case] source: &str, #[case] a: u8, #[case] flags: &str) {
+    let harness = Harness::run(source);
+    assert_eq!(harness.snapshot().a, a);
+    assert_eq!(harness.flags(), flags);
+}
+
+#[rstest]
+#[case("LD B, 0x80; BIT 7, B", "--H-")]
+#[case("SCF; LD B, 0x7F; BIT 7, B", "Z-HC")]
+#[case("LD HL, 0xC000; LD (HL), 0x01; BIT 0, (HL)", "--H-")]
+fn test_bit(#[case] source: &str, #[case] flags: &str) {
+    assert_eq!(Harness::run(source).flags(), flags);
+}
+
+#[test]
+fn test_set_and_reset() {
+    let harness = Harness::run("LD B, 0x00; SET 3, B; SET 7, B; LD HL, 0xC000; LD (HL), 0xFF; RES 0, (HL); RES 7, (HL)");
+    assert_eq!(harness.snapshot().b, 0x88);
+    assert_eq!(harness.bus.memory[0xC000], 0x7E);
+}
+
+#[rstest]
+#[case("LD A, 0x45; ADD A, 0x38; DAA", 0x83, "----")]
+#[case("LD A, 0x83; SUB 0x38; DAA", 0x45, "-N--")]
+#[case("LD A, 0x99; ADD A, 0x01; DAA", 0x00, "Z--C")]
+#[case("LD A, 0x00; SUB 0x01; DAA", 0x99, "-N-C")]
+fn test_decimal_adjust(#[case] source: &str, #[case] a: u8, #[case] flags: &str) {
+    let harness = Harness::run(source);
+    assert_eq!(harness.snapshot().a, a);
+    assert_eq!(harness.flags(), flags);
+}
+
+#[rstest]
+#[case("LD A, 0x35; CPL", 0xCA, "-NH-")]
+#[case("SCF; CCF", 0x01, "----")]
+#[case("XOR A; SCF", 0x00, "Z--C")]
+fn test_accumulator_and_carry_operations(#[case] source: &str, #[case] a: u8, #[case] flags: &str) {
+    let harness = Harness::run(source);
+    assert_eq!(harness.snapshot().a, a);
+    assert_eq!(harness.flags(), flags);
+}
+
+#[test]
+fn test_stack_operations() {
+    let harness = Harness::run("LD SP, 0xD000; LD BC, 0x12FF; PUSH BC; POP AF; PUSH AF; POP DE");
+    let snapshot = harness.snapshot();
+    // The lower nibble of F is not writable
+    assert_eq!(snapshot.get_af(), 0x12F0);
+    assert_eq!(snapshot.get_de(), 0x12F0);
+    assert_eq!(snapshot.sp, 0xD000);
+    assert_eq!(&harness.bus.memory[0xCFFE..0xD000], &[0xF0, 0x12]);
+}
+
+#[test]
+fn test_call_and_return() {
+    let mut harness = Harness::new("LD SP, 0xD000; CALL 0x0200; NOP");
+    assemble_at(0x0200, "LD B, 0x42; RET").unwrap().write_into(&mut harness.bus.memory);
+
+    let cycles: Vec<u8> = (0..4).map(|_| harness.step()).collect();
+    assert_eq!(cycles, vec![3, 6, 2, 4]);
+    let snapshot = harness.snapshot();
+    assert_eq!(snapshot.pc, 0x0106);
+    assert_eq!(snapshot.b, 0x42);
+    assert_eq!(snapshot.sp, 0xD000);
+}
+
+#[rstest]
+#[case("JP 0x0200", 0x0200, 4)]
+#[case("JR 5", 0x0107, 3)]
+#[case("JR -2", 0x0100, 3)]
+#[case("RST 0x28", 0x0028, 4)]
+#[case("LD HL, 0x1234; JP HL", 0x1234, 1)]
+fn test_jumps(#[case] source: &str, #[case] pc: u16, #[case] last_cycles: u8) {
+    let mut harness = Harness::new(source);
+    let instructions = source.split(';').count();
+    let cycles: Vec<u8> = (0..instructions).map(|_| harness.step()).collect();
+    assert_eq!(harness.snapshot().pc, pc);
+    assert_eq!(*cycles.last().unwrap(), last_cycles);
+}
+
+#[rstest]
+#[case("XOR A; JR Z, 4", 0x0107, 3)]
+#[case("XOR A; JR NZ, 4", 0x0103, 2)]
+#[case("XOR A; JP NZ, 0x0200", 0x0104, 3)]
+#[case("SCF; JP C, 0x0200", 0x0200, 4)]
+#[case("SCF; CALL NC, 0x0200", 0x0104, 3)]
+#[case("SCF; CALL C, 0x0200", 0x0200, 6)]
+#[case("SCF; RET NC", 0x0102, 2)]
+fn test_conditional_control_flow(#[case] source: &str, #[case] pc: u16, #[case] last_cycles: u8) {
+    let mut harness = Harness::new(source);
+    harness.step();
+    let cycles = harness.step();
+    assert_eq!(harness.snapshot().pc, pc);
+    assert_eq!(cycles, last_cycles);
+}
+
+/// Every instruction has to take exactly the cycles listed in the opcode table
+#[test]
+fn test_cycles_match_opcode_table() {
+    // The DMG0 initial flags are all reset, so NZ and NC are taken while Z and C are not
+    let taken = |mnemonic: &str| !(mnemonic.contains(" Z") || mnemonic.contains(" C,") || mnemonic == "RET C");
+
+    for opcode in 0..=0xFFu8 {
+        let Some(info) = get_opcode_info(opcode) else {
+            continue;
+        };
+        if matches!(opcode, 0x10 | 0x76 | CB_PREFIX) {
+            continue;
+        }
+
+        let mut harness = Harness::new("");
+        harness.bus.memory[ENTRY_POINT as usize] = opcode;
+        let expected = if taken(info.get_mnemonic()) {
+            info.get_cycles_taken()
+        } else {
+            info.get_cycles()
+        };
+        assert_eq!(harness.step(), expected, "{:#04X} {}", opcode, info.get_mnemonic());
+        assert_eq!(harness.bus.ticks, expected as usize, "{:#04X} {}", opcode, info.get_mnemonic());
+    }
+
+    for opcode in 0..=0xFFu8 {
+        let info = get_cb_opcode_info(opcode);
+        let mut harness = Harness::new("");
+        harness.bus.memory[ENTRY_POINT as usize] = CB_PREFIX;
+        harness.bus.memory[ENTRY_POINT as usize + 1] = opcode;
+        assert_eq!(harness.step(), info.get_cycles(), "CB {:#04X} {}", opcode, info.get_mnemonic());
+    }
+}
+
+#[test]
+fn test_interrupt_is_serviced_one_instruction_after_ei() {
+    let mut harness = Harness::new("LD SP, 0xD000; EI; NOP; NOP");
+    harness.bus.interrupt_enable = Interrupt::Timer.get_bit();
+    harness.bus.interrupt_flag = Interrupt::Timer.get_bit();
+
+    harness.step();
+    harness.step();
+    assert!(!harness.snapshot().ime);
+    assert!(harness.snapshot().ime_scheduled);
+
+    // The instruction after EI still executes
+    harness.step();
+    assert_eq!(harness.snapshot().pc, 0x0105);
+
+    assert_eq!(harness.step(), 5);
+    let snapshot = harness.snapshot();
+    assert_eq!(snapshot.pc, Interrupt::Timer.get_vector());
+    assert!(!snapshot.ime);
+    assert_eq!(harness.bus.interrupt_flag, 0);
+    assert_eq!(&harness.bus.memory[0xCFFE..0xD000], &[0x05, 0x01]);
+}
+
+#[test]
+fn test_interrupt_priority_and_reti() {
+    let mut harness = Harness::new("LD SP, 0xD000; EI; NOP");
+    harness.bus.memory[0x0040] = 0xD9; // RETI
+    harness.bus.interrupt_enable = 0x1F;
+    harness.bus.interrupt_flag = Interrupt::Joypad.get_bit() | Interrupt::VBlank.get_bit();
+
+    for _ in 0..4 {
+        harness.step();
+    }
+    assert_eq!(harness.snapshot().pc, Interrupt::VBlank.get_vector());
+    assert_eq!(harness.bus.interrupt_flag, Interrupt::Joypad.get_bit());
+
+    // RETI enables IME immediately, so the next interrupt follows right away
+    harness.step();
+    assert_eq!(harness.snapshot().pc, 0x0105);
+    harness.step();
+    assert_eq!(harness.snapshot().pc, Interrupt::Joypad.get_vector());
+}
+
+#[test]
+fn test_di_cancels_ei() {
+    let mut harness = Harness::new("EI; DI; NOP; NOP");
+    harness.bus.interrupt_enable = 0x1F;
+    harness.bus.interrupt_flag = 0x1F;
+    for _ in 0..4 {
+        harness.step();
+    }
+    assert_eq!(harness.snapshot().pc, 0x0104);
+}
+
+#[test]
+fn test_halt_waits_for_pending_interrupt() {
+    let mut harness = Harness::new("HALT; LD B, 0x42");
+    harness.bus.interrupt_enable = Interrupt::Timer.get_bit();
+
+    harness.step();
+    assert!(harness.snapshot().halted);
+    for _ in 0..10 {
+        assert_eq!(harness.step(), 1);
+    }
+    assert_eq!(harness.snapshot().pc, 0x0101);
+
+    // With IME disabled the CPU continues after HALT without servicing the interrupt
+    harness.bus.interrupt_flag = Interrupt::Timer.get_bit();
+    harness.step();
+    let snapshot = harness.snapshot();
+    assert!(!snapshot.halted);
+    assert_eq!(snapshot.b, 0x42);
+    assert_eq!(harness.bus.interrupt_flag, Interrupt::Timer.get_bit());
+}
+
+#[rstest]
+#[case::ime_enabled("EI; HALT; LD B, 0x42", 6, 0x0040)]
+#[case::ime_disabled("DI; HALT; LD B, 0x42", 2, 0x0104)]
+fn test_halt_wake_timing(#[case] source: &str, #[case] expected_cycles: u8, #[case] expected_pc: u16) {
+    let mut harness = Harness::new(source);
+    harness.bus.interrupt_enable = Interrupt::VBlank.get_bit();
+    harness.step();
+    harness.step();
+    assert!(harness.snapshot().halted);
+    assert_eq!(harness.step(), 1);
+
+    // The interrupt becomes pending while halted, the wake-up is measured from the next step
+    harness.bus.interrupt_flag = Interrupt::VBlank.get_bit();
+    assert_eq!(harness.step(), expected_cycles);
+    assert_eq!(harness.snapshot().pc, expected_pc);
+}
+
+#[test]
+fn test_halt_bug_repeats_next_byte() {
+    let mut harness = Harness::new("XOR A; HALT; INC A; NOP");
+    harness.bus.interrupt_enable = Interrupt::Timer.get_bit();
+    harness.bus.interrupt_flag = Interrupt::Timer.get_bit();
+
+    for _ in 0..4 {
+        harness.step();
+    }
+    let snapshot = harness.snapshot();
+    assert!(!snapshot.halted);
+    assert_eq!(snapshot.a, 2);
+    assert_eq!(snapshot.pc, 0x0103);
+}
+
+#[test]
+fn test_stop_skips_next_byte() {
+    let mut harness = Harness::new("STOP");
+    harness.step();
+    let snapshot = harness.snapshot();
+    assert!(snapshot.stopped);
+    assert_eq!(snapshot.pc, 0x0102);
+}
+
+#[test]
+fn test_illegal_opcode_locks_cpu() {
+    let mut harness = Harness::new("");
+    harness.bus.memory[ENTRY_POINT as usize] = 0xD3;
+    harness.step();
+    harness.step();
+    let snapshot = harness.snapshot();
+    assert!(snapshot.locked);
+    assert_eq!(snapshot.pc, 0x0101);
+}
+
+#[test]
+fn test_snapshot_contains_instruction_bytes() {
+    let harness = Harness::new("CALL 0x1234");
+    assert_eq!(harness.snapshot().get_instruction_bytes(), &[0xCD, 0x34, 0x12]);
+}
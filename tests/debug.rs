@@ -0,0 +1,356 @@
+mod common;
+
+use common::boot;
+use lemon_gb_core::cartridge::rom_builder::RomBuilder;
+use lemon_gb_core::circuitry::ppu::PpuMode;
+use lemon_gb_core::cpu::assembler::assemble;
+use lemon_gb_core::debug::disassembler::disassemble_instruction;
+use lemon_gb_core::debug::io_changes::IoRegisterChange;
+use lemon_gb_core::debug::io_register::IoRegister;
+use lemon_gb_core::debug::{
+    BlockedWrite, BreakReason, MemoryAccess, MemoryProtection, ProtectionMode, RegisterCondition, RegisterWatch,
+    RegisterWriteHit, StepKind, WatchAccess, Watchpoint, WatchpointHit,
+};
+use lemon_gb_core::game_boy::GameBoy;
+use lemon_gb_core::game_boy::checkpoints::EmulationError;
+use lemon_gb_core::hardware_model::HardwareModel;
+use rstest::rstest;
+use std::time::Duration;
+
+#[rstest]
+#[case(&[0x00], "NOP")]
+#[case(&[0x3E, 0x42], "LD A,0x42")]
+#[case(&[0x21, 0x34, 0x12], "LD HL,0x1234")]
+#[case(&[0xEA, 0x00, 0xC0], "LD (0xC000),A")]
+#[case(&[0xE0, 0x40], "LDH (0xFF40),A")]
+#[case(&[0x18, 0xFE], "JR 0x1000")]
+#[case(&[0x20, 0x05], "JR NZ,0x1007")]
+#[case(&[0xF8, 0xFE], "LD HL,SP-0x02")]
+#[case(&[0xE8, 0x10], "ADD SP,0x10")]
+#[case(&[0xCB, 0x7C], "BIT 7,H")]
+#[case(&[0xD3], "DB 0xD3")]
+fn test_disassemble_instruction(#[case] bytes: &[u8], #[case] text: &str) {
+    let instruction = disassemble_instruction(|address| bytes.get((address - 0x1000) as usize).copied().unwrap_or(0), 0x1000);
+    assert_eq!(instruction.text, text);
+    assert_eq!(instruction.bytes, bytes);
+}
+
+#[test]
+fn test_disassemble_range() {
+    let game_boy = boot("LD A, 0x01; INC A; JR -3");
+    let texts: Vec<String> = game_boy
+        .disassemble(0x0150, 0x0153)
+        .into_iter()
+        .map(|instruction| instruction.text)
+        .collect();
+    assert_eq!(texts, ["LD A,0x01", "INC A", "JR 0x0152"]);
+}
+
+#[test]
+fn test_step_instruction_trace() {
+    let mut game_boy = boot("LD A, 0x42");
+    game_boy.step_instruction();
+    game_boy.step_instruction();
+
+    let record = game_boy.step_instruction();
+    assert_eq!(record.kind, StepKind::Instruction);
+    assert_eq!(record.registers.pc, 0x0150);
+    assert_eq!(record.instruction.text, "LD A,0x42");
+    assert_eq!(record.cycles, 2);
+    assert_eq!(game_boy.get_cpu_snapshot().a, 0x42);
+}
+
+#[test]
+fn test_breakpoints() {
+    let mut game_boy = boot("LD B, 0x00; INC B; INC B; INC B; JR -4");
+    game_boy.get_debugger_mut().add_breakpoint(0x0154);
+    assert_eq!(game_boy.run_until_break(1_000), BreakReason::Breakpoint(0x0154));
+    assert_eq!(game_boy.get_cpu_snapshot().b, 2);
+
+    // Resuming executes the instruction at the breakpoint
+    assert_eq!(game_boy.run_until_break(1_000), BreakReason::Breakpoint(0x0154));
+    assert_eq!(game_boy.get_cpu_snapshot().b, 4);
+
+    game_boy.get_debugger_mut().remove_breakpoint(0x0154);
+    game_boy.get_debugger_mut().add_opcode_breakpoint(0x18);
+    assert_eq!(game_boy.run_until_break(1_000), BreakReason::OpcodeBreakpoint(0x18));
+    assert_eq!(game_boy.get_cpu_snapshot().pc, 0x0155);
+
+    game_boy.get_debugger_mut().clear();
+    assert_eq!(game_boy.run_until_break(1_000), BreakReason::CycleLimit);
+}
+
+#[test]
+fn test_watchpoints() {
+    let mut game_boy = boot("LD A, 0x11; LD (0xC010), A; LD A, (0xC010); LD (0xC020), A; JR -2");
+    game_boy
+        .get_debugger_mut()
+        .add_watchpoint(Watchpoint::new(0xC000, 0xC01F, WatchAccess::ReadWrite));
+
+    let hit = |access| {
+        BreakReason::Watchpoint(WatchpointHit {
+            address: 0xC010,
+            value: 0x11,
+            access,
+        })
+    };
+    assert_eq!(game_boy.run_until_break(1_000), hit(MemoryAccess::Write));
+    assert_eq!(game_boy.run_until_break(1_000), hit(MemoryAccess::Read));
+    assert_eq!(game_boy.run_until_break(1_000), BreakReason::CycleLimit);
+
+    game_boy.get_debugger_mut().add_watchpoint(Watchpoint::read(0x015B));
+    let record = game_boy.step_instruction();
+    assert_eq!(record.instruction.text, "JR 0x015B");
+    assert_eq!(
+        game_boy.run_until_break(1_000),
+        BreakReason::Watchpoint(WatchpointHit {
+            address: 0x015B,
+            value: 0x18,
+            access: MemoryAccess::Read,
+        })
+    );
+}
+
+#[test]
+fn test_memory_protections() {
+    let mut game_boy = boot("LD A, 0x11; LD (0xC000), A; LD (0xC010), A; LD (0xC020), A; JR -2");
+    game_boy
+        .get_debugger_mut()
+        .add_protection(MemoryProtection::new(0xC000, 0xC01F, ProtectionMode::ReadOnly));
+    game_boy.get_debugger_mut().add_protection(MemoryProtection::trap(0xC010));
+
+    assert_eq!(
+        game_boy.run_until_break(1_000),
+        BreakReason::ProtectedWrite(BlockedWrite {
+            address: 0xC010,
+            value: 0x11,
+            mode: ProtectionMode::Trap,
+        })
+    );
+    assert_eq!(game_boy.run_until_break(1_000), BreakReason::CycleLimit);
+    assert_eq!([0xC000, 0xC010, 0xC020].map(|address| game_boy.peek(address)), [0x00, 0x00, 0x11]);
+
+    // Protections also apply outside of the debugging methods
+    let mut game_boy = boot("LD A, 0x22; LD (0xC000), A; JR -5");
+    game_boy.get_debugger_mut().add_protection(MemoryProtection::read_only(0xC000));
+    game_boy.run_until_frame();
+    assert_eq!(game_boy.peek(0xC000), 0x00);
+
+    let protection = game_boy.get_debugger().get_protections()[0];
+    assert!(game_boy.get_debugger_mut().remove_protection(&protection));
+    game_boy.run_until_frame();
+    assert_eq!(game_boy.peek(0xC000), 0x22);
+}
+
+#[rstest]
+#[case("LCDC", Some(IoRegister::LCDC))]
+#[case("tac", Some(IoRegister::TAC))]
+#[case("JOYP", Some(IoRegister::P1))]
+#[case("NR15", None)]
+fn test_io_register_names(#[case] name: &str, #[case] register: Option<IoRegister>) {
+    assert_eq!(IoRegister::from_name(name), register);
+    if let Some(register) = register {
+        assert_eq!(IoRegister::from_address(register.get_address()), Some(register));
+    }
+}
+
+#[test]
+fn test_io_changes() {
+    let mut game_boy = boot("LD A, 0x12; LDH (0x43), A; JR -2");
+    game_boy.get_debugger_mut().enable_io_changes();
+    game_boy.step();
+    let changes = game_boy.get_debugger().get_io_changes().unwrap();
+    assert_eq!(changes.get_value(IoRegister::SCX), Some(0x00));
+    assert!(changes.get_changes().is_empty());
+
+    game_boy.run_until_frame();
+    let changes = game_boy.get_debugger().get_io_changes().unwrap();
+    let scx = IoRegisterChange { register: IoRegister::SCX, old: 0x00, new: 0x12 };
+    assert!(changes.get_changes().contains(&scx));
+    assert_eq!(changes.get_value(IoRegister::SCX), Some(0x12));
+    assert_eq!(changes.get_frame_count(), Some(game_boy.get_frame_count()));
+
+    game_boy.run_until_frame();
+    let changes = game_boy.get_debugger().get_io_changes().unwrap();
+    assert!(changes.get_changes().iter().all(|change| change.register != IoRegister::SCX));
+    assert!(game_boy.get_debugger_mut().disable_io_changes().is_some());
+}
+
+#[test]
+fn test_register_watches() {
+    let mut game_boy = boot(
+        "LD A, 0x11; LDH (0x07), A; LD A, 0x05; LDH (0x07), A; LD A, 0x91; LDH (0x40), A; LD A, 0x81; LDH (0x40), A; JR -2",
+    );
+    // Only breaks once the timer gets enabled
+    game_boy
+        .get_debugger_mut()
+        .add_register_watch(RegisterWatch::new(IoRegister::TAC, 0x04, RegisterCondition::Equals(0x04)));
+    // Only breaks once the BG tile data area changes
+    game_boy
+        .get_debugger_mut()
+        .add_register_watch(RegisterWatch::new(IoRegister::LCDC, 0x10, RegisterCondition::Changed));
+
+    assert_eq!(
+        game_boy.run_until_break(1_000),
+        BreakReason::RegisterWrite(RegisterWriteHit {
+            register: IoRegister::TAC,
+            old_value: 0xF9,
+            value: 0x05,
+        })
+    );
+    assert_eq!(
+        game_boy.run_until_break(1_000),
+        BreakReason::RegisterWrite(RegisterWriteHit {
+            register: IoRegister::LCDC,
+            old_value: 0x91,
+            value: 0x81,
+        })
+    );
+    assert_eq!(game_boy.get_cpu_snapshot().pc, 0x0160);
+    assert_eq!(game_boy.run_until_break(1_000), BreakReason::CycleLimit);
+}
+
+#[test]
+fn test_dot_tracing() {
+    let mut game_boy = boot("LD A, (0xC000); JR -5");
+    assert!(game_boy.step_instruction().dots.is_empty());
+
+    game_boy.get_debugger_mut().set_dot_tracing(true);
+    let mut previous = game_boy.step_instruction().dots.last().copied().unwrap();
+    let mut transitions = Vec::new();
+    for _ in 0..200 {
+        let record = game_boy.step_instruction();
+        assert_eq!(record.dots.len(), record.cycles as usize * 4);
+        for dot in record.dots {
+            assert_eq!(dot.dot, (previous.dot + 1) % 456);
+            if dot.mode != previous.mode {
+                transitions.push((dot.mode, dot.dot));
+            }
+            previous = dot;
+        }
+    }
+
+    assert!(transitions.contains(&(PpuMode::Drawing, 80)));
+    assert!(transitions.contains(&(PpuMode::HBlank, 252)));
+    // The mode of a new line begins a few dots after LY increments
+    assert!(transitions.contains(&(PpuMode::OamScan, 4)));
+}
+
+#[test]
+fn test_trace_buffer() {
+    let mut game_boy = boot("LD B, 0x00; INC B; INC B; INC B; JR -4");
+    game_boy.step();
+    assert!(game_boy.get_debugger().get_trace_buffer().is_none());
+
+    game_boy.get_debugger_mut().enable_trace_buffer(3);
+    for _ in 0..6 {
+        game_boy.step();
+    }
+
+    let buffer = game_boy.get_debugger().get_trace_buffer().unwrap();
+    assert_eq!(buffer.len(), 3);
+    let pcs: Vec<u16> = buffer.iter().map(|entry| entry.pc).collect();
+    assert_eq!(pcs, [0x0153, 0x0154, 0x0155]);
+    let last = buffer.iter().last().unwrap();
+    assert_eq!(last.disassemble().text, "JR 0x0153");
+    assert_eq!(last.bc >> 8, 3);
+}
+
+#[test]
+fn test_trace_buffer_dump_after_lock_up() {
+    let mut code = assemble("LD A, 0x42; INC A").unwrap();
+    code.push(0xD3);
+    let rom = RomBuilder::new().code(&code).build();
+    let mut game_boy = GameBoy::new(HardwareModel::DMG);
+    game_boy.insert_cartridge(rom).unwrap();
+    game_boy.get_debugger_mut().enable_trace_buffer(16);
+    for _ in 0..10 {
+        game_boy.step();
+    }
+
+    let dump = game_boy.get_debugger().get_trace_buffer().unwrap().dump();
+    let lines: Vec<&str> = dump.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[2].starts_with("0x0150: LD A,0x42"));
+    assert!(lines[3].contains("AF=0x42"));
+    assert!(lines[4].starts_with("0x0153: DB 0xD3"));
+}
+
+#[test]
+fn test_bank_usage() {
+    let rom = RomBuilder::new()
+        .cartridge_type(0x01)
+        .rom_banks(4)
+        .code(&assemble("LD A, 0x02; LD (0x2000), A; JP 0x4000").unwrap())
+        .code_at(0x8000, &assemble("JR -2").unwrap())
+        .build();
+    let mut game_boy = GameBoy::new(HardwareModel::DMG);
+    game_boy.insert_cartridge(rom).unwrap();
+    game_boy.step();
+    assert!(game_boy.get_debugger().get_bank_usage().is_none());
+
+    game_boy.get_debugger_mut().enable_bank_usage();
+    let cycles = game_boy.run_until_frame() as u64;
+    let usage = game_boy.get_debugger().get_bank_usage().unwrap();
+    // The entry point jump, the bank switch and the jump into bank 2
+    assert_eq!(usage.get_rom_cycles(0), 4 + 2 + 4 + 4);
+    assert_eq!(usage.get_rom_cycles(2), cycles - 14);
+    assert_eq!(usage.get_total_cycles(), cycles);
+    assert_eq!(usage.iter().map(|(bank, _)| bank).collect::<Vec<_>>(), [0, 2]);
+    assert!(usage.report().starts_with("Bank 0x000"));
+
+    assert!(game_boy.get_debugger_mut().disable_bank_usage().is_some());
+    assert!(game_boy.get_debugger().get_bank_usage().is_none());
+}
+
+#[test]
+fn test_error_checkpoints() {
+    let mut code = assemble("LD BC, 0x4000; DEC BC; LD A, B; OR C; JR NZ, -5").unwrap();
+    code.push(0xD3);
+    let rom = RomBuilder::new().code(&code).build();
+    let mut game_boy = GameBoy::new(HardwareModel::DMG);
+    game_boy.insert_cartridge(rom).unwrap();
+    game_boy.enable_error_checkpoints();
+    while !game_boy.get_cpu_snapshot().locked {
+        game_boy.step();
+    }
+    game_boy.step();
+
+    let checkpoint = game_boy.take_error_checkpoint().unwrap();
+    assert_eq!(checkpoint.error, EmulationError::IllegalOpcode { address: 0x0158, opcode: 0xD3 });
+    assert!(checkpoint.frame_count > 0);
+    assert!(game_boy.take_error_checkpoint().is_none());
+
+    let mut replay = GameBoy::new(HardwareModel::DMG);
+    replay.insert_cartridge(game_boy.get_cartridge().unwrap().get_rom().to_vec()).unwrap();
+    replay.load_state(&checkpoint.state).unwrap();
+    assert_eq!(replay.get_frame_count(), checkpoint.frame_count);
+    let mut steps = 0;
+    while !replay.get_cpu_snapshot().locked {
+        replay.step();
+        steps += 1;
+    }
+    assert!(steps > 0);
+    assert_eq!(replay.get_cpu_snapshot().pc, 0x0159);
+}
+
+#[test]
+fn test_frame_profile() {
+    let mut game_boy = boot("LD A, 0x42; LD (0xC000), A; JR -7");
+    game_boy.set_profiling(true);
+    assert!(game_boy.get_frame_profile().is_none());
+
+    game_boy.run_until_frame();
+    game_boy.run_until_frame();
+    let profile = *game_boy.get_frame_profile().unwrap();
+    assert!(profile.cpu > Duration::ZERO);
+    assert!(profile.ppu > Duration::ZERO);
+    assert!(profile.apu > Duration::ZERO);
+    assert!(profile.bus > Duration::ZERO);
+    assert_eq!(profile.get_total(), profile.cpu + profile.ppu + profile.apu + profile.bus);
+
+    game_boy.set_profiling(false);
+    assert!(game_boy.get_frame_profile().is_none());
+    game_boy.run_until_frame();
+    assert!(game_boy.get_frame_profile().is_none());
+}
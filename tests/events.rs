@@ -0,0 +1,56 @@
+mod common;
+
+use common::boot;
+use lemon_gb_core::game_boy::GameBoy;
+use lemon_gb_core::game_boy::events::EventTime;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_events_run_at_their_time() {
+    let mut game_boy = boot("JR -2");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let cycle_log = log.clone();
+    game_boy.schedule_event(EventTime::Cycle(1000), move |game_boy| {
+        cycle_log.lock().unwrap().push(("cycle", game_boy.get_cycle_count()));
+    });
+    let frame_log = log.clone();
+    game_boy.schedule_event(EventTime::Frame(3), move |game_boy| {
+        frame_log.lock().unwrap().push(("frame", game_boy.get_frame_count()));
+    });
+    let cancelled = game_boy.schedule_event(EventTime::Frame(2), |_| panic!("cancelled event ran"));
+    assert!(game_boy.cancel_event(cancelled));
+    assert!(!game_boy.cancel_event(cancelled));
+
+    for _ in 0..4 {
+        game_boy.run_until_frame();
+    }
+
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].0, "cycle");
+    // The loop takes 3 M-cycles, the event runs after the step reaching the cycle
+    assert!((1000..1003).contains(&log[0].1));
+    assert_eq!(log[1], ("frame", 3));
+    assert_eq!(game_boy.get_pending_event_count(), 0);
+}
+
+#[test]
+fn test_events_can_reschedule() {
+    let mut game_boy = boot("JR -2");
+    let count = Arc::new(Mutex::new(0));
+
+    fn every_frame(game_boy: &mut GameBoy, count: Arc<Mutex<u32>>) {
+        *count.lock().unwrap() += 1;
+        let frame = game_boy.get_frame_count() + 1;
+        game_boy.schedule_event(EventTime::Frame(frame), move |game_boy| every_frame(game_boy, count));
+    }
+    let event_count = count.clone();
+    game_boy.schedule_event(EventTime::Frame(1), move |game_boy| every_frame(game_boy, event_count));
+
+    for _ in 0..5 {
+        game_boy.run_until_frame();
+    }
+    assert_eq!(*count.lock().unwrap(), 5);
+    assert_eq!(game_boy.get_pending_event_count(), 1);
+}
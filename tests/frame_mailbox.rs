@@ -0,0 +1,57 @@
+mod common;
+
+use common::boot;
+use lemon_gb_core::game_boy::frame_mailbox::FrameMailbox;
+use std::thread;
+
+#[test]
+fn test_latest_frame_wins() {
+    let (mut publisher, mut consumer) = FrameMailbox::create(0u32);
+    assert!(!consumer.has_new_frame());
+    assert!(consumer.take_new_frame().is_none());
+    assert_eq!(*consumer.latest_frame(), 0);
+
+    publisher.publish(&1);
+    publisher.publish(&2);
+    assert!(consumer.has_new_frame());
+    assert_eq!(consumer.take_new_frame(), Some(&2));
+    assert!(consumer.take_new_frame().is_none());
+    assert_eq!(*consumer.latest_frame(), 2);
+
+    // The slot still holds the frame published two frames ago
+    publisher.publish_with(|frame| *frame += 10);
+    assert_eq!(*consumer.latest_frame(), 11);
+}
+
+#[test]
+fn test_frames_across_threads() {
+    let (mut publisher, mut consumer) = FrameMailbox::create(0u64);
+    let emulation = thread::spawn(move || {
+        for frame in 1..=10_000u64 {
+            publisher.publish(&frame);
+        }
+    });
+
+    let mut last = 0;
+    while last < 10_000 {
+        if let Some(&frame) = consumer.take_new_frame() {
+            // Frames can be skipped, but never arrive out of order
+            assert!(frame > last);
+            last = frame;
+        }
+    }
+    emulation.join().unwrap();
+}
+
+#[test]
+fn test_frame_buffers() {
+    let mut game_boy = boot("LD A, 0xFF; LDH (0x47), A; JR -2");
+    let (mut publisher, mut consumer) = FrameMailbox::for_frames();
+    assert!(consumer.latest_frame().iter().all(|&shade| shade == 0));
+
+    game_boy.run_until_frame();
+    game_boy.run_until_frame();
+    publisher.publish(game_boy.get_frame_buffer());
+    assert_eq!(consumer.latest_frame(), game_boy.get_frame_buffer());
+    assert!(consumer.latest_frame().iter().all(|&shade| shade == 3));
+}
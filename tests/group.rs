@@ -0,0 +1,44 @@
+mod common;
+
+use common::boot_with;
+use lemon_gb_core::game_boy::GameBoy;
+use lemon_gb_core::game_boy::group::GameBoyGroup;
+use lemon_gb_core::hardware_model::HardwareModel;
+
+fn boot(model: HardwareModel) -> GameBoy {
+    boot_with(GameBoy::new(model), "LD HL, 0xC000; INC (HL); PUSH HL; POP HL; JR -5")
+}
+
+#[test]
+fn test_group_runs_instances_in_lockstep() {
+    let mut group = GameBoyGroup::new(vec![boot(HardwareModel::DMG), boot(HardwareModel::DMG)]);
+    group.run_cycles(10_000);
+    group.run_cycles(10_000);
+
+    assert_eq!(group.get_target_cycles(), 20_000);
+    for index in 0..group.len() {
+        let elapsed = group.get_elapsed_cycles(index).unwrap();
+        assert!((20_000..20_006).contains(&elapsed));
+    }
+    assert_eq!(group.get(0), group.get(1));
+
+    // Joining instances start at the current time
+    let index = group.push(boot(HardwareModel::MGB));
+    assert_eq!(group.get_elapsed_cycles(index), Some(20_000));
+}
+
+#[test]
+fn test_group_steps_the_instance_furthest_behind() {
+    let mut group = GameBoyGroup::new(vec![boot(HardwareModel::DMG), boot(HardwareModel::MGB)]);
+    let mut order = Vec::new();
+    for _ in 0..100 {
+        let index = group.step().unwrap();
+        let elapsed = [0, 1].map(|index| group.get_elapsed_cycles(index).unwrap());
+        assert!(elapsed[0].abs_diff(elapsed[1]) <= 6);
+        order.push(index);
+    }
+
+    // Both instances run the same code, so ties always start with the first one
+    assert_eq!(order.iter().step_by(2).collect::<Vec<_>>(), [&0; 50]);
+    assert!(GameBoyGroup::default().step().is_none());
+}
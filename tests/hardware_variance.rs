@@ -0,0 +1,50 @@
+mod common;
+
+use common::boot_with;
+use lemon_gb_core::circuitry::hardware_variance::HardwareVariance;
+use lemon_gb_core::cpu::assembler::assemble;
+use lemon_gb_core::game_boy::GameBoy;
+use rstest::rstest;
+
+fn oam(variance: HardwareVariance) -> Vec<u8> {
+    let game_boy = GameBoy::builder().hardware_variance(variance).build();
+    (0xFE00..=0xFE9F).map(|address| game_boy.peek(address)).collect()
+}
+
+/// Runs a routine from HRAM that stores reads of the empty cartridge slot to WRAM
+fn read_empty_slot(variance: HardwareVariance) -> Vec<u8> {
+    let routine = assemble("LD HL, 0xC000; LD A, (0x4000); LD (HL+), A; JR -6").unwrap();
+    let copies: Vec<String> = routine
+        .iter()
+        .enumerate()
+        .map(|(offset, byte)| format!("LD A, {byte}; LDH (0x{:02X}), A", 0x80 + offset))
+        .collect();
+    let source = format!("{}; JP 0xFF80", copies.join("; "));
+    let mut game_boy = boot_with(GameBoy::builder().hardware_variance(variance).build(), &source);
+    while game_boy.get_cpu_snapshot().pc != 0xFF80 {
+        game_boy.step();
+    }
+    game_boy.remove_cartridge();
+    for _ in 0..64 {
+        game_boy.step();
+    }
+    (0xC000..0xC010).map(|address| game_boy.peek(address)).collect()
+}
+
+#[test]
+fn test_deterministic_by_default() {
+    assert!(oam(HardwareVariance::Deterministic).iter().all(|&byte| byte == 0x00));
+    assert!(read_empty_slot(HardwareVariance::Deterministic).iter().all(|&byte| byte == 0xFF));
+    let deterministic = GameBoy::builder().hardware_variance(HardwareVariance::Deterministic).build();
+    assert_eq!(GameBoy::builder().build(), deterministic);
+}
+
+#[rstest]
+#[case(oam)]
+#[case(read_empty_slot)]
+fn test_seeded_variance(#[case] observe: fn(HardwareVariance) -> Vec<u8>) {
+    let first = observe(HardwareVariance::Seeded { seed: 1 });
+    assert_eq!(first, observe(HardwareVariance::Seeded { seed: 1 }));
+    assert_ne!(first, observe(HardwareVariance::Seeded { seed: 2 }));
+    assert!(first.iter().any(|&byte| byte != first[0]));
+}
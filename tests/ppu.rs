@@ -0,0 +1,376 @@
+mod common;
+
+use common::{boot, boot_with};
+use lemon_gb_core::circuitry::Circuitry;
+use lemon_gb_core::circuitry::hardware_variance::HardwareVariance;
+use lemon_gb_core::circuitry::interface::CircuitryInterface;
+use lemon_gb_core::circuitry::interrupts::{Interrupt, InterruptRegisters};
+use lemon_gb_core::circuitry::ppu::{PPU, PpuMode, SCREEN_WIDTH};
+use lemon_gb_core::circuitry::ppu::layers::{LayerPalettes, LayerPixel, PixelLayer};
+use lemon_gb_core::circuitry::ram_initialization::RamInitialization;
+use lemon_gb_core::cpu::assembler::assemble;
+use lemon_gb_core::game_boy::{CYCLES_PER_FRAME, GameBoy};
+use lemon_gb_core::hardware_model::HardwareModel;
+use lemon_gb_core::hardware_model::revision::Revision;
+use rstest::rstest;
+
+/// Turns the LCD off, fills tile 1 with color 3 and places it at the top left of the background
+const DRAW_TILE: &str = "LD A, 0x00; LDH (0x40), A; LD HL, 0x8010; LD A, 0xFF; LD B, 16; LD (HL+), A; DEC B; JR NZ, -4; \
+                         LD A, 0x01; LD (0x9800), A";
+
+/// Copies the routine to HRAM, the CPU has to run from there while OAM DMA occupies the external bus
+fn copy_to_hram(routine: &str) -> String {
+    let bytes = assemble(routine).unwrap();
+    let copies: Vec<String> = bytes
+        .iter()
+        .enumerate()
+        .map(|(offset, byte)| format!("LD A, {byte}; LDH (0x{:02X}), A", 0x80 + offset))
+        .collect();
+    copies.join("; ")
+}
+
+fn pixel(game_boy: &GameBoy, x: usize, y: usize) -> u8 {
+    game_boy.get_frame_buffer()[y * SCREEN_WIDTH + x]
+}
+
+#[test]
+fn test_frame_timing() {
+    let mut game_boy = boot("JR -2");
+    game_boy.run_until_frame();
+    let frame_count = game_boy.get_frame_count();
+
+    let cycles = game_boy.run_until_frame();
+    assert_eq!(game_boy.get_frame_count(), frame_count + 1);
+    // The loop takes 3 M-cycles, the frame can be completed within the last instruction
+    assert!(cycles.abs_diff(CYCLES_PER_FRAME) < 3);
+}
+
+#[test]
+fn test_reset_ppu() {
+    let mut game_boy = boot("LD A, 0x12; LDH (0x43), A; LD A, 0xE4; LDH (0x47), A; LD A, 0x00; LDH (0x40), A; JR -2");
+    game_boy.run_until_frame();
+    game_boy.run_until_frame();
+    let frame_count = game_boy.get_frame_count();
+    assert_eq!(game_boy.peek(0xFF40), 0x00);
+
+    game_boy.reset_ppu();
+    let fresh = GameBoy::new(HardwareModel::DMG);
+    for address in 0xFF40..=0xFF4B {
+        assert_eq!(game_boy.peek(address), fresh.peek(address), "0x{address:04X}");
+    }
+    assert_eq!(game_boy.get_frame_count(), frame_count);
+    game_boy.run_until_frame();
+    assert_eq!(game_boy.get_frame_count(), frame_count + 1);
+}
+
+#[rstest]
+#[case(0, 2)]
+#[case(3, 2)]
+#[case(5, 1)]
+#[case(8, 1)]
+fn test_screen_tiles(#[case] scx: u8, #[case] column: usize) {
+    // Tiles 0x28 and 0x29 are "H" and "I" in a font starting with the space at tile 0
+    let mut game_boy = boot(&format!(
+        "LD A, 0x00; LDH (0x40), A; LD A, 0x28; LD (0x9822), A; LD A, 0x29; LD (0x9823), A; \
+         LD A, {scx}; LDH (0x43), A; LD A, 0x91; LDH (0x40), A; JR -2"
+    ));
+    game_boy.run_until_frame();
+    game_boy.run_until_frame();
+
+    let tiles = game_boy.get_screen_tiles().unwrap();
+    assert_eq!(tiles.get(column, 1), Some(0x28));
+    assert_eq!(tiles.get(20, 0), None);
+    let text = tiles.to_ascii(0);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 18);
+    assert_eq!(lines[1], format!("{:column$}HI{:rest$}", "", "", rest = 18 - column));
+    assert!(lines[0].chars().all(|char| char == ' '));
+}
+
+#[test]
+fn test_screen_tiles_while_blanked() {
+    // The tile map still holds the text, but the background and window are disabled
+    let mut game_boy = boot(
+        "LD A, 0x00; LDH (0x40), A; LD A, 0x28; LD (0x9822), A; LD A, 0x29; LD (0x9823), A; \
+         LD A, 0x90; LDH (0x40), A; JR -2",
+    );
+    game_boy.run_until_frame();
+    game_boy.run_until_frame();
+
+    assert!(game_boy.get_frame_buffer().iter().all(|&shade| shade == 0));
+    assert_eq!(game_boy.get_screen_tiles(), None);
+}
+
+/// Ticks until the PPU starts the line, the dot is then 0
+fn tick_until_line(ppu: &mut PPU, interrupts: &mut InterruptRegisters, line: u8) {
+    while ppu.get_ly() != line || ppu.get_dot() != 0 {
+        ppu.tick(interrupts);
+    }
+}
+
+#[test]
+fn test_line_start_timing() {
+    let mut ppu = PPU::initialize();
+    let mut interrupts = InterruptRegisters::default();
+    // Select the OAM STAT interrupt
+    ppu.write(0xFF41, 0x20, &mut interrupts);
+
+    while ppu.get_dot() != 452 {
+        ppu.tick(&mut interrupts);
+    }
+    interrupts = InterruptRegisters::default();
+    ppu.tick(&mut interrupts);
+    assert_eq!(ppu.read(0xFF44), 1);
+    assert_eq!(ppu.get_stat() & 0x03, PpuMode::HBlank as u8);
+    assert_ne!(interrupts.get_flag() & Interrupt::LCD.get_bit(), 0);
+    ppu.tick(&mut interrupts);
+    assert_eq!(ppu.get_stat() & 0x03, PpuMode::OamScan as u8);
+
+    // The OAM interrupt is requested on line 144 as well, the VBlank interrupt only once the mode changes
+    interrupts = InterruptRegisters::default();
+    tick_until_line(&mut ppu, &mut interrupts, 144);
+    assert_eq!(ppu.read(0xFF44), 144);
+    assert_eq!(ppu.get_stat() & 0x03, PpuMode::HBlank as u8);
+    assert_eq!(interrupts.get_flag() & Interrupt::VBlank.get_bit(), 0);
+    assert_ne!(interrupts.get_flag() & Interrupt::LCD.get_bit(), 0);
+    ppu.tick(&mut interrupts);
+    assert_eq!(ppu.get_stat() & 0x03, PpuMode::VBlank as u8);
+    assert_ne!(interrupts.get_flag() & Interrupt::VBlank.get_bit(), 0);
+}
+
+#[test]
+fn test_last_line_reads_as_line_0() {
+    let mut ppu = PPU::initialize();
+    let mut interrupts = InterruptRegisters::default();
+    tick_until_line(&mut ppu, &mut interrupts, 153);
+    assert_eq!(ppu.read(0xFF44), 153);
+    assert_eq!(ppu.get_stat() & 0x04, 0);
+
+    ppu.tick(&mut interrupts);
+    assert_eq!(ppu.read(0xFF44), 0);
+    assert_eq!(ppu.get_ly(), 153);
+    assert_ne!(ppu.get_stat() & 0x04, 0);
+    assert_eq!(ppu.get_stat() & 0x03, PpuMode::VBlank as u8);
+}
+
+#[test]
+fn test_lcd_off_completes_no_frames() {
+    let mut game_boy = boot("LD A, 0x00; LDH (0x40), A; JR -2");
+    game_boy.run_until_frame();
+    let frame_count = game_boy.get_frame_count();
+
+    assert!(game_boy.run_until_frame() >= CYCLES_PER_FRAME);
+    assert_eq!(game_boy.get_frame_count(), frame_count);
+    assert!(game_boy.get_frame_buffer().iter().all(|&shade| shade == 0));
+}
+
+#[test]
+fn test_background_rendering() {
+    let mut game_boy = boot(&format!("{DRAW_TILE}; LD A, 0x91; LDH (0x40), A; JR -2"));
+    game_boy.run_until_frame();
+    game_boy.run_until_frame();
+
+    assert_eq!(pixel(&game_boy, 0, 0), 3);
+    assert_eq!(pixel(&game_boy, 7, 7), 3);
+    assert_eq!(pixel(&game_boy, 8, 0), 0);
+    assert_eq!(pixel(&game_boy, 0, 8), 0);
+}
+
+#[test]
+fn test_background_scrolling() {
+    let mut game_boy = boot(&format!(
+        "{DRAW_TILE}; LD A, 0x04; LDH (0x42), A; LDH (0x43), A; LD A, 0x91; LDH (0x40), A; JR -2"
+    ));
+    game_boy.run_until_frame();
+    game_boy.run_until_frame();
+
+    assert_eq!(pixel(&game_boy, 3, 3), 3);
+    assert_eq!(pixel(&game_boy, 4, 0), 0);
+    assert_eq!(pixel(&game_boy, 0, 4), 0);
+}
+
+#[test]
+fn test_object_rendering_with_dma() {
+    // The object is placed in WRAM and copied to OAM via DMA, the wait loop covers the 160 M-cycles of the transfer
+    let dma_routine = copy_to_hram("LDH (0x46), A; LD B, 40; DEC B; JR NZ, -3; RET");
+    let mut game_boy = boot(&format!(
+        "{DRAW_TILE}; LD A, 0x00; LD (0x9800), A; {dma_routine}; \
+         LD HL, 0xC000; LD A, 0x10; LD (HL+), A; LD A, 0x10; LD (HL+), A; LD A, 0x01; LD (HL+), A; LD A, 0x00; LD (HL+), A; \
+         LD A, 0xC0; CALL 0xFF80; \
+         LD A, 0xE4; LDH (0x48), A; LD A, 0x93; LDH (0x40), A; JR -2"
+    ));
+    game_boy.run_until_frame();
+    game_boy.run_until_frame();
+
+    assert_eq!(pixel(&game_boy, 7, 0), 0);
+    assert_eq!(pixel(&game_boy, 8, 0), 3);
+    assert_eq!(pixel(&game_boy, 15, 7), 3);
+    assert_eq!(pixel(&game_boy, 16, 0), 0);
+    assert_eq!(pixel(&game_boy, 8, 8), 0);
+}
+
+#[test]
+fn test_frame_metadata_lcd_off() {
+    let mut game_boy = boot("JR -2");
+    game_boy.run_until_frame();
+    assert!(!game_boy.get_frame_metadata().lcd_off);
+
+    let mut game_boy = boot("LD A, 0x00; LDH (0x40), A; JR -2");
+    game_boy.run_until_frame();
+    assert!(game_boy.get_frame_metadata().lcd_off);
+}
+
+#[test]
+fn test_frame_metadata_window_lines() {
+    let mut game_boy = boot(&format!(
+        "{DRAW_TILE}; LD A, 0x10; LDH (0x4A), A; LD A, 0x07; LDH (0x4B), A; LD A, 0xB1; LDH (0x40), A; JR -2"
+    ));
+    game_boy.run_until_frame();
+    game_boy.run_until_frame();
+
+    let metadata = game_boy.get_frame_metadata();
+    assert!(!metadata.lcd_off);
+    assert!(metadata.window_lines[..16].iter().all(|&active| !active));
+    assert!(metadata.window_lines[16..].iter().all(|&active| active));
+}
+
+#[test]
+fn test_layer_tracking() {
+    // The window from line 16 on and an object using OBP1 at (72, 32)
+    let mut game_boy = boot(&format!(
+        "{DRAW_TILE}; LD HL, 0xFE00; LD A, 0x30; LD (HL+), A; LD A, 0x50; LD (HL+), A; LD A, 0x01; LD (HL+), A; \
+         LD A, 0x10; LD (HL+), A; LDH (0x4A), A; LD A, 0x07; LDH (0x4B), A; LD A, 0xB3; LDH (0x40), A; JR -2"
+    ));
+    game_boy.run_until_frame();
+    assert!(game_boy.get_layer_buffer().is_none());
+
+    game_boy.set_layer_tracking(true);
+    game_boy.run_until_frame();
+    let layers = game_boy.get_layer_buffer().unwrap();
+    let layer = |x: usize, y: usize| layers[y * SCREEN_WIDTH + x];
+    assert_eq!(layer(0, 0), LayerPixel { layer: PixelLayer::Background, color: 3 });
+    assert_eq!(layer(20, 5), LayerPixel { layer: PixelLayer::Background, color: 0 });
+    assert_eq!(layer(0, 16), LayerPixel { layer: PixelLayer::Window, color: 3 });
+    assert_eq!(layer(20, 20), LayerPixel { layer: PixelLayer::Window, color: 0 });
+    assert_eq!(layer(72, 32), LayerPixel { layer: PixelLayer::Object1, color: 3 });
+
+    let palettes = LayerPalettes::default();
+    let colors = palettes.colorize(layers);
+    assert_eq!(colors[32 * SCREEN_WIDTH + 72], palettes.object1[3]);
+    assert_eq!(colors[16 * SCREEN_WIDTH], palettes.window[3]);
+
+    game_boy.set_layer_tracking(false);
+    assert!(game_boy.get_layer_buffer().is_none());
+}
+
+#[test]
+fn test_frame_metadata_sprite_overflow_lines() {
+    // 11 objects on lines 16 to 23, written to OAM while the LCD is off
+    let mut game_boy = boot(&format!(
+        "{DRAW_TILE}; LD HL, 0xFE00; LD B, 11; LD A, 0x20; LD (HL+), A; LD (HL+), A; LD (HL+), A; LD (HL+), A; \
+         DEC B; JR NZ, -7; LD A, 0x93; LDH (0x40), A; JR -2"
+    ));
+    game_boy.run_until_frame();
+    game_boy.run_until_frame();
+
+    let metadata = game_boy.get_frame_metadata();
+    assert!(metadata.sprite_overflow_lines[16..24].iter().all(|&overflow| overflow));
+    assert_eq!(metadata.get_overflow_line_count(), 8);
+    // Only the 11th object is dropped, once on each of its lines
+    assert_eq!(metadata.get_dropped_objects(16).collect::<Vec<_>>(), [10]);
+    assert_eq!(metadata.get_dropped_objects(24).count(), 0);
+    assert_eq!(metadata.get_dropped_object_count(), 8);
+    assert!(metadata.window_lines.iter().all(|&active| !active));
+}
+
+#[rstest]
+#[case(HardwareModel::DMG, None, true)]
+#[case(HardwareModel::CGB, None, false)]
+#[case(HardwareModel::DMG, Some(Revision::CGB), false)]
+fn test_stat_write_interrupt_quirk(#[case] model: HardwareModel, #[case] revision: Option<Revision>, #[case] requested: bool) {
+    // LY and LYC are both 0 after boot, so the LYC source is active
+    let mut builder = GameBoy::builder().model(model);
+    if let Some(revision) = revision {
+        builder = builder.revision(revision);
+    }
+    let mut game_boy = boot_with(builder.build(), "LD A, 0x00; LDH (0x0F), A; LDH (0x41), A; JR -2");
+    for _ in 0..5 {
+        game_boy.step();
+    }
+
+    assert_eq!(game_boy.peek(0xFF0F) & 0x02 != 0, requested);
+}
+
+#[test]
+fn test_capture_when() {
+    // Counts frames in WRAM by waking up from HALT on every VBlank
+    let mut game_boy = boot("LD A, 0x01; LDH (0xFF), A; XOR A; LDH (0x0F), A; HALT; LD HL, 0xC000; INC (HL); JR -10");
+
+    let frame = game_boy.capture_when(|game_boy| game_boy.peek(0xC000) >= 3, 10).unwrap();
+    assert_eq!(game_boy.peek(0xC000), 3);
+    assert_eq!(*frame, *game_boy.get_frame_buffer());
+
+    let mut game_boy = boot(&format!("{DRAW_TILE}; LD A, 0x91; LDH (0x40), A; JR -2"));
+    let frame = game_boy.capture_when(|game_boy| game_boy.get_frame_buffer()[0] == 3, 10).unwrap();
+    assert_eq!(frame[0], 3);
+
+    assert!(game_boy.capture_when(|_| false, 5).is_none());
+}
+
+#[rstest]
+#[case(0xC0, 0x02)]
+#[case(0x80, 0x10)]
+#[case(0xFE, 0x02)]
+#[case(0xFF, 0x02)]
+fn test_dma_bus_conflict_reads(#[case] source: u8, #[case] expected: u8) {
+    // WRAM holds the low byte of each address, the routine reads 0xC010 right after starting the transfer.
+    // Sources from 0xE000 upward copy from WRAM, so the routine in HRAM keeps running.
+    let dma_routine = copy_to_hram("LDH (0x46), A; LD A, (0xC010); LDH (0x90), A; LD B, 40; DEC B; JR NZ, -3; RET");
+    let wram_page = if source >= 0xE0 { source - 0x20 } else { 0xC0 };
+    let mut game_boy = boot(&format!(
+        "{dma_routine}; LD HL, 0xC000; LD A, L; LD (HL+), A; LD A, L; CP 0xA0; JR NZ, -7; \
+         LD H, {wram_page}; LD L, 0; LD A, L; LD (HL+), A; LD A, L; CP 0xA0; JR NZ, -7; \
+         LD A, {source}; CALL 0xFF80; JR -2"
+    ));
+    game_boy.run_until_frame();
+
+    // Reads from the bus the transfer occupies return the byte that is being copied
+    assert_eq!(game_boy.peek(0xFF90), expected);
+}
+
+#[test]
+fn test_dma_restart() {
+    // The LCD is off, WRAM at 0xC000 holds the low byte of each address and at 0xC100 the low byte with bit 7 set
+    let mut circuitry =
+        Circuitry::initialize(HardwareModel::DMG, RamInitialization::default(), HardwareVariance::default());
+    circuitry.write(0xFF40, 0x00);
+    for index in 0..0xA0 {
+        circuitry.write(0xC000 + index, index as u8);
+        circuitry.write(0xC100 + index, index as u8 | 0x80);
+    }
+    circuitry.write(0xFE00, 0x55);
+
+    // OAM is still accessible in the M-cycle after starting a transfer
+    circuitry.write(0xFF46, 0xC0);
+    circuitry.tick();
+    assert_eq!(circuitry.read(0xFE00), 0x55);
+    for _ in 0..10 {
+        circuitry.tick();
+    }
+    assert_eq!(circuitry.read(0xFE00), 0xFF);
+
+    // When restarting, the running transfer keeps copying and blocking OAM until the new one begins
+    circuitry.write(0xFF46, 0xC1);
+    circuitry.tick();
+    assert_eq!(circuitry.read(0xFE00), 0xFF);
+    assert_eq!(circuitry.read(0xC000), 0x0A);
+    circuitry.tick();
+    assert_eq!(circuitry.read(0xFE00), 0xFF);
+    assert_eq!(circuitry.read(0xC000), 0x80);
+
+    for _ in 0..160 {
+        circuitry.tick();
+    }
+    assert_eq!(circuitry.read(0xFE00), 0x80);
+    assert_eq!(circuitry.read(0xFE9F), 0x9F | 0x80);
+}
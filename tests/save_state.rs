@@ -0,0 +1,243 @@
+use lemon_gb_core::cartridge::Cartridge;
+use lemon_gb_core::cartridge::clock_source::CycleClock;
+use lemon_gb_core::cartridge::rom_builder::RomBuilder;
+use lemon_gb_core::circuitry::hardware_variance::HardwareVariance;
+use lemon_gb_core::cpu::assembler::assemble;
+use lemon_gb_core::debug::io_register::IoRegister;
+use lemon_gb_core::debug::{DebuggerStatePolicy, MemoryProtection, RegisterWatch, Watchpoint};
+use lemon_gb_core::game_boy::GameBoy;
+use lemon_gb_core::hardware_model::HardwareModel;
+use lemon_gb_core::helpers::crc32::crc32;
+use lemon_gb_core::save_state::{OLDEST_MIGRATABLE_VERSION, SAVE_STATE_VERSION, SaveStateError};
+
+/// Enables cartridge RAM and the timer, then keeps incrementing WRAM and cartridge RAM
+const PROGRAM: &str = "LD A, 0x0A; LD (0x0000), A; LD A, 0x05; LDH (0x07), A; LD HL, 0xC000; \
+                       INC (HL); LD A, (HL); LD (0xA000), A; JR -7";
+
+fn rom(title: &str) -> Vec<u8> {
+    RomBuilder::new()
+        .title(title)
+        .cartridge_type(0x03)
+        .rom_banks(4)
+        .ram_size_code(0x02)
+        .code(&assemble(PROGRAM).unwrap())
+        .build()
+}
+
+fn boot(model: HardwareModel, title: &str) -> GameBoy {
+    let mut game_boy = GameBoy::new(model);
+    game_boy.insert_cartridge(rom(title)).unwrap();
+    game_boy
+}
+
+#[test]
+fn test_save_state_round_trip() {
+    let mut game_boy = boot(HardwareModel::DMG, "STATE");
+    game_boy.run_until_frame();
+    for _ in 0..1234 {
+        game_boy.step();
+    }
+
+    let state = game_boy.save_state();
+    let saved = game_boy.clone();
+    for _ in 0..3 {
+        game_boy.run_until_frame();
+    }
+    let expected = game_boy.clone();
+    assert_ne!(game_boy, saved);
+
+    game_boy.load_state(&state).unwrap();
+    assert_eq!(game_boy, saved);
+    for _ in 0..3 {
+        game_boy.run_until_frame();
+    }
+    assert_eq!(game_boy, expected);
+}
+
+#[test]
+fn test_save_state_loads_into_fresh_game_boy() {
+    let mut game_boy = boot(HardwareModel::DMG, "STATE");
+    game_boy.run_until_frame();
+    let state = game_boy.save_state();
+
+    let mut other = boot(HardwareModel::DMG, "STATE");
+    other.load_state(&state).unwrap();
+    assert_eq!(other, game_boy);
+}
+
+#[test]
+fn test_invalid_save_states_are_rejected() {
+    let mut game_boy = boot(HardwareModel::DMG, "STATE");
+    let state = game_boy.save_state();
+    game_boy.run_until_frame();
+    let unchanged = game_boy.clone();
+
+    let mut invalid_magic = state.clone();
+    invalid_magic[0] = b'X';
+    assert_eq!(game_boy.load_state(&invalid_magic), Err(SaveStateError::InvalidMagic));
+
+    let mut other_version = state.clone();
+    other_version[4..6].copy_from_slice(&(SAVE_STATE_VERSION + 1).to_le_bytes());
+    assert_eq!(
+        game_boy.load_state(&other_version),
+        Err(SaveStateError::UnsupportedVersion(SAVE_STATE_VERSION + 1))
+    );
+
+    assert_eq!(game_boy.load_state(&state[..state.len() - 1]), Err(SaveStateError::UnexpectedEnd));
+
+    let mut trailing = state.clone();
+    trailing.push(0);
+    assert_eq!(game_boy.load_state(&trailing), Err(SaveStateError::InvalidData));
+
+    assert_eq!(game_boy, unchanged);
+}
+
+#[test]
+fn test_save_state_requires_same_setup() {
+    let state = boot(HardwareModel::DMG, "STATE").save_state();
+
+    let mut other_cartridge = boot(HardwareModel::DMG, "OTHER");
+    assert_eq!(
+        other_cartridge.load_state(&state),
+        Err(SaveStateError::RomMismatch {
+            state_crc32: crc32(&rom("STATE")),
+            current_crc32: crc32(&rom("OTHER")),
+        })
+    );
+    other_cartridge.load_state_ignoring_rom(&state).unwrap();
+    assert_eq!(other_cartridge.get_cartridge().unwrap().get_header().get_title(), "OTHER");
+
+    let mut no_cartridge = GameBoy::new(HardwareModel::DMG);
+    assert_eq!(no_cartridge.load_state(&state), Err(SaveStateError::CartridgeMismatch));
+
+    let mut other_model = boot(HardwareModel::MGB, "STATE");
+    assert_eq!(other_model.load_state(&state), Err(SaveStateError::ModelMismatch));
+}
+
+#[test]
+fn test_debugger_is_kept_by_default() {
+    let mut game_boy = boot(HardwareModel::DMG, "STATE");
+    game_boy.get_debugger_mut().add_breakpoint(0x0150);
+    let state = game_boy.save_state();
+
+    game_boy.get_debugger_mut().clear();
+    game_boy.get_debugger_mut().add_breakpoint(0x0160);
+    game_boy.load_state(&state).unwrap();
+    assert_eq!(game_boy.get_debugger().get_breakpoints().iter().copied().collect::<Vec<_>>(), [0x0160]);
+}
+
+#[test]
+fn test_debugger_included_in_state() {
+    let mut game_boy = boot(HardwareModel::DMG, "STATE");
+    game_boy.get_debugger_mut().set_state_policy(DebuggerStatePolicy::Include);
+    game_boy.get_debugger_mut().add_breakpoint(0x0150);
+    game_boy.get_debugger_mut().add_watchpoint(Watchpoint::write(0xC000));
+    game_boy.get_debugger_mut().add_register_watch(RegisterWatch::any(IoRegister::LCDC));
+    game_boy.get_debugger_mut().add_protection(MemoryProtection::trap(0xC100));
+    let expected = game_boy.get_debugger().clone();
+    let state = game_boy.save_state();
+
+    game_boy.get_debugger_mut().clear();
+    game_boy.get_debugger_mut().add_breakpoint(0x0160);
+    game_boy.load_state(&state).unwrap();
+    assert_eq!(game_boy.get_debugger(), &expected);
+
+    // Instances keeping their own configuration skip the included one
+    let mut other = boot(HardwareModel::DMG, "STATE");
+    other.load_state(&state).unwrap();
+    assert!(other.get_debugger().get_breakpoints().is_empty());
+    assert_eq!(other.get_cpu_snapshot(), game_boy.get_cpu_snapshot());
+}
+
+#[test]
+fn test_state_migration() {
+    let mut game_boy = GameBoy::new(HardwareModel::DMG);
+    game_boy.run_until_frame();
+    let state = game_boy.save_state();
+    let current = game_boy.migrate_state(&state).unwrap();
+    assert_eq!(current.from_version, SAVE_STATE_VERSION);
+    assert!(current.defaulted.is_empty());
+    assert_eq!(current.state, state);
+
+    // Version 11 didn't have the restarted OAM DMA transfer and the bus noise generator,
+    // which come last before the debugger flag without a cartridge
+    let mut old = state[..state.len() - 13].to_vec();
+    old.push(*state.last().unwrap());
+    old[4..6].copy_from_slice(&11u16.to_le_bytes());
+    assert_eq!(game_boy.load_state(&old), Err(SaveStateError::UnsupportedVersion(11)));
+    let migration = game_boy.migrate_state(&old).unwrap();
+    assert_eq!(migration.from_version, 11);
+    assert_eq!(migration.defaulted, ["restarted OAM DMA transfer", "hardware variance of the bus"]);
+    assert_eq!(migration.state, state);
+
+    let mut too_old = state.clone();
+    too_old[4..6].copy_from_slice(&(OLDEST_MIGRATABLE_VERSION - 1).to_le_bytes());
+    assert_eq!(
+        game_boy.migrate_state(&too_old),
+        Err(SaveStateError::UnsupportedVersion(OLDEST_MIGRATABLE_VERSION - 1))
+    );
+}
+
+#[test]
+fn test_state_keeps_hardware_variance() {
+    // Without a cartridge the CPU executes whatever the empty slot reads as
+    let seeded = || GameBoy::builder().hardware_variance(HardwareVariance::Seeded { seed: 1 }).build();
+    let mut game_boy = seeded();
+    game_boy.run_until_frame();
+    let state = game_boy.save_state();
+    game_boy.run_until_frame();
+    let expected = game_boy.clone();
+
+    // Seeded states continue the same noise
+    let mut other = seeded();
+    other.load_state(&state).unwrap();
+    other.run_until_frame();
+    assert_eq!(other, expected);
+
+    // Deterministic states don't disable the configured variance
+    let mut deterministic = GameBoy::builder().build();
+    let state = deterministic.save_state();
+    let mut other = seeded();
+    other.load_state(&state).unwrap();
+    deterministic.run_until_frame();
+    other.run_until_frame();
+    assert_ne!(other.get_cpu_snapshot(), deterministic.get_cpu_snapshot());
+}
+
+#[test]
+fn test_cycle_clock_state() {
+    // An MBC3 cartridge with an RTC whose time is derived from the emulated cycles
+    let cycle_clock_game_boy = || {
+        let rom = RomBuilder::new().cartridge_type(0x10).ram_size_code(0x03).code(&assemble("JR -2").unwrap()).build();
+        let mut game_boy = GameBoy::new(HardwareModel::DMG);
+        game_boy.set_cartridge(Cartridge::load_with_clock_source(rom, Box::new(CycleClock::new())).unwrap());
+        game_boy
+    };
+    let rtc_registers = |game_boy: &mut GameBoy| {
+        game_boy.get_cartridge_mut().unwrap().get_rtc_mut().unwrap().get_current_registers()
+    };
+
+    // Saved about half a second in, each copy runs on for about two thirds of a second
+    let mut game_boy = cycle_clock_game_boy();
+    for _ in 0..30 {
+        game_boy.run_until_frame();
+    }
+    let state = game_boy.save_state();
+    for _ in 0..40 {
+        game_boy.run_until_frame();
+    }
+    let expected = rtc_registers(&mut game_boy);
+    assert_eq!(expected[0], 1);
+
+    let mut fresh = cycle_clock_game_boy();
+    fresh.load_state(&state).unwrap();
+    // Rewinding resets the clock that already ran on
+    game_boy.load_state(&state).unwrap();
+    for _ in 0..40 {
+        fresh.run_until_frame();
+        game_boy.run_until_frame();
+    }
+    assert_eq!(rtc_registers(&mut fresh), expected);
+    assert_eq!(rtc_registers(&mut game_boy), expected);
+    assert_eq!(fresh, game_boy);
+}